@@ -0,0 +1,15 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="sample" tilewidth="32" tileheight="32" tilecount="4" columns="2">
+ <!-- 草地与石墙：color 为 #AARRGGBB，solid 图块不可通行 -->
+ <tile id="0">
+  <properties>
+   <property name="color" type="color" value="#ff3a5f3a"/>
+  </properties>
+ </tile>
+ <tile id="1">
+  <properties>
+   <property name="color" type="color" value="#ff5a5a64"/>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+</tileset>
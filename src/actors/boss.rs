@@ -0,0 +1,378 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::actors::projectile::Projectile;
+use crate::actors::stats::{Stat, StatBlock, Stats};
+use crate::assets::load_json;
+use crate::world::collision::move_and_slide;
+use crate::world::map::TileGrid;
+
+// 受击闪白时长（秒）
+const HURT_FLASH: f32 = 0.12;
+
+// 首领图鉴：按名称索引的首领定义（data/bosses.json）
+#[derive(Clone, Debug, Default)]
+pub struct BossBook {
+    defs: HashMap<String, BossDef>,
+}
+
+impl BossBook {
+    // 从JSON文件加载首领定义
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            defs: load_json(path)?,
+        })
+    }
+
+    // 按名称获取首领定义
+    pub fn get(&self, name: &str) -> Option<&BossDef> {
+        self.defs.get(name)
+    }
+
+    // 引用了掉落表的首领：(名称, 掉落表 id)
+    pub fn loot_refs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defs
+            .iter()
+            .filter_map(|(name, def)| Some((name.as_str(), def.loot.as_deref()?)))
+    }
+}
+
+// 首领定义：血量、外观、阶段与掉落
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossDef {
+    pub hp: i32,
+    pub sprite: String,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    pub radius: f32,
+    #[serde(default)]
+    pub contact_damage: i32,
+    #[serde(default)]
+    pub defense: i32,
+    pub phases: Vec<PhaseDef>,
+    // 掉落表 id（data/loot.json）
+    #[serde(default)]
+    pub loot: Option<String>,
+    // 击败后获得的修为
+    #[serde(default)]
+    pub exp: u32,
+}
+
+impl BossDef {
+    // 基础属性：气血、攻击（接触伤害）与防御
+    pub fn stats(&self) -> StatBlock {
+        StatBlock {
+            hp: self.hp,
+            attack: self.contact_damage,
+            defense: self.defense,
+            ..StatBlock::default()
+        }
+    }
+}
+
+// 默认精灵放大倍数
+fn default_scale() -> f32 {
+    3.0
+}
+
+// 战斗阶段：血量比例低于 hp_below 时进入，按顺序轮换攻击模式
+#[derive(Clone, Debug, Deserialize)]
+pub struct PhaseDef {
+    pub hp_below: f32,
+    #[serde(default)]
+    pub move_speed: f32,
+    pub patterns: Vec<AttackPattern>,
+    #[serde(default)]
+    pub shout: Option<String>,
+}
+
+// 攻击模式
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttackPattern {
+    // 环形弹幕
+    Ring {
+        count: u32,
+        speed: f32,
+        damage: i32,
+        cooldown: f32,
+    },
+    // 朝玩家的扇形弹幕，spread 为总张角（度）
+    Aimed {
+        count: u32,
+        spread: f32,
+        speed: f32,
+        damage: i32,
+        cooldown: f32,
+    },
+    // 朝玩家冲撞
+    Charge {
+        speed: f32,
+        duration: f32,
+        damage: i32,
+        cooldown: f32,
+    },
+}
+
+impl AttackPattern {
+    // 该模式释放后到下一次攻击的间隔
+    fn cooldown(&self) -> f32 {
+        match self {
+            AttackPattern::Ring { cooldown, .. }
+            | AttackPattern::Aimed { cooldown, .. }
+            | AttackPattern::Charge { cooldown, .. } => *cooldown,
+        }
+    }
+}
+
+// 冲撞状态：方向、速度、剩余时间与伤害
+#[derive(Clone, Copy, Debug)]
+struct Charge {
+    dir: Vec2,
+    speed: f32,
+    remaining: f32,
+    damage: i32,
+}
+
+// 首领运行时状态
+#[derive(Clone, Debug)]
+pub struct Boss {
+    pub name: String,
+    pub def: BossDef,
+    pub pos: Vec2,
+    pub stats: Stats,
+    pub hp: i32,
+    phase: usize,
+    next_pattern: usize,
+    attack_timer: f32,
+    charge: Option<Charge>,
+    hurt_timer: f32,
+}
+
+impl Boss {
+    // 在指定位置生成首领
+    pub fn spawn(name: &str, def: &BossDef, pos: Vec2) -> Self {
+        Self {
+            name: name.to_string(),
+            def: def.clone(),
+            pos,
+            stats: Stats::new(def.stats()),
+            hp: def.hp,
+            phase: 0,
+            next_pattern: 0,
+            attack_timer: 1.5,
+            charge: None,
+            hurt_timer: 0.0,
+        }
+    }
+
+    // 剩余血量比例
+    pub fn hp_ratio(&self) -> f32 {
+        let max = self.stats.get(Stat::Hp).max(1);
+        (self.hp as f32 / max as f32).clamp(0.0, 1.0)
+    }
+
+    // 是否已被击败
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0
+    }
+
+    // 是否处于受击闪白
+    pub fn is_hurt(&self) -> bool {
+        self.hurt_timer > 0.0
+    }
+
+    // 当前接触伤害（冲撞时使用冲撞伤害）
+    pub fn contact_damage(&self) -> i32 {
+        match self.charge {
+            Some(charge) => charge.damage,
+            None => self.stats.get(Stat::Attack),
+        }
+    }
+
+    // 承受伤害，进入新阶段时返回该阶段的喊话
+    pub fn take_damage(&mut self, damage: i32) -> Option<String> {
+        self.hp -= self.stats.mitigate(damage);
+        self.hurt_timer = HURT_FLASH;
+        let ratio = self.hp_ratio();
+        // 取满足血量条件的最后一个阶段（阶段按 hp_below 从高到低书写）
+        let phase = self
+            .def
+            .phases
+            .iter()
+            .rposition(|p| ratio <= p.hp_below)
+            .unwrap_or(0);
+        if phase == self.phase {
+            return None;
+        }
+        self.phase = phase;
+        self.next_pattern = 0;
+        self.def.phases[phase].shout.clone()
+    }
+
+    // 推进首领行为：移动、冲撞与释放弹幕
+    pub fn update(
+        &mut self,
+        dt: f32,
+        target: Vec2,
+        grid: Option<&TileGrid>,
+        out: &mut Vec<Projectile>,
+    ) {
+        self.hurt_timer = (self.hurt_timer - dt).max(0.0);
+        self.stats.update(dt);
+        let Some(phase) = self.def.phases.get(self.phase) else {
+            return;
+        };
+        let to_target = (target - self.pos).normalize_or_zero();
+
+        // 冲撞中只沿直线移动
+        let delta = if let Some(charge) = &mut self.charge {
+            charge.remaining -= dt;
+            let delta = charge.dir * charge.speed * dt;
+            if charge.remaining <= 0.0 {
+                self.charge = None;
+            }
+            delta
+        } else if self.pos.distance(target) > 140.0 {
+            to_target * phase.move_speed * dt
+        } else {
+            Vec2::ZERO
+        };
+        self.pos = match grid {
+            Some(grid) => move_and_slide(grid, self.pos, delta, self.def.radius * 0.6),
+            None => self.pos + delta,
+        };
+
+        self.attack_timer -= dt;
+        if self.attack_timer > 0.0 || phase.patterns.is_empty() {
+            return;
+        }
+        let pattern = phase.patterns[self.next_pattern % phase.patterns.len()].clone();
+        self.next_pattern += 1;
+        self.attack_timer = pattern.cooldown();
+        match pattern {
+            AttackPattern::Ring {
+                count,
+                speed,
+                damage,
+                ..
+            } => {
+                let offset = rand::gen_range(0.0, std::f32::consts::TAU);
+                for i in 0..count {
+                    let angle = offset + i as f32 * std::f32::consts::TAU / count.max(1) as f32;
+                    let dir = vec2(angle.cos(), angle.sin());
+                    out.push(Projectile::new(self.pos, dir * speed, damage));
+                }
+            }
+            AttackPattern::Aimed {
+                count,
+                spread,
+                speed,
+                damage,
+                ..
+            } => {
+                let base = to_target.y.atan2(to_target.x);
+                let spread = spread.to_radians();
+                for i in 0..count {
+                    let t = if count > 1 {
+                        i as f32 / (count - 1) as f32 - 0.5
+                    } else {
+                        0.0
+                    };
+                    let angle = base + t * spread;
+                    let dir = vec2(angle.cos(), angle.sin());
+                    out.push(Projectile::new(self.pos, dir * speed, damage));
+                }
+            }
+            AttackPattern::Charge {
+                speed,
+                duration,
+                damage,
+                ..
+            } => {
+                self.charge = Some(Charge {
+                    dir: to_target,
+                    speed,
+                    remaining: duration,
+                    damage,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // 百血首领：满血时单发，六成血以下三连发并怒吼，三成血以下冲撞
+    fn boss() -> Boss {
+        let aimed = |count: u32| json!({ "type": "aimed", "count": count, "spread": 30.0, "speed": 100.0, "damage": 1, "cooldown": 1.0 });
+        let def: BossDef = serde_json::from_value(json!({
+            "hp": 100,
+            "sprite": "boss",
+            "radius": 20.0,
+            "phases": [
+                { "hp_below": 1.0, "patterns": [aimed(1), aimed(2)] },
+                { "hp_below": 0.6, "patterns": [aimed(3)], "shout": "怒" },
+                {
+                    "hp_below": 0.3,
+                    "patterns": [{ "type": "charge", "speed": 200.0, "duration": 0.5, "damage": 5, "cooldown": 2.0 }],
+                    "shout": "狂"
+                }
+            ]
+        }))
+        .unwrap();
+        Boss::spawn("test", &def, vec2(100.0, 100.0))
+    }
+
+    // 推进一帧，返回本帧射出的弹幕数
+    fn fire(boss: &mut Boss, dt: f32) -> usize {
+        let mut out = Vec::new();
+        boss.update(dt, vec2(150.0, 100.0), None, &mut out);
+        out.len()
+    }
+
+    #[test]
+    fn phases_switch_at_hp_thresholds_and_shout_once() {
+        let mut boss = boss();
+        assert_eq!(boss.take_damage(10), None);
+        assert_eq!(boss.phase, 0);
+        assert_eq!(boss.take_damage(25), None);
+        // 血量比例恰好等于阈值时进入该阶段
+        assert_eq!(boss.take_damage(5).as_deref(), Some("怒"));
+        assert_eq!(boss.phase, 1);
+        assert_eq!(boss.take_damage(5), None);
+        assert_eq!(boss.take_damage(30).as_deref(), Some("狂"));
+        assert_eq!(boss.phase, 2);
+        assert!(!boss.is_dead());
+        boss.take_damage(25);
+        assert!(boss.is_dead());
+    }
+
+    #[test]
+    fn a_heavy_hit_skips_straight_to_the_lowest_phase() {
+        let mut boss = boss();
+        assert_eq!(boss.take_damage(80).as_deref(), Some("狂"));
+        assert_eq!(boss.phase, 2);
+    }
+
+    #[test]
+    fn patterns_rotate_and_restart_in_a_new_phase() {
+        let mut boss = boss();
+        assert_eq!(fire(&mut boss, 1.0), 0);
+        assert_eq!(fire(&mut boss, 0.5), 1);
+        assert_eq!(fire(&mut boss, 1.0), 2);
+        assert_eq!(fire(&mut boss, 1.0), 1);
+        boss.take_damage(50);
+        assert_eq!(fire(&mut boss, 1.0), 3);
+
+        boss.take_damage(30);
+        assert_eq!(fire(&mut boss, 1.0), 0);
+        assert_eq!(boss.contact_damage(), 5);
+    }
+}
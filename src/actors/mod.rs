@@ -1 +1,5 @@
-﻿pub mod player;
+﻿pub mod boss;
+pub mod monster;
+pub mod player;
+pub mod projectile;
+pub mod stats;
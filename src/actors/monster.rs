@@ -0,0 +1,200 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::actors::stats::{Stat, StatBlock, Stats};
+use crate::assets::load_json;
+use crate::world::area::Area;
+use crate::world::collision::move_and_slide;
+use crate::world::map::TileGrid;
+
+// 受击闪白时长（秒）
+const HURT_FLASH: f32 = 0.12;
+// 游荡时的速度比例
+const WANDER_SPEED: f32 = 0.5;
+
+// 妖兽图鉴：按 id 索引的野外怪物定义（data/monsters.json）
+#[derive(Clone, Debug, Default)]
+pub struct MonsterBook {
+    defs: HashMap<String, MonsterDef>,
+}
+
+impl MonsterBook {
+    // 从JSON文件加载妖兽定义
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            defs: load_json(path)?,
+        })
+    }
+
+    // 按 id 获取妖兽定义
+    pub fn get(&self, id: &str) -> Option<&MonsterDef> {
+        self.defs.get(id)
+    }
+
+    // 引用了掉落表的妖兽：(id, 掉落表 id)
+    pub fn loot_refs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defs
+            .iter()
+            .filter_map(|(id, def)| Some((id.as_str(), def.loot.as_deref()?)))
+    }
+}
+
+// 妖兽定义：名称、血量、外观、移动与掉落
+#[derive(Clone, Debug, Deserialize)]
+pub struct MonsterDef {
+    pub name: String,
+    pub hp: i32,
+    pub sprite: String,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    pub radius: f32,
+    pub speed: f32,
+    #[serde(default)]
+    pub contact_damage: i32,
+    #[serde(default)]
+    pub defense: i32,
+    #[serde(default)]
+    pub behavior: Behavior,
+    // 发现玩家的距离（仅追击型）
+    #[serde(default = "default_sight")]
+    pub sight: f32,
+    // 玩家离出生区域中心超过此距离时放弃追击
+    #[serde(default = "default_leash")]
+    pub leash: f32,
+    // 掉落表 id（data/loot.json）
+    #[serde(default)]
+    pub loot: Option<String>,
+    // 击败后获得的修为
+    #[serde(default)]
+    pub exp: u32,
+}
+
+impl MonsterDef {
+    // 基础属性：气血、攻击（接触伤害）与防御
+    pub fn stats(&self) -> StatBlock {
+        StatBlock {
+            hp: self.hp,
+            attack: self.contact_damage,
+            defense: self.defense,
+            ..StatBlock::default()
+        }
+    }
+}
+
+// 默认精灵放大倍数
+fn default_scale() -> f32 {
+    2.5
+}
+
+// 默认发现距离
+fn default_sight() -> f32 {
+    160.0
+}
+
+// 默认追击范围
+fn default_leash() -> f32 {
+    320.0
+}
+
+// 行为模式：追击型发现玩家后追赶，巡逻型只在区域内游荡
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+    #[default]
+    Chase,
+    Patrol,
+}
+
+// 野外妖兽运行时状态
+#[derive(Clone, Debug)]
+pub struct Monster {
+    pub def: MonsterDef,
+    pub pos: Vec2,
+    pub stats: Stats,
+    pub hp: i32,
+    // 所属刷怪区域在地图中的序号（剧情生成的妖兽不属于任何区域）
+    pub zone: Option<usize>,
+    // 活动范围：游荡点在其中选取，追击以其中心为基准
+    territory: Area,
+    wander_target: Vec2,
+    wander_timer: f32,
+    hurt_timer: f32,
+}
+
+impl Monster {
+    // 在指定位置生成妖兽
+    pub fn spawn(def: &MonsterDef, pos: Vec2, zone: Option<usize>, territory: Area) -> Self {
+        Self {
+            def: def.clone(),
+            pos,
+            stats: Stats::new(def.stats()),
+            hp: def.hp,
+            zone,
+            territory,
+            wander_target: pos,
+            wander_timer: rand::gen_range(0.5, 2.0),
+            hurt_timer: 0.0,
+        }
+    }
+
+    // 剩余血量比例
+    pub fn hp_ratio(&self) -> f32 {
+        let max = self.stats.get(Stat::Hp).max(1);
+        (self.hp as f32 / max as f32).clamp(0.0, 1.0)
+    }
+
+    // 是否已被击败
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0
+    }
+
+    // 是否处于受击闪白
+    pub fn is_hurt(&self) -> bool {
+        self.hurt_timer > 0.0
+    }
+
+    // 接触伤害
+    pub fn contact_damage(&self) -> i32 {
+        self.stats.get(Stat::Attack)
+    }
+
+    // 承受伤害（按防御减免）
+    pub fn take_damage(&mut self, damage: i32) {
+        self.hp -= self.stats.mitigate(damage);
+        self.hurt_timer = HURT_FLASH;
+    }
+
+    // 推进妖兽行为：发现玩家则追击，否则在活动范围内游荡
+    pub fn update(&mut self, dt: f32, target: Vec2, grid: Option<&TileGrid>) {
+        self.hurt_timer = (self.hurt_timer - dt).max(0.0);
+        self.stats.update(dt);
+        let area = &self.territory;
+        let home = area.center();
+        let chasing = self.def.behavior == Behavior::Chase
+            && self.pos.distance(target) <= self.def.sight
+            && target.distance(home) <= self.def.leash;
+
+        let delta = if chasing {
+            (target - self.pos).normalize_or_zero() * self.def.speed * dt
+        } else {
+            // 追丢或到达目标后停顿片刻，再在区域内挑选新的游荡点
+            self.wander_timer -= dt;
+            if !area.contains(self.wander_target) || self.wander_timer <= 0.0 {
+                self.wander_target = area.random_point();
+                self.wander_timer = rand::gen_range(2.0, 4.0);
+            }
+            let offset = self.wander_target - self.pos;
+            if offset.length() < 4.0 {
+                Vec2::ZERO
+            } else {
+                offset.normalize() * self.def.speed * WANDER_SPEED * dt
+            }
+        };
+        self.pos = match grid {
+            Some(grid) => move_and_slide(grid, self.pos, delta, self.def.radius * 0.6),
+            None => self.pos + delta,
+        };
+    }
+}
@@ -1,18 +1,107 @@
 ﻿use macroquad::prelude::*;
 
-// 玩家实体：位置与移动速度
-#[derive(Clone, Copy, Debug)]
+use crate::actors::stats::{Stat, StatBlock, Stats};
+use crate::items::equipment::{Aura, Equipment};
+use crate::items::ItemCatalog;
+
+// 炼气期·初期的基础属性与每提升一重境界的成长
+const BASE_STATS: StatBlock = StatBlock {
+    hp: 100,
+    mana: 50,
+    attack: 10,
+    defense: 0,
+    agility: 20,
+    perception: 10,
+};
+const REALM_GROWTH: StatBlock = StatBlock {
+    hp: 20,
+    mana: 15,
+    attack: 3,
+    defense: 2,
+    agility: 1,
+    perception: 1,
+};
+
+// 装备修正在属性中的来源 id
+const EQUIPMENT_SOURCE: &str = "装备";
+
+// 玩家实体：位置、碰撞半径、属性、当前气血/灵力与法宝灵光
+#[derive(Clone, Debug)]
 pub struct Player {
     pub pos: Vec2,
-    pub speed: f32,
+    pub radius: f32,
+    pub stats: Stats,
+    pub hp: i32,
+    pub mana: i32,
+    pub aura: Option<Aura>,
 }
 
 impl Player {
-    // 创建玩家
+    // 创建炼气期·初期、满气血与灵力的玩家
     pub fn new(spawn: Vec2) -> Self {
+        let stats = Stats::new(BASE_STATS);
         Self {
             pos: spawn,
-            speed: 180.0,
+            radius: 10.0,
+            hp: stats.get(Stat::Hp),
+            mana: stats.get(Stat::Mana),
+            stats,
+            aura: None,
         }
     }
+
+    // 气血上限
+    pub fn max_hp(&self) -> i32 {
+        self.stats.get(Stat::Hp).max(1)
+    }
+
+    // 灵力上限
+    pub fn max_mana(&self) -> i32 {
+        self.stats.get(Stat::Mana)
+    }
+
+    // 移动速度（由身法推算）
+    pub fn speed(&self) -> f32 {
+        self.stats.move_speed()
+    }
+
+    // 回满气血与灵力
+    pub fn restore(&mut self) {
+        self.hp = self.max_hp();
+        self.mana = self.max_mana();
+    }
+
+    // 按境界更新基础属性，当前气血/灵力不超过新的上限
+    pub fn sync_realm(&mut self, realm: usize) {
+        self.stats.base = realm_stats(realm);
+        self.hp = self.hp.min(self.max_hp());
+        self.mana = self.mana.min(self.max_mana());
+    }
+
+    // 按已装备的物品与套装更新属性修正和法宝灵光，当前气血/灵力不超过新的上限
+    pub fn sync_equipment(&mut self, equipment: &Equipment, catalog: &ItemCatalog) {
+        self.stats
+            .set_modifiers(EQUIPMENT_SOURCE, equipment.modifiers(catalog));
+        self.aura = equipment.aura(catalog);
+        self.hp = self.hp.min(self.max_hp());
+        self.mana = self.mana.min(self.max_mana());
+    }
+
+    // 承受伤害（按防御减免）
+    pub fn take_damage(&mut self, damage: i32) {
+        self.hp -= self.stats.mitigate(damage);
+    }
+}
+
+// 指定境界的基础属性
+fn realm_stats(realm: usize) -> StatBlock {
+    let n = realm as i32;
+    StatBlock {
+        hp: BASE_STATS.hp + REALM_GROWTH.hp * n,
+        mana: BASE_STATS.mana + REALM_GROWTH.mana * n,
+        attack: BASE_STATS.attack + REALM_GROWTH.attack * n,
+        defense: BASE_STATS.defense + REALM_GROWTH.defense * n,
+        agility: BASE_STATS.agility + REALM_GROWTH.agility * n,
+        perception: BASE_STATS.perception + REALM_GROWTH.perception * n,
+    }
 }
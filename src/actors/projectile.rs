@@ -0,0 +1,33 @@
+﻿use macroquad::prelude::*;
+
+// 弹幕最长存活时间（秒）
+const PROJECTILE_LIFE: f32 = 4.0;
+
+// 敌方弹幕：位置、速度、半径与伤害
+#[derive(Clone, Copy, Debug)]
+pub struct Projectile {
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub radius: f32,
+    pub damage: i32,
+    pub life: f32,
+}
+
+impl Projectile {
+    // 创建弹幕
+    pub fn new(pos: Vec2, vel: Vec2, damage: i32) -> Self {
+        Self {
+            pos,
+            vel,
+            radius: 5.0,
+            damage,
+            life: PROJECTILE_LIFE,
+        }
+    }
+
+    // 推进位置与寿命
+    pub fn update(&mut self, dt: f32) {
+        self.pos += self.vel * dt;
+        self.life -= dt;
+    }
+}
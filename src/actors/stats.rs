@@ -0,0 +1,245 @@
+﻿use serde::Deserialize;
+
+// 移动速度 = 基础速度 + 身法 × 每点身法的加速
+const BASE_SPEED: f32 = 120.0;
+const SPEED_PER_AGILITY: f32 = 3.0;
+
+// 会心：每点神识提供的会心率、会心率上限与会心伤害倍率
+const CRIT_PER_PERCEPTION: f32 = 0.005;
+const CRIT_CAP: f32 = 0.5;
+pub const CRIT_MULTIPLIER: f32 = 1.5;
+
+// 防御减伤常数：实际伤害 = 伤害 × K / (K + 防御)
+const DEFENSE_K: f32 = 50.0;
+
+// 属性种类
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    Hp,
+    Mana,
+    Attack,
+    Defense,
+    Agility,
+    Perception,
+}
+
+impl Stat {
+    // 全部属性（面板显示顺序）
+    pub const ALL: [Stat; 6] = [
+        Stat::Hp,
+        Stat::Mana,
+        Stat::Attack,
+        Stat::Defense,
+        Stat::Agility,
+        Stat::Perception,
+    ];
+
+    // 属性名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Stat::Hp => "气血",
+            Stat::Mana => "灵力",
+            Stat::Attack => "攻击",
+            Stat::Defense => "防御",
+            Stat::Agility => "身法",
+            Stat::Perception => "神识",
+        }
+    }
+}
+
+// 属性块：气血上限、灵力上限、攻击、防御、身法、神识
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StatBlock {
+    pub hp: i32,
+    pub mana: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub agility: i32,
+    pub perception: i32,
+}
+
+impl StatBlock {
+    // 读取指定属性
+    pub fn get(&self, stat: Stat) -> i32 {
+        match stat {
+            Stat::Hp => self.hp,
+            Stat::Mana => self.mana,
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::Agility => self.agility,
+            Stat::Perception => self.perception,
+        }
+    }
+}
+
+// 属性修正：固定加值与百分比加成（0.2 即 +20%），同一属性先加固定值再乘百分比
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Modifier {
+    pub stat: Stat,
+    #[serde(default)]
+    pub flat: i32,
+    #[serde(default)]
+    pub percent: f32,
+}
+
+// 一组修正的来源：装备、增益等；remaining 为剩余秒数，None 表示常驻
+#[derive(Clone, Debug)]
+struct ModifierSource {
+    id: String,
+    modifiers: Vec<Modifier>,
+    remaining: Option<f32>,
+}
+
+// 角色属性：基础值与各来源的修正，玩家、妖兽与首领共用
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub base: StatBlock,
+    sources: Vec<ModifierSource>,
+}
+
+impl Stats {
+    // 以基础属性创建
+    pub fn new(base: StatBlock) -> Self {
+        Self {
+            base,
+            sources: Vec::new(),
+        }
+    }
+
+    // 添加限时增益（同 id 的增益刷新而不叠加）
+    pub fn add_buff(&mut self, id: &str, modifiers: Vec<Modifier>, duration: f32) {
+        self.sources.retain(|s| s.id != id);
+        self.sources.push(ModifierSource {
+            id: id.to_string(),
+            modifiers,
+            remaining: Some(duration),
+        });
+    }
+
+    // 设置常驻修正（装备等），同 id 的修正整体替换
+    pub fn set_modifiers(&mut self, id: &str, modifiers: Vec<Modifier>) {
+        self.sources.retain(|s| s.id != id);
+        if !modifiers.is_empty() {
+            self.sources.push(ModifierSource {
+                id: id.to_string(),
+                modifiers,
+                remaining: None,
+            });
+        }
+    }
+
+    // 推进增益计时，移除到期的增益
+    pub fn update(&mut self, dt: f32) {
+        for source in &mut self.sources {
+            if let Some(remaining) = &mut source.remaining {
+                *remaining -= dt;
+            }
+        }
+        self.sources.retain(|s| s.remaining.is_none_or(|r| r > 0.0));
+    }
+
+    // 生效中的限时增益：(id, 剩余秒数)
+    pub fn buffs(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.sources
+            .iter()
+            .filter_map(|s| Some((s.id.as_str(), s.remaining?)))
+    }
+
+    // 计入全部修正后的属性值（不低于 0）
+    pub fn get(&self, stat: Stat) -> i32 {
+        let mods = self
+            .sources
+            .iter()
+            .flat_map(|s| &s.modifiers)
+            .filter(|m| m.stat == stat);
+        let (flat, percent) = mods.fold((0, 0.0), |(f, p), m| (f + m.flat, p + m.percent));
+        let value = (self.base.get(stat) + flat) as f32 * (1.0 + percent);
+        value.round().max(0.0) as i32
+    }
+
+    // 移动速度（由身法推算）
+    pub fn move_speed(&self) -> f32 {
+        BASE_SPEED + self.get(Stat::Agility) as f32 * SPEED_PER_AGILITY
+    }
+
+    // 会心率（由神识推算）
+    pub fn crit_chance(&self) -> f32 {
+        (self.get(Stat::Perception) as f32 * CRIT_PER_PERCEPTION).min(CRIT_CAP)
+    }
+
+    // 按防御减免后实际承受的伤害（至少 1 点）
+    pub fn mitigate(&self, damage: i32) -> i32 {
+        if damage <= 0 {
+            return 0;
+        }
+        let defense = self.get(Stat::Defense).max(0) as f32;
+        ((damage as f32 * DEFENSE_K / (DEFENSE_K + defense)).round() as i32).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::Vec2;
+
+    use super::*;
+    use crate::actors::player::Player;
+
+    // 修正
+    fn modifier(stat: Stat, flat: i32, percent: f32) -> Modifier {
+        Modifier {
+            stat,
+            flat,
+            percent,
+        }
+    }
+
+    #[test]
+    fn flat_modifiers_apply_before_percentages() {
+        let mut stats = Stats::new(StatBlock {
+            attack: 10,
+            ..StatBlock::default()
+        });
+        stats.set_modifiers("剑", vec![modifier(Stat::Attack, 0, 0.5)]);
+        stats.set_modifiers("戒", vec![modifier(Stat::Attack, 10, 0.0)]);
+        // (10 + 10) × 1.5，而不是 10 × 1.5 + 10
+        assert_eq!(stats.get(Stat::Attack), 30);
+        stats.add_buff("丹", vec![modifier(Stat::Attack, 2, 0.5)], 5.0);
+        // 百分比相加而不是相乘：(10 + 10 + 2) × (1 + 0.5 + 0.5)
+        assert_eq!(stats.get(Stat::Attack), 44);
+        stats.update(5.0);
+        assert_eq!(stats.get(Stat::Attack), 30);
+        stats.set_modifiers("剑", Vec::new());
+        assert_eq!(stats.get(Stat::Attack), 20);
+        assert_eq!(stats.get(Stat::Defense), 0);
+    }
+
+    #[test]
+    fn negative_modifiers_floor_at_zero() {
+        let mut stats = Stats::default();
+        stats.add_buff("破甲", vec![modifier(Stat::Defense, -30, 0.0)], 1.0);
+        assert_eq!(stats.get(Stat::Defense), 0);
+    }
+
+    #[test]
+    fn mitigation_scales_with_defense_and_floors_at_one() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.mitigate(20), 20);
+        assert_eq!(stats.mitigate(0), 0);
+        assert_eq!(stats.mitigate(-5), 0);
+        stats.base.defense = 50;
+        assert_eq!(stats.mitigate(20), 10);
+        stats.base.defense = 10_000;
+        assert_eq!(stats.mitigate(20), 1);
+    }
+
+    #[test]
+    fn base_player_keeps_the_original_move_speed() {
+        assert_eq!(Player::new(Vec2::ZERO).speed(), 180.0);
+        let mut stats = Stats::default();
+        assert_eq!(stats.move_speed(), 120.0);
+        stats.base.agility = 10;
+        assert_eq!(stats.move_speed(), 150.0);
+    }
+}
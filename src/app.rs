@@ -1,24 +1,173 @@
-﻿use macroquad::prelude::*;
+﻿use std::path::Path;
+use std::rc::Rc;
 
+use macroquad::prelude::*;
+
+use crate::actors::boss::BossBook;
+use crate::actors::monster::MonsterBook;
 use crate::actors::player::Player;
 use crate::assets::load_ui_font;
-use crate::config::{INTERNAL_RENDER_SCALE, SCREEN_H, SCREEN_W};
-use crate::render::{draw_hud, draw_map, draw_player, draw_portals};
+use crate::config::{
+    BOSSES_PATH, DIALOGUES_PATH, INTERNAL_RENDER_SCALE, ITEMS_PATH, LOOT_PATH, MAPS_PATH,
+    MONSTERS_PATH, SCREEN_H, SCREEN_W, SETS_PATH, SPRITES_PATH,
+};
+use crate::items::loot::LootBook;
+use crate::items::ItemCatalog;
+use crate::render::background::BackgroundTextures;
+use crate::render::camera::FollowCamera;
+use crate::render::editor::{draw_editor_overlay, draw_editor_panel};
+use crate::render::inventory::draw_inventory;
+use crate::render::minimap::draw_minimap;
+use crate::render::transition::draw_transition;
+use crate::render::weather::{draw_day_tint, draw_weather};
+use crate::render::world_map::draw_world_map;
+use crate::render::{
+    draw_bosses, draw_dialogue, draw_error_banner, draw_hud, draw_interact_prompt, draw_map,
+    draw_map_banner, draw_monsters, draw_npcs, draw_player, draw_portal_prompt, draw_portals,
+    draw_projectiles, draw_props, draw_stat_panel,
+};
+use crate::save::SaveStore;
+use crate::sprite::SpriteLibrary;
+use crate::systems::combat::{update_combat, Combat};
+use crate::systems::cultivation::{update_cultivation, Meditation};
+use crate::systems::dialogue::{update_dialogue, DialogueState};
+use crate::systems::editor::{update_editor, EditorState};
+use crate::systems::inventory::{update_inventory, InventoryScreen};
+use crate::systems::portal::{
+    update_portal_prompt, update_portal_touch, use_portal, PortalPrompt, PortalTouch,
+};
+use crate::systems::transition::{Transition, TransitionStep};
+use crate::systems::trigger::{run_trigger_actions, update_triggers, TriggerContext, TriggerState};
+use crate::systems::weather::{update_weather, WeatherState};
+use crate::systems::world_map::{update_world_map, WorldMapAction, WorldMapScreen};
 use crate::systems::{handle_interaction, handle_movement};
-use crate::ui::Ui;
+use crate::ui::{MapBanner, Notice, Ui};
+use crate::world::collision::nearest_open;
+use crate::world::dialogue::DialogueBook;
+use crate::world::fog::reveal_around;
+use crate::world::interact::InteractKind;
+use crate::world::map::TransitionKind;
+use crate::world::progress::Progress;
+use crate::world::source::{MAP_DIR, MOD_DIR};
+use crate::world::trigger::TriggerAction;
+use crate::world::watch::FileWatcher;
 use crate::world::World;
 
 // 游戏主循环：加载地图数据，处理输入与渲染
 pub async fn run() {
     let ui = Ui {
         font: load_ui_font().await,
+        sprites: SpriteLibrary::load_from_file(SPRITES_PATH).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            SpriteLibrary::default()
+        }),
+    };
+
+    // 加载地图配置（数据驱动），校验问题输出到标准错误
+    let mut world = match World::load_from_file(MAPS_PATH) {
+        Ok((world, report)) => {
+            for warning in report.warnings() {
+                eprintln!("[警告] {warning}");
+            }
+            world
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            World::default()
+        }
     };
 
-    // 加载地图配置（数据驱动）
-    let mut world = World::load_from_file("data/maps.json").unwrap_or_else(|_| World::default());
+    // 加载首领与妖兽定义、对话库与掉落表，并提示地图中引用了但未定义的条目
+    let bosses = BossBook::load_from_file(BOSSES_PATH).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        BossBook::default()
+    });
+    let monsters = MonsterBook::load_from_file(MONSTERS_PATH).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        MonsterBook::default()
+    });
+    let dialogues = DialogueBook::load_from_file(DIALOGUES_PATH).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        DialogueBook::default()
+    });
+    let loot = LootBook::load_from_file(LOOT_PATH).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        LootBook::default()
+    });
+    warn_missing_refs(&world, &bosses, &monsters, &dialogues, &loot);
+    let mut catalog = ItemCatalog::load_from_file(ITEMS_PATH).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        ItemCatalog::default()
+    });
+    if let Err(e) = catalog.load_sets(SETS_PATH) {
+        eprintln!("{e}");
+    }
+    let catalog = Rc::new(catalog);
+    let mut textures = BackgroundTextures::default();
+    textures.load_missing(&world).await;
 
-    // 初始化玩家位置到当前地图的出生点
+    // 初始化玩家位置到当前地图的出生点，有存档时恢复所在地图、位置与进度
+    let store = SaveStore::new();
     let mut player = Player::new(world.current_spawn());
+    let mut progress = Progress::new(Rc::clone(&catalog));
+    let mut notice = Notice::default();
+    match store.load_world() {
+        Ok(Some(save)) => {
+            progress = Progress::from_save(&save, Rc::clone(&catalog));
+            if world.has_map(&save.map) {
+                world.switch_map(save.map.clone());
+                player.pos = vec2(save.x, save.y);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("读取存档失败：{e}");
+            notice.show(format!("读取存档失败：{e}"));
+        }
+    }
+    player.sync_realm(progress.cultivation.realm);
+    player.sync_equipment(&progress.equipment, &catalog);
+    player.restore();
+    progress.visited_maps.insert(world.current_map().id.clone());
+    let mut combat = Combat::default();
+    let mut dialogue: Option<DialogueState> = None;
+    let mut triggers = TriggerState::default();
+    let mut transition: Option<Transition> = None;
+    let mut world_map: Option<WorldMapScreen> = None;
+    let mut inventory: Option<InventoryScreen> = None;
+    let mut editor: Option<EditorState> = None;
+    let mut portal_prompt: Option<PortalPrompt> = None;
+    let mut portal_touch = PortalTouch::default();
+    let mut meditation = Meditation::default();
+    let mut show_stats = false;
+    let mut weather = WeatherState::default();
+    let mut banner = MapBanner::default();
+    banner.show(world.current_map().name.clone());
+
+    // 按当前地图同步首领与妖兽（同一地图内重复调用无副作用）
+    let sync_combat = |combat: &mut Combat, world: &World, player: &Player, progress: &Progress| {
+        combat.sync_map(
+            world.current_map(),
+            &bosses,
+            &monsters,
+            player.pos,
+            progress,
+        );
+    };
+
+    // 跟随镜头（切换地图时直接对准玩家）
+    let mut camera = FollowCamera::new(player.pos, world.current_map().world_size());
+    let mut camera_map = world.current_map().id.clone();
+
+    // 监视地图文件、地图目录、mod 目录及导入的 Tiled 文件，修改后热重载；失败时保留旧数据并显示错误横幅
+    let data_dir = Path::new(MAPS_PATH).parent().unwrap_or(Path::new("."));
+    let mut watcher = FileWatcher::new(MAPS_PATH)
+        .with_dir(data_dir.join(MAP_DIR))
+        .with_dir(data_dir.join(MOD_DIR));
+    watcher.watch_files(world.tiled_files());
+    let mut reload_error: Option<String> = None;
+    // 编辑器有未保存的修改时推迟热重载，修改保存或放弃后再载入
+    let mut reload_pending = false;
 
     // 低分辨率渲染目标，用于像素风文字
     let rt_w = (SCREEN_W * INTERNAL_RENDER_SCALE).max(1.0) as u32;
@@ -27,23 +176,310 @@ pub async fn run() {
     let rt_h_f = rt_h as f32;
     let render_target = render_target(rt_w, rt_h);
     render_target.texture.set_filter(FilterMode::Nearest);
+    // 关闭窗口时先存档再退出
+    prevent_quit();
 
     loop {
-        // 移动与交互
-        handle_movement(&mut player);
-        handle_interaction(&mut world, &mut player);
+        if is_quit_requested() {
+            if let Err(e) = save_world(&store, &world, &player, &progress) {
+                eprintln!("退出存档失败：{e}");
+            }
+            break;
+        }
+
+        // 地图数据热重载
+        let changed = watcher.poll(get_frame_time());
+        let unsaved = editor.as_ref().is_some_and(|state| state.dirty);
+        if changed && unsaved {
+            notice.show("地图文件已在外部修改，保存或放弃编辑器中的修改后再载入");
+        }
+        reload_pending |= changed;
+        if reload_pending && !unsaved {
+            reload_pending = false;
+            match World::load_from_file(MAPS_PATH) {
+                Ok((fresh, report)) => {
+                    for warning in report.warnings() {
+                        eprintln!("[警告] {warning}");
+                    }
+                    // 当前地图仍在时，新加的墙若压住玩家就移到最近的空地
+                    if !world.hot_swap(fresh) {
+                        player.pos = world.current_spawn();
+                    } else if let Some(grid) = &world.current_map().tiles {
+                        player.pos = nearest_open(grid, player.pos, player.radius);
+                    }
+                    // 过场的目标地图已被删除时取消过场
+                    if transition
+                        .as_ref()
+                        .and_then(Transition::destination)
+                        .is_some_and(|to_map| !world.has_map(to_map))
+                    {
+                        transition = None;
+                    }
+                    watcher.watch_files(world.tiled_files());
+                    combat.reset();
+                    triggers.reset();
+                    portal_prompt = None;
+                    if let Some(state) = &mut editor {
+                        state.reset();
+                    }
+                    textures.load_missing(&world).await;
+                    reload_error = None;
+                }
+                Err(e) => reload_error = Some(e.to_string()),
+            }
+        }
+
+        // 过场或对话进行中时冻结移动与战斗；画面完全遮住时载入目标地图并自动存档
+        if let Some(active) = &mut transition {
+            match active.update(get_frame_time()) {
+                TransitionStep::Load { to_map, to_pos } => {
+                    world.switch_map(to_map);
+                    player.pos = to_pos;
+                    progress.visited_maps.insert(world.current_map().id.clone());
+                    sync_combat(&mut combat, &world, &player, &progress);
+                    banner.show(world.current_map().name.clone());
+                    if let Err(e) = save_world(&store, &world, &player, &progress) {
+                        notice.show(format!("自动存档失败：{e}"));
+                    }
+                }
+                TransitionStep::Finished => transition = None,
+                TransitionStep::Running => {}
+            }
+        } else if let Some(screen) = &mut world_map {
+            // 世界地图打开时冻结移动与战斗，选定目的地后以淡入淡出过场快速旅行
+            match update_world_map(screen, &world, &progress) {
+                Some(WorldMapAction::Close) => world_map = None,
+                Some(WorldMapAction::Travel { to_map, to_pos }) => {
+                    transition = Some(Transition::new(TransitionKind::Fade, to_map, to_pos));
+                    world_map = None;
+                }
+                None => {}
+            }
+        } else if let Some(screen) = &mut inventory {
+            // 背包打开时冻结移动与战斗
+            if !update_inventory(screen, &mut player, &mut progress, &mut notice) {
+                inventory = None;
+            }
+        } else if let Some(state) = &mut editor {
+            // 编辑模式冻结游戏；退出时若已离开原地图（或原地图被删除），玩家回到当前地图出生点
+            let cursor = mouse_world(camera.view_rect());
+            if !update_editor(state, &mut world, cursor, &mut notice) {
+                if state.entry_map != world.current_map().id {
+                    player.pos = world.current_spawn();
+                    progress.visited_maps.insert(world.current_map().id.clone());
+                    banner.show(world.current_map().name.clone());
+                }
+                combat.reset();
+                triggers.reset();
+                textures.load_missing(&world).await;
+                editor = None;
+            }
+        } else if let Some(prompt) = &portal_prompt {
+            // 传送确认框打开时冻结移动与战斗
+            if !update_portal_prompt(prompt, &world, &mut progress, &mut notice, &mut transition) {
+                portal_prompt = None;
+            }
+        } else if let Some(state) = &mut dialogue {
+            if !update_dialogue(state, &dialogues, &mut progress, &mut notice) {
+                dialogue = None;
+            }
+        } else {
+            if is_key_pressed(KeyCode::M) {
+                world_map = Some(WorldMapScreen::open(&world, &progress));
+            }
+            if is_key_pressed(KeyCode::I) {
+                inventory = Some(InventoryScreen::default());
+            }
+            if is_key_pressed(KeyCode::F2) {
+                editor = Some(EditorState::open(&world, player.pos));
+            }
+            if is_key_pressed(KeyCode::C) {
+                show_stats = !show_stats;
+            }
+
+            // 游戏内时间与增益计时只在自由行动时流逝；属性随境界成长
+            progress.clock.update(get_frame_time());
+            player.stats.update(get_frame_time());
+            player.sync_realm(progress.cultivation.realm);
+
+            // 打坐、服丹与突破；打坐时不能移动
+            update_cultivation(
+                &mut meditation,
+                &mut player,
+                world.current_map(),
+                &mut progress,
+                &mut notice,
+            );
+
+            // 移动与交互
+            if !meditation.is_active() {
+                handle_movement(&mut player, world.current_map());
+            }
+            let target = world.nearest_interactable(player.pos, &progress);
+            let used = handle_interaction(
+                target.as_ref(),
+                &world,
+                &mut progress,
+                &mut notice,
+                &dialogues,
+                &mut dialogue,
+                &loot,
+            );
+
+            // 传送点：按 E 使用或踏入踩踏式传送点，需要确认时先弹出确认框
+            let touched = update_portal_touch(&mut portal_touch, &world, player.pos);
+            let portal = match (used, &target) {
+                (Some(InteractKind::Portal), Some(target)) => Some(target.index),
+                _ => touched,
+            };
+            if let Some(index) = portal {
+                use_portal(
+                    index,
+                    &world,
+                    &mut progress,
+                    &mut notice,
+                    &mut portal_prompt,
+                    &mut transition,
+                );
+            }
+
+            // 触发区域：先同步战斗到当前地图，剧情生成的妖兽才不会被清掉
+            sync_combat(&mut combat, &world, &player, &progress);
+            update_triggers(
+                &mut triggers,
+                world.current_map(),
+                player.pos,
+                &progress,
+                get_frame_time(),
+            );
+            run_trigger_actions(
+                &mut triggers,
+                &mut TriggerContext {
+                    world: &world,
+                    player: &mut player,
+                    progress: &mut progress,
+                    notice: &mut notice,
+                    combat: &mut combat,
+                    monsters: &monsters,
+                    dialogues: &dialogues,
+                    dialogue: &mut dialogue,
+                    transition: &mut transition,
+                },
+            );
+
+            // 存档点回复气血并手动存档
+            if used == Some(InteractKind::SavePoint) {
+                player.restore();
+                match save_world(&store, &world, &player, &progress) {
+                    Ok(()) => notice.show("灵气充盈，气血已恢复，进度已保存"),
+                    Err(e) => notice.show(format!("存档失败：{e}")),
+                }
+            }
+            update_combat(
+                &mut combat,
+                world.current_map(),
+                &monsters,
+                &loot,
+                &mut player,
+                &mut progress,
+                &mut notice,
+            );
+        }
+        reveal_around(&mut progress.fog, world.current_map(), player.pos);
+        notice.update(get_frame_time());
+        banner.update(get_frame_time());
+
+        // 镜头跟随（编辑模式下跟随编辑器焦点）
+        let world_size = world.current_map().world_size();
+        let focus = editor.as_ref().map_or(player.pos, |state| state.focus);
+        if camera_map != world.current_map().id {
+            camera.snap(focus, world_size);
+            camera_map = world.current_map().id.clone();
+        } else {
+            camera.update(focus, world_size);
+        }
+        let view = camera.view_rect();
+        update_weather(
+            &mut weather,
+            world.current_map(),
+            view,
+            progress.clock.phase(),
+            get_frame_time(),
+        );
 
         // 计算窗口缩放
         let (scale, offset_x, offset_y) = compute_viewport();
-        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, SCREEN_W, SCREEN_H));
-        camera.render_target = Some(render_target.clone());
-        set_camera(&camera);
+        let mut world_camera = Camera2D::from_display_rect(view);
+        world_camera.render_target = Some(render_target.clone());
+        set_camera(&world_camera);
 
-        // 绘制当前地图与实体
-        draw_map(&ui, world.current_map());
-        draw_portals(&ui, world.current_map());
-        draw_player(&ui, &player);
-        draw_hud(&ui, world.current_map());
+        // 绘制当前地图与实体（世界坐标）
+        draw_map(&ui, world.current_map(), &textures, view);
+        draw_portals(&ui, world.current_map(), &progress);
+        draw_props(&ui, world.current_map(), &progress);
+        draw_npcs(&ui, world.current_map(), &progress);
+        draw_monsters(&ui, &combat);
+        draw_bosses(&ui, &combat);
+        draw_projectiles(&combat);
+        draw_player(&ui, &player, &combat);
+        if let Some(state) = &editor {
+            draw_editor_overlay(&ui, &world, state, mouse_world(view));
+        } else {
+            draw_weather(&weather);
+            draw_day_tint(&progress.clock, view);
+        }
+        let busy = dialogue.is_some()
+            || transition.is_some()
+            || world_map.is_some()
+            || inventory.is_some()
+            || editor.is_some()
+            || portal_prompt.is_some();
+        if !busy {
+            if let Some(target) = world.nearest_interactable(player.pos, &progress) {
+                draw_interact_prompt(&ui, &target);
+            }
+        }
+
+        // HUD 使用屏幕坐标，不随镜头移动
+        let mut hud_camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, SCREEN_W, SCREEN_H));
+        hud_camera.render_target = Some(render_target.clone());
+        set_camera(&hud_camera);
+        if let Some(state) = &editor {
+            draw_editor_panel(&ui, &world, state, &notice);
+        } else {
+            draw_hud(
+                &ui,
+                world.current_map(),
+                &player,
+                &combat,
+                &notice,
+                &progress,
+                meditation.is_active(),
+            );
+            draw_map_banner(&ui, &banner);
+            if show_stats {
+                draw_stat_panel(&ui, &player, &progress);
+            }
+            draw_minimap(&ui, world.current_map(), &progress, player.pos, view);
+        }
+        if let Some(screen) = &world_map {
+            draw_world_map(&ui, &world, &progress, screen);
+        }
+        if let Some(screen) = &inventory {
+            draw_inventory(&ui, &progress, screen, &notice);
+        }
+        if let Some(state) = &dialogue {
+            draw_dialogue(&ui, state, &progress);
+        }
+        if let Some(prompt) = &portal_prompt {
+            draw_portal_prompt(&ui, prompt);
+        }
+        if let Some(active) = &transition {
+            draw_transition(active, player.pos - view.point());
+        }
+        if let Some(message) = &reload_error {
+            draw_error_banner(&ui, message);
+        }
 
         // 回到默认相机并放大显示
         set_default_camera();
@@ -65,6 +501,102 @@ pub async fn run() {
     }
 }
 
+// 写入大地图存档
+fn save_world(
+    store: &SaveStore,
+    world: &World,
+    player: &Player,
+    progress: &Progress,
+) -> std::io::Result<()> {
+    store.save_world(&progress.to_save(&world.current_map().id, player.pos))
+}
+
+// 提示地图、首领与妖兽中引用了但数据文件里未定义的首领、妖兽、对话与掉落表
+fn warn_missing_refs(
+    world: &World,
+    bosses: &BossBook,
+    monsters: &MonsterBook,
+    dialogues: &DialogueBook,
+    loot: &LootBook,
+) {
+    for (table, missing) in loot.missing_refs() {
+        eprintln!("[警告] 掉落表 {table} 引用的掉落表 {missing} 未在 {LOOT_PATH} 中定义");
+    }
+    for (boss, table) in bosses.loot_refs().filter(|(_, t)| loot.get(t).is_none()) {
+        eprintln!("[警告] 首领 {boss} 的掉落表 {table} 未在 {LOOT_PATH} 中定义");
+    }
+    for (monster, table) in monsters.loot_refs().filter(|(_, t)| loot.get(t).is_none()) {
+        eprintln!("[警告] 妖兽 {monster} 的掉落表 {table} 未在 {LOOT_PATH} 中定义");
+    }
+    for map in world.maps() {
+        for spawn in &map.bosses {
+            if bosses.get(spawn.name()).is_none() {
+                eprintln!(
+                    "[警告] 地图 {} 的首领 {} 未在 {BOSSES_PATH} 中定义",
+                    map.id,
+                    spawn.name()
+                );
+            }
+        }
+        for zone in &map.spawn_zones {
+            for entry in &zone.monsters {
+                if monsters.get(&entry.monster).is_none() {
+                    eprintln!(
+                        "[警告] 地图 {} 的刷怪区域引用的妖兽 {} 未在 {MONSTERS_PATH} 中定义",
+                        map.id, entry.monster
+                    );
+                }
+            }
+        }
+        for chest in &map.chests {
+            if let Some(table) = chest.loot.as_deref().filter(|t| loot.get(t).is_none()) {
+                eprintln!(
+                    "[警告] 地图 {} 的宝箱 {} 的掉落表 {table} 未在 {LOOT_PATH} 中定义",
+                    map.id, chest.id
+                );
+            }
+        }
+        for npc in &map.npcs {
+            if dialogues.get(&npc.dialogue).is_none() {
+                eprintln!(
+                    "[警告] 地图 {} 的 NPC {} 引用的对话 {} 未在 {DIALOGUES_PATH} 中定义",
+                    map.id, npc.id, npc.dialogue
+                );
+            }
+        }
+        for trigger in &map.triggers {
+            for action in &trigger.actions {
+                match action {
+                    TriggerAction::SpawnMonsters { monster, .. }
+                        if monsters.get(monster).is_none() =>
+                    {
+                        eprintln!(
+                            "[警告] 地图 {} 的触发区域 {} 引用的妖兽 {monster} 未在 {MONSTERS_PATH} 中定义",
+                            map.id, trigger.id
+                        );
+                    }
+                    TriggerAction::Cutscene { dialogue, .. }
+                        if dialogues.get(dialogue).is_none() =>
+                    {
+                        eprintln!(
+                            "[警告] 地图 {} 的触发区域 {} 引用的对话 {dialogue} 未在 {DIALOGUES_PATH} 中定义",
+                            map.id, trigger.id
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+// 鼠标位置换算为世界坐标（先去掉窗口缩放与留边，再加上镜头偏移）
+fn mouse_world(view: Rect) -> Vec2 {
+    let (scale, offset_x, offset_y) = compute_viewport();
+    let (mx, my) = mouse_position();
+    vec2((mx - offset_x) / scale, (my - offset_y) / scale) + view.point()
+}
+
 // 根据窗口尺寸计算缩放比例与居中偏移
 fn compute_viewport() -> (f32, f32, f32) {
     let sw = screen_width();
@@ -1,6 +1,14 @@
-﻿use std::path::Path;
+﻿use std::fs;
+use std::path::Path;
 
 use macroquad::prelude::*;
+use serde::de::DeserializeOwned;
+
+// 读取JSON数据文件（兼容 BOM），错误信息附带文件路径
+pub fn load_json<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| format!("{path}: {e}"))
+}
 
 // 加载UI字体，优先使用项目资源中的字体，其次尝试系统字体
 pub async fn load_ui_font() -> Option<Font> {
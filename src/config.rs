@@ -3,6 +3,27 @@
 pub const SCREEN_W: f32 = 960.0;
 pub const SCREEN_H: f32 = 540.0;
 
+// 地图数据文件路径（运行中修改会自动热重载）
+pub const MAPS_PATH: &str = "data/maps.json";
+
+// 首领与妖兽定义、对话与精灵库数据文件
+pub const BOSSES_PATH: &str = "data/bosses.json";
+pub const MONSTERS_PATH: &str = "data/monsters.json";
+pub const DIALOGUES_PATH: &str = "data/dialogues.json";
+pub const SPRITES_PATH: &str = "data/sprites.json";
+
+// 物品表与套装数据文件
+pub const ITEMS_PATH: &str = "data/items.json";
+pub const SETS_PATH: &str = "data/sets.json";
+
+// 掉落表数据文件
+pub const LOOT_PATH: &str = "data/loot.json";
+
+// 境界总数与每升一重所需分数（射击玩法）
+pub const PLANE_LEVELS: usize = 15;
+#[allow(dead_code)]
+pub const SCORE_PER_LEVEL: u32 = 1000;
+
 // 降低内部渲染分辨率，放大时呈现像素风文本与画面
 pub const INTERNAL_RENDER_SCALE: f32 = 0.5;
 
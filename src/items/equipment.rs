@@ -0,0 +1,340 @@
+﻿use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actors::stats::Modifier;
+use crate::items::ItemCatalog;
+use crate::sprite::ColorDef;
+
+// 装备种类：法宝、法衣、饰品、符箓
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipKind {
+    Artifact,
+    Robe,
+    Accessory,
+    Talisman,
+}
+
+// 装备栏位（符箓有两个栏位）
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipSlot {
+    Artifact,
+    Robe,
+    Accessory,
+    Talisman1,
+    Talisman2,
+}
+
+impl EquipSlot {
+    // 全部栏位（界面显示顺序）
+    pub const ALL: [EquipSlot; 5] = [
+        EquipSlot::Artifact,
+        EquipSlot::Robe,
+        EquipSlot::Accessory,
+        EquipSlot::Talisman1,
+        EquipSlot::Talisman2,
+    ];
+
+    // 栏位名称
+    pub fn label(self) -> &'static str {
+        match self {
+            EquipSlot::Artifact => "法宝",
+            EquipSlot::Robe => "法衣",
+            EquipSlot::Accessory => "饰品",
+            EquipSlot::Talisman1 | EquipSlot::Talisman2 => "符箓",
+        }
+    }
+
+    // 栏位可装备的种类
+    pub fn kind(self) -> EquipKind {
+        match self {
+            EquipSlot::Artifact => EquipKind::Artifact,
+            EquipSlot::Robe => EquipKind::Robe,
+            EquipSlot::Accessory => EquipKind::Accessory,
+            EquipSlot::Talisman1 | EquipSlot::Talisman2 => EquipKind::Talisman,
+        }
+    }
+}
+
+// 物品的装备属性：种类、属性修正、所属套装、境界要求与法宝灵光颜色
+#[derive(Clone, Debug, Deserialize)]
+pub struct EquipDef {
+    pub kind: EquipKind,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(default)]
+    pub min_realm: usize,
+    // 装备为法宝时环绕玩家的灵光颜色
+    #[serde(default)]
+    pub aura: Option<ColorDef>,
+}
+
+// 套装：名称与按件数生效的加成（data/sets.json）
+#[derive(Clone, Debug, Deserialize)]
+pub struct SetDef {
+    pub name: String,
+    pub bonuses: Vec<SetBonus>,
+}
+
+// 套装加成：装备不少于 pieces 件时生效
+#[derive(Clone, Debug, Deserialize)]
+pub struct SetBonus {
+    pub pieces: usize,
+    pub modifiers: Vec<Modifier>,
+}
+
+// 法宝灵光：绘制时环绕玩家的法宝图标与光环颜色
+#[derive(Clone, Debug)]
+pub struct Aura {
+    pub icon: String,
+    pub color: ColorDef,
+}
+
+// 已装备的物品：栏位 → 物品 id
+#[derive(Clone, Debug, Default)]
+pub struct Equipment {
+    slots: BTreeMap<EquipSlot, String>,
+}
+
+impl Equipment {
+    // 从存档恢复
+    pub fn from_save(slots: &BTreeMap<EquipSlot, String>) -> Self {
+        Self {
+            slots: slots.clone(),
+        }
+    }
+
+    // 生成存档记录
+    pub fn to_save(&self) -> BTreeMap<EquipSlot, String> {
+        self.slots.clone()
+    }
+
+    // 指定栏位装备的物品
+    pub fn get(&self, slot: EquipSlot) -> Option<&str> {
+        self.slots.get(&slot).map(String::as_str)
+    }
+
+    // 某种装备应放入的栏位：优先空栏位，符箓栏都满时替换第一个
+    pub fn slot_for(&self, kind: EquipKind) -> EquipSlot {
+        let mut candidates = EquipSlot::ALL.into_iter().filter(|s| s.kind() == kind);
+        let first = candidates.clone().next().unwrap_or(EquipSlot::Artifact);
+        candidates
+            .find(|s| !self.slots.contains_key(s))
+            .unwrap_or(first)
+    }
+
+    // 放入栏位，返回被换下的物品
+    pub fn equip(&mut self, slot: EquipSlot, item: &str) -> Option<String> {
+        self.slots.insert(slot, item.to_string())
+    }
+
+    // 卸下栏位中的物品
+    pub fn unequip(&mut self, slot: EquipSlot) -> Option<String> {
+        self.slots.remove(&slot)
+    }
+
+    // 各套装已装备的件数（按套装 id 排序）
+    pub fn set_pieces(&self, catalog: &ItemCatalog) -> BTreeMap<String, usize> {
+        let mut pieces = BTreeMap::new();
+        for item in self.slots.values() {
+            let set = catalog.equip(item).and_then(|def| def.set.as_ref());
+            if let Some(set) = set {
+                *pieces.entry(set.clone()).or_default() += 1;
+            }
+        }
+        pieces
+    }
+
+    // 装备与已生效套装加成的全部属性修正
+    pub fn modifiers(&self, catalog: &ItemCatalog) -> Vec<Modifier> {
+        let mut modifiers: Vec<Modifier> = self
+            .slots
+            .values()
+            .filter_map(|item| catalog.equip(item))
+            .flat_map(|def| def.modifiers.iter().copied())
+            .collect();
+        for (set, count) in self.set_pieces(catalog) {
+            let Some(def) = catalog.set(&set) else {
+                continue;
+            };
+            for bonus in def.bonuses.iter().filter(|b| count >= b.pieces) {
+                modifiers.extend(bonus.modifiers.iter().copied());
+            }
+        }
+        modifiers
+    }
+
+    // 法宝的灵光（法宝未设置灵光颜色时没有）
+    pub fn aura(&self, catalog: &ItemCatalog) -> Option<Aura> {
+        let item = self.get(EquipSlot::Artifact)?;
+        let color = catalog.equip(item)?.aura?;
+        let icon = catalog.get(item)?.icon.clone();
+        Some(Aura { icon, color })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use macroquad::prelude::Vec2;
+    use serde_json::json;
+
+    use super::*;
+    use crate::actors::player::Player;
+    use crate::actors::stats::Stat;
+    use crate::systems::inventory::{equip_item, unequip_item};
+    use crate::world::progress::Progress;
+
+    // 测试用物品表：玉衡套装四件（两件成套加气血、四件成套加攻击）与两件散件
+    fn catalog() -> ItemCatalog {
+        let equip = |kind: &str, stat: &str, flat: i32, set: Option<&str>| {
+            json!({
+                "name": format!("{kind}·{stat}"),
+                "icon": kind,
+                "category": "equipment",
+                "equip": {
+                    "kind": kind,
+                    "modifiers": [{ "stat": stat, "flat": flat }],
+                    "set": set
+                }
+            })
+        };
+        ItemCatalog {
+            defs: serde_json::from_value(json!({
+                "jade_sword": equip("artifact", "attack", 5, Some("jade")),
+                "jade_robe": equip("robe", "defense", 3, Some("jade")),
+                "jade_ring": equip("accessory", "perception", 2, Some("jade")),
+                "jade_charm": equip("talisman", "agility", 1, Some("jade")),
+                "iron_blade": equip("artifact", "attack", 8, None),
+                "paper_charm": equip("talisman", "mana", 4, None)
+            }))
+            .unwrap(),
+            sets: serde_json::from_value(json!({
+                "jade": {
+                    "name": "玉衡套装",
+                    "bonuses": [
+                        { "pieces": 2, "modifiers": [{ "stat": "hp", "flat": 10 }] },
+                        { "pieces": 4, "modifiers": [{ "stat": "attack", "percent": 0.5 }] }
+                    ]
+                }
+            }))
+            .unwrap(),
+        }
+    }
+
+    // 某属性的修正合计（固定值, 百分比）
+    fn total(equipment: &Equipment, catalog: &ItemCatalog, stat: Stat) -> (i32, f32) {
+        equipment
+            .modifiers(catalog)
+            .iter()
+            .filter(|m| m.stat == stat)
+            .fold((0, 0.0), |(f, p), m| (f + m.flat, p + m.percent))
+    }
+
+    #[test]
+    fn each_kind_goes_to_its_slot_and_talismans_fill_both() {
+        let mut equipment = Equipment::default();
+        assert_eq!(equipment.slot_for(EquipKind::Artifact), EquipSlot::Artifact);
+        assert_eq!(equipment.slot_for(EquipKind::Robe), EquipSlot::Robe);
+        assert_eq!(
+            equipment.slot_for(EquipKind::Accessory),
+            EquipSlot::Accessory
+        );
+        assert_eq!(
+            equipment.slot_for(EquipKind::Talisman),
+            EquipSlot::Talisman1
+        );
+        equipment.equip(EquipSlot::Talisman1, "paper_charm");
+        assert_eq!(
+            equipment.slot_for(EquipKind::Talisman),
+            EquipSlot::Talisman2
+        );
+        equipment.equip(EquipSlot::Talisman2, "jade_charm");
+        // 两个符箓栏都满时替换第一个
+        assert_eq!(
+            equipment.slot_for(EquipKind::Talisman),
+            EquipSlot::Talisman1
+        );
+        assert_eq!(equipment.slot_for(EquipKind::Artifact), EquipSlot::Artifact);
+    }
+
+    #[test]
+    fn replacing_equipment_returns_the_old_item_to_the_bag() {
+        let mut progress = Progress::new(Rc::new(catalog()));
+        progress.inventory.add("jade_sword", 1);
+        progress.inventory.add("iron_blade", 1);
+        let mut player = Player::new(Vec2::ZERO);
+        let base = player.stats.get(Stat::Attack);
+
+        equip_item(0, &mut player, &mut progress).unwrap();
+        assert_eq!(
+            progress.equipment.get(EquipSlot::Artifact),
+            Some("jade_sword")
+        );
+        assert_eq!(player.stats.get(Stat::Attack), base + 5);
+
+        let text = equip_item(1, &mut player, &mut progress).unwrap();
+        assert!(text.contains("换下"), "{text}");
+        assert_eq!(
+            progress.equipment.get(EquipSlot::Artifact),
+            Some("iron_blade")
+        );
+        assert_eq!(progress.inventory.count("jade_sword"), 1);
+        assert_eq!(progress.inventory.count("iron_blade"), 0);
+        assert_eq!(player.stats.get(Stat::Attack), base + 8);
+
+        unequip_item(EquipSlot::Artifact, &mut player, &mut progress).unwrap();
+        assert_eq!(progress.equipment.get(EquipSlot::Artifact), None);
+        assert_eq!(progress.inventory.count("iron_blade"), 1);
+        assert_eq!(player.stats.get(Stat::Attack), base);
+    }
+
+    #[test]
+    fn a_full_bag_keeps_the_old_item_equipped() {
+        let mut progress = Progress::new(Rc::new(catalog()));
+        progress.equipment.equip(EquipSlot::Artifact, "jade_sword");
+        // 取出一把后这一格仍有剩余，背包没有空位放回原有法宝
+        progress.inventory.add("iron_blade", 2);
+        progress.inventory.add("paper_charm", 29 * 99);
+        let mut player = Player::new(Vec2::ZERO);
+        assert!(equip_item(0, &mut player, &mut progress).is_err());
+        assert_eq!(
+            progress.equipment.get(EquipSlot::Artifact),
+            Some("jade_sword")
+        );
+        assert_eq!(progress.inventory.count("iron_blade"), 2);
+        assert_eq!(progress.inventory.count("jade_sword"), 0);
+    }
+
+    #[test]
+    fn set_bonuses_follow_the_piece_count() {
+        let catalog = catalog();
+        let mut equipment = Equipment::default();
+        equipment.equip(EquipSlot::Artifact, "jade_sword");
+        assert_eq!(total(&equipment, &catalog, Stat::Hp), (0, 0.0));
+
+        equipment.equip(EquipSlot::Robe, "jade_robe");
+        assert_eq!(equipment.set_pieces(&catalog)["jade"], 2);
+        assert_eq!(total(&equipment, &catalog, Stat::Hp), (10, 0.0));
+        assert_eq!(total(&equipment, &catalog, Stat::Attack), (5, 0.0));
+
+        equipment.equip(EquipSlot::Accessory, "jade_ring");
+        equipment.equip(EquipSlot::Talisman1, "jade_charm");
+        assert_eq!(total(&equipment, &catalog, Stat::Hp), (10, 0.0));
+        assert_eq!(total(&equipment, &catalog, Stat::Attack), (5, 0.5));
+
+        // 换下一件后四件加成失效，两件加成仍在
+        equipment.equip(EquipSlot::Artifact, "iron_blade");
+        assert_eq!(total(&equipment, &catalog, Stat::Attack), (8, 0.0));
+        assert_eq!(total(&equipment, &catalog, Stat::Hp), (10, 0.0));
+
+        equipment.unequip(EquipSlot::Robe);
+        equipment.unequip(EquipSlot::Accessory);
+        assert_eq!(total(&equipment, &catalog, Stat::Hp), (0, 0.0));
+    }
+}
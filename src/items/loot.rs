@@ -0,0 +1,493 @@
+﻿use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::assets::load_json;
+use crate::items::Inventory;
+
+// 嵌套掉落表的最大深度（防止表互相引用造成死循环）
+const MAX_DEPTH: usize = 8;
+
+// 单次掷骰最多发放的条目数（防止嵌套表按 rolls 成倍展开）
+const MAX_ENTRIES: usize = 1024;
+
+// 稀有度：影响掉落提示的标注
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    // 稀有度名称（普通品质不标注）
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Rarity::Common => None,
+            Rarity::Uncommon => Some("良品"),
+            Rarity::Rare => Some("稀有"),
+            Rarity::Legendary => Some("传说"),
+        }
+    }
+}
+
+// 掉落条目的内容：物品（数量在 count..=max 之间）、嵌套的掉落表或空
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LootKind {
+    Item {
+        item: String,
+        #[serde(default = "default_count")]
+        count: u32,
+        #[serde(default)]
+        max: Option<u32>,
+    },
+    Table {
+        table: String,
+    },
+    Nothing,
+}
+
+// 掉落条目：内容、权重、稀有度与生效条件（境界范围、所在地图，留空表示不限）
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootEntry {
+    #[serde(flatten)]
+    pub kind: LootKind,
+    #[serde(default = "default_count")]
+    pub weight: u32,
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub min_realm: Option<usize>,
+    #[serde(default)]
+    pub max_realm: Option<usize>,
+    #[serde(default)]
+    pub maps: Vec<String>,
+}
+
+// 默认数量与权重
+fn default_count() -> u32 {
+    1
+}
+
+impl LootEntry {
+    // 在给定境界与地图下是否生效
+    fn applies(&self, ctx: &LootContext) -> bool {
+        self.min_realm.is_none_or(|r| ctx.realm >= r)
+            && self.max_realm.is_none_or(|r| ctx.realm <= r)
+            && (self.maps.is_empty()
+                || ctx
+                    .map
+                    .is_some_and(|map| self.maps.iter().any(|m| m == map)))
+    }
+}
+
+// 掉落表：必掉条目全部发放，再从抽取池中按权重抽取 rolls 次
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootTable {
+    #[serde(default)]
+    pub guaranteed: Vec<LootEntry>,
+    #[serde(default = "default_count")]
+    pub rolls: u32,
+    #[serde(default)]
+    pub pool: Vec<LootEntry>,
+}
+
+// 掷骰时的上下文：玩家境界与所在地图（不在地图中掷骰时为 None，限定地图的条目不生效）
+#[derive(Clone, Copy, Debug)]
+pub struct LootContext<'a> {
+    pub realm: usize,
+    pub map: Option<&'a str>,
+}
+
+// 一项掉落结果
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LootDrop {
+    pub item: String,
+    pub count: u32,
+    pub rarity: Rarity,
+}
+
+// 掉落与刷怪抽取用的随机数发生器（SplitMix64）：同一种子得到相同的结果，便于复现与测试
+#[derive(Clone, Debug)]
+pub struct LootRng {
+    state: u64,
+}
+
+impl LootRng {
+    // 以指定种子创建
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // 以全局随机数播种（游戏内掉落使用）
+    pub fn random() -> Self {
+        let high = macroquad::rand::rand() as u64;
+        let low = macroquad::rand::rand() as u64;
+        Self::new(high << 32 | low)
+    }
+
+    // 下一个 64 位随机数
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // [0, n) 内的随机整数（n 为 0 时返回 0）
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        (self.next_u64() % n as u64) as u32
+    }
+}
+
+// 掷骰中累积的结果与剩余可发放的条目数
+struct Tally {
+    drops: Vec<LootDrop>,
+    left: usize,
+}
+
+// 掉落表集合：按 id 索引（data/loot.json）
+#[derive(Clone, Debug, Default)]
+pub struct LootBook {
+    tables: HashMap<String, LootTable>,
+}
+
+impl LootBook {
+    // 从JSON文件加载掉落表
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Self::from_tables(load_json(path)?)
+    }
+
+    // 由掉落表构建，抽取池权重总和超出 u32 范围时报错
+    fn from_tables(tables: HashMap<String, LootTable>) -> Result<Self, String> {
+        for (id, table) in &tables {
+            let total: u64 = table.pool.iter().map(|e| u64::from(e.weight)).sum();
+            if total > u64::from(u32::MAX) {
+                return Err(format!("掉落表 {id} 的抽取池权重总和 {total} 超出上限"));
+            }
+        }
+        Ok(Self { tables })
+    }
+
+    // 按 id 获取掉落表
+    pub fn get(&self, id: &str) -> Option<&LootTable> {
+        self.tables.get(id)
+    }
+
+    // 掉落表中引用了但未定义的嵌套表：(所在表, 引用的表)，按表名排序
+    pub fn missing_refs(&self) -> Vec<(&str, &str)> {
+        let mut missing: Vec<(&str, &str)> = self
+            .tables
+            .iter()
+            .flat_map(|(id, table)| {
+                table
+                    .guaranteed
+                    .iter()
+                    .chain(&table.pool)
+                    .filter_map(move |entry| match &entry.kind {
+                        LootKind::Table { table } if self.get(table).is_none() => {
+                            Some((id.as_str(), table.as_str()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    // 掷一次掉落表：同种物品合并，稀有度取最高；表不存在时没有掉落，发放的条目数不超过 MAX_ENTRIES
+    pub fn roll(&self, table: &str, ctx: &LootContext, rng: &mut LootRng) -> Vec<LootDrop> {
+        let mut tally = Tally {
+            drops: Vec::new(),
+            left: MAX_ENTRIES,
+        };
+        self.roll_into(table, ctx, rng, Rarity::Common, 0, &mut tally);
+        tally.drops
+    }
+
+    // 递归掷骰并累积结果（rarity 为外层条目的稀有度）
+    fn roll_into(
+        &self,
+        table: &str,
+        ctx: &LootContext,
+        rng: &mut LootRng,
+        rarity: Rarity,
+        depth: usize,
+        out: &mut Tally,
+    ) {
+        let Some(def) = self.get(table).filter(|_| depth < MAX_DEPTH) else {
+            return;
+        };
+        for entry in def.guaranteed.iter().filter(|e| e.applies(ctx)) {
+            self.take(entry, ctx, rng, rarity, depth, out);
+        }
+        let pool: Vec<&LootEntry> = def.pool.iter().filter(|e| e.applies(ctx)).collect();
+        let total: u64 = pool.iter().map(|e| u64::from(e.weight)).sum();
+        if total == 0 {
+            return;
+        }
+        for _ in 0..def.rolls {
+            if out.left == 0 {
+                return;
+            }
+            let mut roll = rng.next_u64() % total;
+            for entry in &pool {
+                let weight = u64::from(entry.weight);
+                if roll < weight {
+                    self.take(entry, ctx, rng, rarity, depth, out);
+                    break;
+                }
+                roll -= weight;
+            }
+        }
+    }
+
+    // 发放一个条目
+    fn take(
+        &self,
+        entry: &LootEntry,
+        ctx: &LootContext,
+        rng: &mut LootRng,
+        rarity: Rarity,
+        depth: usize,
+        out: &mut Tally,
+    ) {
+        if out.left == 0 {
+            return;
+        }
+        out.left -= 1;
+        let rarity = rarity.max(entry.rarity);
+        match &entry.kind {
+            LootKind::Item { item, count, max } => {
+                let max = max.unwrap_or(*count).max(*count);
+                let count = count + rng.below(max.saturating_sub(*count).saturating_add(1));
+                if count == 0 {
+                    return;
+                }
+                match out.drops.iter_mut().find(|d| d.item == *item) {
+                    Some(drop) => {
+                        drop.count = drop.count.saturating_add(count);
+                        drop.rarity = drop.rarity.max(rarity);
+                    }
+                    None => out.drops.push(LootDrop {
+                        item: item.clone(),
+                        count,
+                        rarity,
+                    }),
+                }
+            }
+            LootKind::Table { table } => self.roll_into(table, ctx, rng, rarity, depth + 1, out),
+            LootKind::Nothing => {}
+        }
+    }
+}
+
+// 将掉落放入背包，返回获得物品的描述（稀有物品带标注）与是否有物品因背包已满未能收下
+pub fn grant(drops: &[LootDrop], inventory: &mut Inventory) -> (Vec<String>, bool) {
+    let mut gains = Vec::new();
+    let mut overflow = false;
+    for drop in drops {
+        let kept = drop.count - inventory.add(&drop.item, drop.count);
+        overflow |= kept < drop.count;
+        if kept == 0 {
+            continue;
+        }
+        let name = inventory.name(&drop.item);
+        gains.push(match drop.rarity.label() {
+            Some(label) => format!("【{label}】{name}×{kept}"),
+            None => format!("{name}×{kept}"),
+        });
+    }
+    (gains, overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // 测试用掉落表
+    fn book() -> LootBook {
+        LootBook {
+            tables: serde_json::from_value(json!({
+                "mixed": {
+                    "guaranteed": [{ "type": "item", "item": "stone", "count": 2, "max": 5 }],
+                    "rolls": 3,
+                    "pool": [
+                        { "type": "item", "item": "herb", "weight": 3 },
+                        { "type": "item", "item": "never", "weight": 0 },
+                        { "type": "table", "table": "gems", "weight": 1, "rarity": "rare" }
+                    ]
+                },
+                "gems": {
+                    "pool": [{ "type": "item", "item": "gem" }]
+                },
+                "filtered": {
+                    "guaranteed": [
+                        { "type": "item", "item": "low", "max_realm": 2 },
+                        { "type": "item", "item": "high", "min_realm": 3 },
+                        { "type": "item", "item": "local", "maps": ["town"] }
+                    ],
+                    "pool": [
+                        { "type": "item", "item": "gated", "min_realm": 9 },
+                        { "type": "nothing" }
+                    ]
+                },
+                "nested": {
+                    "guaranteed": [{ "type": "table", "table": "gems", "rarity": "legendary" }]
+                },
+                "huge": {
+                    "guaranteed": [
+                        { "type": "item", "item": "dust", "count": 0, "max": 4294967295u32 },
+                        { "type": "item", "item": "dust", "count": 4294967295u32 }
+                    ]
+                },
+                "fan": {
+                    "rolls": 10,
+                    "pool": [
+                        { "type": "table", "table": "fan" },
+                        { "type": "item", "item": "coin" }
+                    ]
+                },
+                "heavy": {
+                    "pool": [
+                        { "type": "item", "item": "left", "weight": 4294967295u32 },
+                        { "type": "item", "item": "right", "weight": 4294967295u32 }
+                    ]
+                },
+                "loop": {
+                    "guaranteed": [
+                        { "type": "item", "item": "coin" },
+                        { "type": "table", "table": "loop" }
+                    ]
+                }
+            }))
+            .unwrap(),
+        }
+    }
+
+    // 指定境界、不在地图中的上下文
+    fn ctx(realm: usize) -> LootContext<'static> {
+        LootContext { realm, map: None }
+    }
+
+    // 某物品掉落的数量（没有掉落时为 0）
+    fn count(drops: &[LootDrop], item: &str) -> u32 {
+        drops.iter().find(|d| d.item == item).map_or(0, |d| d.count)
+    }
+
+    #[test]
+    fn same_seed_gives_same_drops() {
+        let book = book();
+        for seed in 0..50 {
+            let a = book.roll("mixed", &ctx(0), &mut LootRng::new(seed));
+            let b = book.roll("mixed", &ctx(0), &mut LootRng::new(seed));
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn guaranteed_entries_stay_in_count_range() {
+        let book = book();
+        for seed in 0..200 {
+            let drops = book.roll("mixed", &ctx(0), &mut LootRng::new(seed));
+            assert!((2..=5).contains(&count(&drops, "stone")), "{drops:?}");
+        }
+    }
+
+    #[test]
+    fn weighted_pool_skips_zero_weight_and_rolls_each_time() {
+        let book = book();
+        let mut seen_gem = false;
+        for seed in 0..200 {
+            let drops = book.roll("mixed", &ctx(0), &mut LootRng::new(seed));
+            assert_eq!(count(&drops, "never"), 0);
+            assert_eq!(count(&drops, "herb") + count(&drops, "gem"), 3);
+            seen_gem |= count(&drops, "gem") > 0;
+        }
+        assert!(seen_gem);
+    }
+
+    #[test]
+    fn realm_and_map_filters() {
+        let book = book();
+        let mut rng = LootRng::new(7);
+        let items =
+            |drops: Vec<LootDrop>| -> Vec<String> { drops.into_iter().map(|d| d.item).collect() };
+        assert_eq!(items(book.roll("filtered", &ctx(2), &mut rng)), ["low"]);
+        assert_eq!(items(book.roll("filtered", &ctx(3), &mut rng)), ["high"]);
+        let town = LootContext {
+            realm: 3,
+            map: Some("town"),
+        };
+        assert_eq!(
+            items(book.roll("filtered", &town, &mut rng)),
+            ["high", "local"]
+        );
+        let elsewhere = LootContext {
+            realm: 3,
+            map: Some("cave"),
+        };
+        assert_eq!(items(book.roll("filtered", &elsewhere, &mut rng)), ["high"]);
+    }
+
+    #[test]
+    fn counts_near_the_u32_limit_do_not_overflow() {
+        let book = book();
+        for seed in 0..20 {
+            let drops = book.roll("huge", &ctx(0), &mut LootRng::new(seed));
+            assert_eq!(count(&drops, "dust"), u32::MAX);
+        }
+    }
+
+    #[test]
+    fn pool_weights_past_u32_roll_and_fail_to_load() {
+        let book = book();
+        for seed in 0..20 {
+            let drops = book.roll("heavy", &ctx(0), &mut LootRng::new(seed));
+            assert_eq!(count(&drops, "left") + count(&drops, "right"), 1);
+        }
+        let err = LootBook::from_tables(book.tables).unwrap_err();
+        assert!(err.contains("heavy"), "{err}");
+    }
+
+    #[test]
+    fn wide_self_reference_is_capped_per_roll() {
+        let drops = book().roll("fan", &ctx(0), &mut LootRng::new(3));
+        let coins = count(&drops, "coin");
+        assert!(coins > 0 && coins as usize <= MAX_ENTRIES, "{coins}");
+    }
+
+    #[test]
+    fn nested_tables_inherit_rarity() {
+        let drops = book().roll("nested", &ctx(0), &mut LootRng::new(1));
+        assert_eq!(
+            drops,
+            [LootDrop {
+                item: "gem".to_string(),
+                count: 1,
+                rarity: Rarity::Legendary,
+            }]
+        );
+    }
+
+    #[test]
+    fn self_referencing_table_stops_at_max_depth() {
+        let book = book();
+        let drops = book.roll("loop", &ctx(0), &mut LootRng::new(1));
+        assert_eq!(count(&drops, "coin"), MAX_DEPTH as u32);
+        assert!(book
+            .roll("missing", &ctx(0), &mut LootRng::new(1))
+            .is_empty());
+    }
+}
@@ -1 +1,489 @@
-﻿// 预留：装备/掉落等物品系统后续扩展
+﻿use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::actors::stats::Modifier;
+use crate::assets::load_json;
+use crate::items::equipment::{EquipDef, SetDef};
+use crate::save::SlotSave;
+
+pub mod equipment;
+pub mod loot;
+
+// 背包格数
+pub const INVENTORY_SLOTS: usize = 30;
+
+// 未在物品表中定义的物品的堆叠上限
+const DEFAULT_STACK: u32 = 99;
+
+// 物品类别
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCategory {
+    Currency,
+    Equipment,
+    Consumable,
+    #[default]
+    Material,
+    Quest,
+}
+
+impl ItemCategory {
+    // 类别名称
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemCategory::Currency => "货币",
+            ItemCategory::Equipment => "法器",
+            ItemCategory::Consumable => "丹药",
+            ItemCategory::Material => "材料",
+            ItemCategory::Quest => "信物",
+        }
+    }
+}
+
+// 使用物品的效果
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemUse {
+    // 回复气血
+    Heal {
+        amount: i32,
+    },
+    // 回复灵力
+    RestoreMana {
+        amount: i32,
+    },
+    // 增长修为
+    Exp {
+        amount: u32,
+    },
+    // 限时增益（同名增益刷新时长）
+    Buff {
+        name: String,
+        modifiers: Vec<Modifier>,
+        duration: f32,
+    },
+}
+
+// 物品定义：名称、图标（精灵库 id）、堆叠上限、类别、说明、使用效果、突破加成与装备属性
+#[derive(Clone, Debug, Deserialize)]
+pub struct ItemDef {
+    pub name: String,
+    pub icon: String,
+    #[serde(default = "default_stack")]
+    pub stack: u32,
+    #[serde(default)]
+    pub category: ItemCategory,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "use")]
+    pub uses: Vec<ItemUse>,
+    // 冲击瓶颈时自动服用一枚，提高成功率
+    #[serde(default)]
+    pub breakthrough_bonus: f32,
+    #[serde(default)]
+    pub equip: Option<EquipDef>,
+}
+
+// 默认堆叠上限
+fn default_stack() -> u32 {
+    DEFAULT_STACK
+}
+
+impl ItemDef {
+    // 能否在背包中使用
+    pub fn usable(&self) -> bool {
+        !self.uses.is_empty()
+    }
+}
+
+// 物品表：按 id 索引的物品定义（data/items.json）与套装（data/sets.json）
+#[derive(Clone, Debug, Default)]
+pub struct ItemCatalog {
+    defs: HashMap<String, ItemDef>,
+    sets: HashMap<String, SetDef>,
+}
+
+impl ItemCatalog {
+    // 从JSON文件加载物品表
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            defs: load_json(path)?,
+            sets: HashMap::new(),
+        })
+    }
+
+    // 从JSON文件加载套装定义
+    pub fn load_sets(&mut self, path: &str) -> Result<(), String> {
+        self.sets = load_json(path)?;
+        Ok(())
+    }
+
+    // 按 id 获取物品定义
+    pub fn get(&self, id: &str) -> Option<&ItemDef> {
+        self.defs.get(id)
+    }
+
+    // 物品的装备属性（不可装备时为 None）
+    pub fn equip(&self, id: &str) -> Option<&EquipDef> {
+        self.get(id)?.equip.as_ref()
+    }
+
+    // 按 id 获取套装
+    pub fn set(&self, id: &str) -> Option<&SetDef> {
+        self.sets.get(id)
+    }
+
+    // 物品名称，未定义时返回 id
+    pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.get(id).map_or(id, |def| def.name.as_str())
+    }
+
+    // 堆叠上限（至少为 1）
+    pub fn stack_size(&self, id: &str) -> u32 {
+        self.get(id).map_or(DEFAULT_STACK, |def| def.stack).max(1)
+    }
+}
+
+// 背包中的一格物品
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+// 背包：固定格数，同种物品先补满已有的格子再占用空格
+#[derive(Clone, Debug)]
+pub struct Inventory {
+    catalog: Rc<ItemCatalog>,
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Default for Inventory {
+    // 空背包（未关联物品表时按默认堆叠上限）
+    fn default() -> Self {
+        Self::new(Rc::default())
+    }
+}
+
+impl Inventory {
+    // 以物品表创建空背包
+    pub fn new(catalog: Rc<ItemCatalog>) -> Self {
+        Self {
+            catalog,
+            slots: vec![None; INVENTORY_SLOTS],
+        }
+    }
+
+    // 物品表
+    pub fn catalog(&self) -> &ItemCatalog {
+        &self.catalog
+    }
+
+    // 物品名称
+    pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.catalog.name(id)
+    }
+
+    // 全部格子
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    // 指定格子中的物品
+    pub fn slot(&self, index: usize) -> Option<&ItemStack> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    // 持有指定物品的总数
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|s| s.item == item)
+            .map(|s| s.count)
+            .sum()
+    }
+
+    // 放入物品，返回放不下的数量
+    pub fn add(&mut self, item: &str, count: u32) -> u32 {
+        let stack = self.catalog.stack_size(item);
+        let mut left = count;
+        for slot in self.slots.iter_mut().flatten() {
+            if left == 0 {
+                break;
+            }
+            if slot.item == item && slot.count < stack {
+                let moved = left.min(stack - slot.count);
+                slot.count += moved;
+                left -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|s| s.is_none()) {
+            if left == 0 {
+                break;
+            }
+            let moved = left.min(stack);
+            *slot = Some(ItemStack {
+                item: item.to_string(),
+                count: moved,
+            });
+            left -= moved;
+        }
+        left
+    }
+
+    // 能否一次放下全部物品（物品 id, 数量）
+    pub fn can_fit<'a>(&self, items: impl IntoIterator<Item = (&'a str, u32)>) -> bool {
+        let mut probe = self.clone();
+        items
+            .into_iter()
+            .all(|(item, count)| probe.add(item, count) == 0)
+    }
+
+    // 取出物品（从后往前扣，持有不足时全部取出），返回实际取出的数量
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let mut left = count;
+        for slot in self.slots.iter_mut().rev() {
+            if left == 0 {
+                break;
+            }
+            let Some(stack) = slot.as_mut().filter(|s| s.item == item) else {
+                continue;
+            };
+            let moved = left.min(stack.count);
+            stack.count -= moved;
+            left -= moved;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        count - left
+    }
+
+    // 第一个装有满足条件的已定义物品的格子
+    pub fn find(&self, pred: impl Fn(&ItemDef) -> bool) -> Option<usize> {
+        self.slots.iter().position(|slot| {
+            slot.as_ref()
+                .and_then(|s| self.catalog.get(&s.item))
+                .is_some_and(&pred)
+        })
+    }
+
+    // 从指定格子取出一件，返回物品 id
+    pub fn take_one(&mut self, index: usize) -> Option<String> {
+        let slot = self.slots.get_mut(index)?;
+        let stack = slot.as_mut()?;
+        let item = stack.item.clone();
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(item)
+    }
+
+    // 整理背包：合并同种物品并按物品表类别、id 排序
+    pub fn sort(&mut self) {
+        let mut totals: Vec<(String, u32)> = Vec::new();
+        for stack in self.slots.iter().flatten() {
+            match totals.iter_mut().find(|(item, _)| *item == stack.item) {
+                Some((_, count)) => *count += stack.count,
+                None => totals.push((stack.item.clone(), stack.count)),
+            }
+        }
+        let catalog = Rc::clone(&self.catalog);
+        let category = |id: &str| catalog.get(id).map(|def| def.category as u8);
+        totals.sort_by(|(a, _), (b, _)| (category(a), a).cmp(&(category(b), b)));
+        self.slots = vec![None; self.slots.len()];
+        for (item, count) in totals {
+            self.add(&item, count);
+        }
+    }
+
+    // 从存档恢复各格物品（越界或数量为 0 的记录忽略）
+    pub fn from_save(catalog: Rc<ItemCatalog>, slots: &[SlotSave]) -> Self {
+        let mut inventory = Self::new(catalog);
+        for saved in slots.iter().filter(|s| s.count > 0) {
+            if let Some(slot) = inventory.slots.get_mut(saved.slot) {
+                *slot = Some(ItemStack {
+                    item: saved.item.clone(),
+                    count: saved.count,
+                });
+            }
+        }
+        inventory
+    }
+
+    // 生成存档记录（只记录非空格子）
+    pub fn to_save(&self) -> Vec<SlotSave> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, stack)| {
+                let stack = stack.as_ref()?;
+                Some(SlotSave {
+                    slot,
+                    item: stack.item.clone(),
+                    count: stack.count,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // 测试用物品表：草药每格 10 个、丹药每格 5 个、灵石每格 99 个
+    fn catalog() -> Rc<ItemCatalog> {
+        Rc::new(ItemCatalog {
+            defs: serde_json::from_value(json!({
+                "herb": { "name": "草药", "icon": "herb", "stack": 10 },
+                "pill": { "name": "丹药", "icon": "pill", "stack": 5, "category": "consumable" },
+                "stone": { "name": "灵石", "icon": "stone", "category": "currency" }
+            }))
+            .unwrap(),
+            sets: HashMap::new(),
+        })
+    }
+
+    // 各格物品（空格为 None）
+    fn contents(inventory: &Inventory) -> Vec<Option<(&str, u32)>> {
+        inventory
+            .slots()
+            .iter()
+            .map(|s| s.as_ref().map(|s| (s.item.as_str(), s.count)))
+            .collect()
+    }
+
+    // 装满背包：每格 10 个草药
+    fn full() -> Inventory {
+        let mut inventory = Inventory::new(catalog());
+        assert_eq!(inventory.add("herb", 10 * INVENTORY_SLOTS as u32), 0);
+        inventory
+    }
+
+    #[test]
+    fn fills_partial_stacks_before_empty_slots() {
+        let mut inventory = Inventory::new(catalog());
+        assert_eq!(inventory.add("herb", 4), 0);
+        assert_eq!(inventory.add("pill", 2), 0);
+        assert_eq!(inventory.add("herb", 9), 0);
+        assert_eq!(
+            contents(&inventory)[..4],
+            [
+                Some(("herb", 10)),
+                Some(("pill", 2)),
+                Some(("herb", 3)),
+                None
+            ]
+        );
+        assert_eq!(inventory.count("herb"), 13);
+        assert_eq!(inventory.remove("herb", 5), 5);
+        assert_eq!(
+            contents(&inventory)[..3],
+            [Some(("herb", 8)), Some(("pill", 2)), None]
+        );
+    }
+
+    #[test]
+    fn overflow_is_returned_when_the_bag_is_full() {
+        let mut inventory = full();
+        assert_eq!(inventory.add("herb", 3), 3);
+        assert_eq!(inventory.add("pill", 1), 1);
+        inventory.remove("herb", 4);
+        assert_eq!(inventory.add("herb", 7), 3);
+        assert_eq!(inventory.count("herb"), 10 * INVENTORY_SLOTS as u32);
+    }
+
+    #[test]
+    fn can_fit_checks_everything_together() {
+        let full = full();
+        assert!(!full.can_fit([("pill", 1)]));
+        assert!(full.can_fit([]));
+
+        let mut inventory = full.clone();
+        inventory.remove("herb", 15);
+        // 空出一格又半格：5 个草药补满半格，剩下一格只能放一种物品
+        assert!(inventory.can_fit([("herb", 5), ("pill", 5)]));
+        assert!(!inventory.can_fit([("herb", 5), ("pill", 6)]));
+        assert!(!inventory.can_fit([("pill", 1), ("stone", 1)]));
+        // 同一物品跨格拆分
+        assert!(inventory.can_fit([("herb", 15)]));
+        assert!(!inventory.can_fit([("herb", 10), ("herb", 6)]));
+        // 只是试算，不改变背包
+        assert_eq!(inventory.count("herb"), 10 * INVENTORY_SLOTS as u32 - 15);
+    }
+
+    #[test]
+    fn sort_merges_stacks_in_a_stable_order() {
+        let mut inventory = Inventory::new(catalog());
+        inventory.add("pill", 3);
+        inventory.add("unknown", 2);
+        inventory.add("herb", 6);
+        inventory.add("stone", 40);
+        inventory.add("moss", 1);
+        inventory.remove("pill", 1);
+        inventory.add("pill", 4);
+        inventory.slots[8] = Some(ItemStack {
+            item: "herb".to_string(),
+            count: 5,
+        });
+
+        inventory.sort();
+        let once = inventory.clone();
+        let sorted = contents(&once);
+        // 未定义物品在前（按 id），其后按类别：货币、丹药、材料
+        assert_eq!(
+            sorted[..7],
+            [
+                Some(("moss", 1)),
+                Some(("unknown", 2)),
+                Some(("stone", 40)),
+                Some(("pill", 5)),
+                Some(("pill", 1)),
+                Some(("herb", 10)),
+                Some(("herb", 1)),
+            ]
+        );
+        inventory.sort();
+        assert_eq!(contents(&inventory), sorted);
+    }
+
+    #[test]
+    fn save_round_trip_keeps_slot_positions() {
+        let mut inventory = Inventory::new(catalog());
+        inventory.add("herb", 12);
+        inventory.add("stone", 7);
+        inventory.take_one(0);
+        inventory.remove("herb", 2);
+        let saved = inventory.to_save();
+        assert_eq!(saved.len(), 2);
+        let restored = Inventory::from_save(catalog(), &saved);
+        assert_eq!(contents(&restored), contents(&inventory));
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let parsed: Vec<SlotSave> = serde_json::from_str(&json).unwrap();
+        let restored = Inventory::from_save(catalog(), &parsed);
+        assert_eq!(contents(&restored), contents(&inventory));
+
+        // 越界与数量为 0 的记录忽略
+        let bad = [
+            SlotSave {
+                slot: INVENTORY_SLOTS,
+                item: "herb".to_string(),
+                count: 1,
+            },
+            SlotSave {
+                slot: 3,
+                item: "herb".to_string(),
+                count: 0,
+            },
+        ];
+        assert!(Inventory::from_save(catalog(), &bad).to_save().is_empty());
+    }
+}
@@ -7,6 +7,15 @@ mod ui;
 mod world;
 mod actors;
 mod items;
+// 射击玩法的数据模型与像素资源：大地图只用到境界名称与 PixelSprite，其余保留给射击玩法
+#[allow(dead_code, clippy::manual_clamp)]
+mod model;
+#[allow(dead_code)]
+mod pixel;
+// 射击玩法的玩家档案与排行榜仍保留在存档模块中，大地图使用其中的世界存档
+#[allow(dead_code, clippy::derivable_impls, clippy::manual_unwrap_or_default)]
+mod save;
+mod sprite;
 
 use crate::config::window_conf;
 
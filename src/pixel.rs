@@ -42,6 +42,26 @@ impl PixelSprite {
             }
         }
     }
+
+    // 以单一颜色绘制精灵（粒子等需要统一着色与透明度的场合）
+    pub fn draw_tinted(&self, pos: Vec2, scale: f32, color: Color) {
+        let origin = vec2(
+            pos.x - self.w as f32 * scale * 0.5,
+            pos.y - self.h as f32 * scale * 0.5,
+        );
+        for (y, row) in self.rows.iter().enumerate().take(self.h as usize) {
+            let bytes = row.as_bytes();
+            for x in 0..self.w as usize {
+                let ch = bytes.get(x).copied().unwrap_or(b'.') as char;
+                if ch == '.' {
+                    continue;
+                }
+                let px = origin.x + x as f32 * scale;
+                let py = origin.y + y as f32 * scale;
+                draw_rectangle(px, py, scale, scale, color);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -254,22 +274,7 @@ impl PixelUi {
         let scale = size / self.sparkle.sprite.w as f32;
         let alpha = (particle.life / 0.45).clamp(0.0, 1.0);
         let color = Color::new(particle.color.r, particle.color.g, particle.color.b, alpha);
-        let origin = vec2(
-            particle.pos.x - self.sparkle.sprite.w as f32 * scale * 0.5,
-            particle.pos.y - self.sparkle.sprite.h as f32 * scale * 0.5,
-        );
-        for (y, row) in self.sparkle.sprite.rows.iter().enumerate() {
-            let bytes = row.as_bytes();
-            for x in 0..self.sparkle.sprite.w as usize {
-                let ch = bytes.get(x).copied().unwrap_or(b'.') as char;
-                if ch == '.' {
-                    continue;
-                }
-                let px = origin.x + x as f32 * scale;
-                let py = origin.y + y as f32 * scale;
-                draw_rectangle(px, py, scale, scale, color);
-            }
-        }
+        self.sparkle.sprite.draw_tinted(particle.pos, scale, color);
     }
 }
 
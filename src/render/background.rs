@@ -0,0 +1,162 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::ui::Ui;
+use crate::world::background::{BackgroundLayer, LayerKind};
+use crate::world::map::MapConfig;
+use crate::world::World;
+
+// 背景图片缓存：按路径加载一次，热重载后只补充新出现的图片
+#[derive(Default)]
+pub struct BackgroundTextures {
+    textures: HashMap<String, Texture2D>,
+}
+
+impl BackgroundTextures {
+    // 加载所有地图背景中尚未缓存的图片，失败时输出到标准错误
+    pub async fn load_missing(&mut self, world: &World) {
+        for map in world.maps() {
+            for layer in &map.background {
+                let LayerKind::Image { path, .. } = &layer.kind else {
+                    continue;
+                };
+                if self.textures.contains_key(path) {
+                    continue;
+                }
+                match load_texture(path).await {
+                    Ok(texture) => {
+                        texture.set_filter(FilterMode::Nearest);
+                        self.textures.insert(path.clone(), texture);
+                    }
+                    Err(e) => eprintln!("加载背景图片失败：{path}: {e}"),
+                }
+            }
+        }
+    }
+}
+
+// 绘制地图的全部背景层（view 为当前镜头视野，time 驱动动画）
+pub fn draw_background(
+    ui: &Ui,
+    map: &MapConfig,
+    textures: &BackgroundTextures,
+    view: Rect,
+    time: f32,
+) {
+    for layer in &map.background {
+        draw_layer(ui, layer, textures, view, time);
+    }
+}
+
+// 绘制单个背景层：视差层的原点随镜头按 (1 - parallax) 跟随
+fn draw_layer(
+    ui: &Ui,
+    layer: &BackgroundLayer,
+    textures: &BackgroundTextures,
+    view: Rect,
+    time: f32,
+) {
+    let (offset, alpha) = match &layer.animation {
+        Some(animation) => (animation.offset(time), animation.alpha(time)),
+        None => (Vec2::ZERO, 1.0),
+    };
+    let origin = view.point() * (1.0 - layer.parallax) + offset;
+
+    match &layer.kind {
+        LayerKind::Solid { color } => {
+            draw_rectangle(view.x, view.y, view.w, view.h, fade(color.0, alpha));
+        }
+        LayerKind::Gradient { top, bottom, bands } => {
+            let bands = (*bands).max(1);
+            let band_h = view.h / bands as f32;
+            for i in 0..bands {
+                let t = if bands == 1 {
+                    0.0
+                } else {
+                    i as f32 / (bands - 1) as f32
+                };
+                let color = fade(mix(top.0, bottom.0, t), alpha);
+                // 多画 1 像素避免色带之间出现缝隙
+                draw_rectangle(
+                    view.x,
+                    view.y + i as f32 * band_h,
+                    view.w,
+                    band_h + 1.0,
+                    color,
+                );
+            }
+        }
+        LayerKind::Image {
+            path,
+            offset: image_offset,
+            scale,
+            repeat_x,
+            repeat_y,
+        } => {
+            let Some(texture) = textures.textures.get(path) else {
+                return;
+            };
+            let size = texture.size() * *scale;
+            let start = origin + image_offset.map_or(Vec2::ZERO, |o| o.to_vec2());
+            let xs = repeats(start.x, size.x, view.x, view.w, *repeat_x);
+            let ys = repeats(start.y, size.y, view.y, view.h, *repeat_y);
+            for y in &ys {
+                for x in &xs {
+                    draw_texture_ex(
+                        texture,
+                        *x,
+                        *y,
+                        fade(WHITE, alpha),
+                        DrawTextureParams {
+                            dest_size: Some(size),
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+        LayerKind::Sprite {
+            sprite,
+            pos,
+            scale,
+            spacing,
+        } => {
+            let base = origin + pos.to_vec2();
+            match spacing {
+                // 精灵以中心绘制，向两侧多扩一格避免边缘露出
+                Some(step) => {
+                    for x in repeats(base.x, *step, view.x - step, view.w + step * 2.0, true) {
+                        ui.sprites.draw(sprite, vec2(x, base.y), *scale, None);
+                    }
+                }
+                None => ui.sprites.draw(sprite, base, *scale, None),
+            }
+        }
+    }
+}
+
+// 平铺时覆盖视野所需的起点列表；不平铺时只有原点
+fn repeats(start: f32, step: f32, view_min: f32, view_len: f32, repeat: bool) -> Vec<f32> {
+    if !repeat || step <= 0.0 {
+        return vec![start];
+    }
+    let first = start + ((view_min - start) / step).floor() * step;
+    let count = (view_len / step).ceil() as usize + 1;
+    (0..count).map(|i| first + i as f32 * step).collect()
+}
+
+// 颜色线性插值
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Color::new(
+        a.r + (b.r - a.r) * t,
+        a.g + (b.g - a.g) * t,
+        a.b + (b.b - a.b) * t,
+        a.a + (b.a - a.a) * t,
+    )
+}
+
+// 按系数降低透明度
+fn fade(color: Color, alpha: f32) -> Color {
+    Color::new(color.r, color.g, color.b, color.a * alpha)
+}
@@ -0,0 +1,64 @@
+﻿use macroquad::prelude::*;
+
+use crate::config::{SCREEN_H, SCREEN_W};
+
+// 镜头死区半宽/半高：玩家在死区内移动时镜头不动
+const DEAD_ZONE: Vec2 = vec2(120.0, 70.0);
+
+// 跟随镜头：记录视野中心，按死区跟随玩家并限制在地图范围内
+#[derive(Clone, Copy, Debug)]
+pub struct FollowCamera {
+    center: Vec2,
+}
+
+impl FollowCamera {
+    // 以目标为中心创建镜头
+    pub fn new(target: Vec2, world_size: Vec2) -> Self {
+        let mut camera = Self { center: target };
+        camera.clamp_to(world_size);
+        camera
+    }
+
+    // 立即对准目标（切换地图、读档等瞬移场景）
+    pub fn snap(&mut self, target: Vec2, world_size: Vec2) {
+        self.center = target;
+        self.clamp_to(world_size);
+    }
+
+    // 目标超出死区时推动镜头，再限制在地图边缘内
+    pub fn update(&mut self, target: Vec2, world_size: Vec2) {
+        let offset = target - self.center;
+        if offset.x.abs() > DEAD_ZONE.x {
+            self.center.x += offset.x - DEAD_ZONE.x * offset.x.signum();
+        }
+        if offset.y.abs() > DEAD_ZONE.y {
+            self.center.y += offset.y - DEAD_ZONE.y * offset.y.signum();
+        }
+        self.clamp_to(world_size);
+    }
+
+    // 当前视野矩形（世界坐标）
+    pub fn view_rect(&self) -> Rect {
+        Rect::new(
+            (self.center.x - SCREEN_W * 0.5).round(),
+            (self.center.y - SCREEN_H * 0.5).round(),
+            SCREEN_W,
+            SCREEN_H,
+        )
+    }
+
+    // 镜头边缘限制；地图小于一屏时居中显示
+    fn clamp_to(&mut self, world_size: Vec2) {
+        let half = vec2(SCREEN_W, SCREEN_H) * 0.5;
+        self.center.x = if world_size.x <= SCREEN_W {
+            world_size.x * 0.5
+        } else {
+            self.center.x.clamp(half.x, world_size.x - half.x)
+        };
+        self.center.y = if world_size.y <= SCREEN_H {
+            world_size.y * 0.5
+        } else {
+            self.center.y.clamp(half.y, world_size.y - half.y)
+        };
+    }
+}
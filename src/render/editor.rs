@@ -0,0 +1,133 @@
+﻿use macroquad::prelude::*;
+
+use crate::config::{SCREEN_H, SCREEN_W};
+use crate::systems::editor::{EditorState, Handle};
+use crate::ui::{draw_centered_text, draw_text_ui, Notice, Ui};
+use crate::world::World;
+
+// 选中对象与普通对象的标记颜色
+const SELECTED: Color = Color::new(1.0, 0.85, 0.3, 1.0);
+const SPAWN_COLOR: Color = Color::new(0.4, 0.95, 0.5, 1.0);
+const PORTAL_COLOR: Color = Color::new(0.45, 0.75, 1.0, 1.0);
+const RETURN_COLOR: Color = Color::new(0.45, 0.75, 1.0, 0.45);
+
+// 编辑器按键说明
+const HELP_LINES: [&str; 2] = [
+    "左键拖动  滚轮/[ ] 半径  P 新增传送点  T 切换目标  Delete 删除传送点",
+    "Tab 切换地图  N 新建地图  Ctrl+Delete 删除地图  Ctrl+S 保存  F2/Esc 退出",
+];
+
+// 绘制编辑器的世界坐标标记：地图边界、出生点与传送点（半径、序号与目标）、光标坐标
+pub fn draw_editor_overlay(ui: &Ui, world: &World, state: &EditorState, cursor: Vec2) {
+    let map = world.current_map();
+    let size = map.world_size();
+    draw_rectangle_lines(
+        0.0,
+        0.0,
+        size.x,
+        size.y,
+        2.0,
+        Color::new(1.0, 1.0, 1.0, 0.4),
+    );
+
+    let color_of = |handle: Handle, base: Color| {
+        if state.selected == Some(handle) {
+            SELECTED
+        } else {
+            base
+        }
+    };
+
+    for (idx, portal) in map.portals.iter().enumerate() {
+        let pos = portal.pos.to_vec2();
+        let base = if portal.generated {
+            RETURN_COLOR
+        } else {
+            PORTAL_COLOR
+        };
+        let color = color_of(Handle::Portal(idx), base);
+        draw_circle(
+            pos.x,
+            pos.y,
+            portal.radius,
+            Color::new(color.r, color.g, color.b, 0.15),
+        );
+        draw_circle_lines(pos.x, pos.y, portal.radius, 2.0, color);
+        let label = if portal.generated {
+            format!("#{idx} → {}（回程）", portal.to_map)
+        } else {
+            format!("#{idx} → {}", portal.to_map)
+        };
+        draw_text_ui(
+            ui,
+            &label,
+            pos.x - portal.radius,
+            pos.y - portal.radius - 6.0,
+            14,
+            color,
+        );
+    }
+
+    let spawn = map.spawn.to_vec2();
+    let color = color_of(Handle::Spawn, SPAWN_COLOR);
+    draw_circle_lines(spawn.x, spawn.y, 10.0, 2.0, color);
+    draw_line(spawn.x - 14.0, spawn.y, spawn.x + 14.0, spawn.y, 2.0, color);
+    draw_line(spawn.x, spawn.y - 14.0, spawn.x, spawn.y + 14.0, 2.0, color);
+    draw_text_ui(ui, "出生点", spawn.x + 12.0, spawn.y - 12.0, 14, color);
+
+    let text = format!("({:.0}, {:.0})", cursor.x, cursor.y);
+    draw_text_ui(ui, &text, cursor.x + 12.0, cursor.y + 20.0, 14, WHITE);
+}
+
+// 绘制编辑器面板（屏幕坐标）：当前地图、保存状态、只读原因、选中对象信息、提示消息与按键说明
+pub fn draw_editor_panel(ui: &Ui, world: &World, state: &EditorState, notice: &Notice) {
+    let map = world.current_map();
+    draw_rectangle(0.0, 0.0, SCREEN_W, 60.0, Color::new(0.04, 0.05, 0.1, 0.85));
+    let dirty = if state.dirty { "  [未保存]" } else { "" };
+    let title = format!("地图编辑 · {}（{}）{dirty}", map.name, map.id);
+    draw_text_ui(ui, &title, 16.0, 26.0, 20, Color::new(1.0, 0.85, 0.5, 1.0));
+
+    let (detail, color) = match world.edit_lock(&map.id) {
+        Some(reason) => (format!("只读：{reason}"), Color::new(1.0, 0.45, 0.4, 1.0)),
+        None => (selection_text(world, state), LIGHTGRAY),
+    };
+    draw_text_ui(ui, &detail, 16.0, 50.0, 16, color);
+
+    draw_rectangle(
+        0.0,
+        SCREEN_H - 52.0,
+        SCREEN_W,
+        52.0,
+        Color::new(0.04, 0.05, 0.1, 0.85),
+    );
+    for (i, line) in HELP_LINES.iter().enumerate() {
+        let y = SCREEN_H - 30.0 + i as f32 * 20.0;
+        draw_text_ui(ui, line, 16.0, y, 14, GRAY);
+    }
+
+    if let Some(text) = notice.text() {
+        draw_centered_text(
+            ui,
+            text,
+            SCREEN_H - 70.0,
+            20,
+            Color::new(1.0, 0.8, 0.4, 1.0),
+        );
+    }
+}
+
+// 选中对象的说明文字
+fn selection_text(world: &World, state: &EditorState) -> String {
+    let map = world.current_map();
+    match state.selected {
+        Some(Handle::Spawn) => format!("出生点 ({:.0}, {:.0})", map.spawn.x, map.spawn.y),
+        Some(Handle::Portal(i)) => match map.portals.get(i) {
+            Some(p) => format!(
+                "传送点 #{i} ({:.0}, {:.0}) 半径 {:.0} → {} ({:.0}, {:.0})",
+                p.pos.x, p.pos.y, p.radius, p.to_map, p.to_pos.x, p.to_pos.y
+            ),
+            None => String::new(),
+        },
+        None => "点击出生点或传送点以选中".to_string(),
+    }
+}
@@ -0,0 +1,216 @@
+﻿use macroquad::prelude::*;
+
+use crate::actors::stats::Modifier;
+use crate::config::{SCREEN_H, SCREEN_W};
+use crate::items::equipment::EquipSlot;
+use crate::items::Inventory;
+use crate::model::plane_level_name;
+use crate::systems::inventory::{InventoryScreen, INVENTORY_COLS};
+use crate::ui::{draw_centered_text, draw_text_ui, measure_text_ui, wrap_text, Notice, Ui};
+use crate::world::progress::Progress;
+
+// 格子区域左上角、格子边长与间距（屏幕坐标）
+const GRID_ORIGIN: Vec2 = vec2(110.0, 100.0);
+const CELL: f32 = 52.0;
+const GAP: f32 = 6.0;
+
+// 详情区域
+const DETAIL_AREA: Rect = Rect {
+    x: 500.0,
+    y: 100.0,
+    w: 360.0,
+    h: 196.0,
+};
+
+// 装备栏左上角与栏位间距（与详情区域同宽）
+const EQUIP_ORIGIN: Vec2 = vec2(500.0, 316.0);
+const EQUIP_GAP: f32 = 25.0;
+
+// 格子边框：选中为金色，其余为灰蓝色
+const SELECTED_BORDER: Color = Color::new(1.0, 0.85, 0.45, 1.0);
+const BORDER: Color = Color::new(0.35, 0.42, 0.55, 1.0);
+
+// 绘制背包界面：物品格（图标与数量）、装备栏与套装、选中物品的详情、最近的提示与操作说明
+pub fn draw_inventory(ui: &Ui, progress: &Progress, screen: &InventoryScreen, notice: &Notice) {
+    draw_rectangle(
+        0.0,
+        0.0,
+        SCREEN_W,
+        SCREEN_H,
+        Color::new(0.02, 0.03, 0.08, 0.94),
+    );
+    draw_centered_text(ui, "行囊", 56.0, 32, Color::new(1.0, 0.85, 0.5, 1.0));
+
+    let inventory = &progress.inventory;
+    for (index, slot) in inventory.slots().iter().enumerate() {
+        let col = (index % INVENTORY_COLS) as f32;
+        let row = (index / INVENTORY_COLS) as f32;
+        let pos = GRID_ORIGIN + vec2(col, row) * (CELL + GAP);
+        let selected = !screen.on_equipment && index == screen.selected;
+        let item = slot.as_ref().map(|s| s.item.as_str());
+        draw_cell(ui, inventory, pos, item, selected);
+        let Some(stack) = slot.as_ref().filter(|s| s.count > 1) else {
+            continue;
+        };
+        let text = stack.count.to_string();
+        let dims = measure_text_ui(ui, &text, 16);
+        draw_text_ui(
+            ui,
+            &text,
+            pos.x + CELL - dims.width - 4.0,
+            pos.y + CELL - 4.0,
+            16,
+            WHITE,
+        );
+    }
+
+    // 装备栏：栏位名写在格子下方
+    for (i, slot) in EquipSlot::ALL.into_iter().enumerate() {
+        let pos = EQUIP_ORIGIN + vec2(i as f32 * (CELL + EQUIP_GAP), 0.0);
+        let selected = screen.on_equipment && slot == screen.equip_slot();
+        draw_cell(ui, inventory, pos, progress.equipment.get(slot), selected);
+        let dims = measure_text_ui(ui, slot.label(), 14);
+        draw_text_ui(
+            ui,
+            slot.label(),
+            pos.x + (CELL - dims.width) * 0.5,
+            pos.y + CELL + 18.0,
+            14,
+            LIGHTGRAY,
+        );
+    }
+
+    // 已装备的套装件数与已生效的加成
+    let catalog = inventory.catalog();
+    let mut line_y = EQUIP_ORIGIN.y + CELL + 42.0;
+    for (set, count) in progress.equipment.set_pieces(catalog) {
+        let Some(def) = catalog.set(&set) else {
+            continue;
+        };
+        let total = def.bonuses.iter().map(|b| b.pieces).max().unwrap_or(0);
+        let active: Vec<String> = def
+            .bonuses
+            .iter()
+            .filter(|b| count >= b.pieces)
+            .flat_map(|b| b.modifiers.iter().map(modifier_text))
+            .collect();
+        let mut text = format!("{}（{count}/{total}）", def.name);
+        if !active.is_empty() {
+            text.push_str(&format!("：{}", active.join("  ")));
+        }
+        let color = if active.is_empty() {
+            GRAY
+        } else {
+            Color::new(0.7, 0.95, 0.75, 1.0)
+        };
+        draw_text_ui(ui, &text, EQUIP_ORIGIN.x, line_y, 16, color);
+        line_y += 20.0;
+    }
+
+    // 详情：选中的背包物品或装备
+    let (focused, held) = if screen.on_equipment {
+        (progress.equipment.get(screen.equip_slot()), None)
+    } else {
+        let stack = inventory.slot(screen.selected);
+        (
+            stack.map(|s| s.item.as_str()),
+            stack.map(|s| inventory.count(&s.item)),
+        )
+    };
+    draw_detail(ui, inventory, focused, held);
+
+    if let Some(text) = notice.text() {
+        draw_centered_text(ui, text, 470.0, 20, Color::new(1.0, 0.85, 0.5, 1.0));
+    }
+    draw_centered_text(
+        ui,
+        "Tab 切换装备栏  方向键 选择  E 使用/装备/卸下  X 丢弃  T 整理  I/Esc 关闭",
+        510.0,
+        16,
+        GRAY,
+    );
+}
+
+// 绘制一个物品格（底色、边框与物品图标）
+fn draw_cell(ui: &Ui, inventory: &Inventory, pos: Vec2, item: Option<&str>, selected: bool) {
+    draw_rectangle(pos.x, pos.y, CELL, CELL, Color::new(0.1, 0.12, 0.2, 1.0));
+    let border = if selected { SELECTED_BORDER } else { BORDER };
+    draw_rectangle_lines(pos.x, pos.y, CELL, CELL, 2.0, border);
+    if let Some(item) = item {
+        let icon = inventory
+            .catalog()
+            .get(item)
+            .map_or(item, |def| def.icon.as_str());
+        ui.sprites
+            .draw(icon, pos + vec2(CELL, CELL) * 0.5, 4.0, None);
+    }
+}
+
+// 绘制详情区域：名称、类别与持有数（装备中的物品不显示）、装备属性与说明文字
+fn draw_detail(ui: &Ui, inventory: &Inventory, item: Option<&str>, held: Option<u32>) {
+    let area = DETAIL_AREA;
+    draw_rectangle(
+        area.x,
+        area.y,
+        area.w,
+        area.h,
+        Color::new(0.06, 0.08, 0.14, 1.0),
+    );
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 2.0, BORDER);
+    let Some(item) = item else {
+        draw_text_ui(ui, "（空）", area.x + 16.0, area.y + 34.0, 20, GRAY);
+        return;
+    };
+    let def = inventory.catalog().get(item);
+    draw_text_ui(
+        ui,
+        inventory.name(item),
+        area.x + 16.0,
+        area.y + 34.0,
+        24,
+        SELECTED_BORDER,
+    );
+    let category = def.map_or("未知", |d| d.category.label());
+    let info = match held {
+        Some(count) => format!("{category}  持有 {count}"),
+        None => format!("{category}  已装备"),
+    };
+    draw_text_ui(ui, &info, area.x + 16.0, area.y + 60.0, 18, LIGHTGRAY);
+
+    let mut line_y = area.y + 86.0;
+    if let Some(equip) = inventory.catalog().equip(item) {
+        let mut stats: Vec<String> = equip.modifiers.iter().map(modifier_text).collect();
+        if equip.min_realm > 0 {
+            stats.push(format!("需{}", plane_level_name(equip.min_realm)));
+        }
+        let text = stats.join("  ");
+        for line in wrap_text(ui, &text, 18, area.w - 32.0) {
+            draw_text_ui(
+                ui,
+                &line,
+                area.x + 16.0,
+                line_y,
+                18,
+                Color::new(0.7, 0.95, 0.75, 1.0),
+            );
+            line_y += 22.0;
+        }
+    }
+    let description = def.map_or("", |d| d.description.as_str());
+    for line in wrap_text(ui, description, 16, area.w - 32.0) {
+        draw_text_ui(ui, &line, area.x + 16.0, line_y, 16, WHITE);
+        line_y += 20.0;
+    }
+}
+
+// 属性修正的文字（如“攻击 +8”“身法 +10%”）
+fn modifier_text(modifier: &Modifier) -> String {
+    let mut parts = Vec::new();
+    if modifier.flat != 0 {
+        parts.push(format!("{:+}", modifier.flat));
+    }
+    if modifier.percent != 0.0 {
+        parts.push(format!("{:+.0}%", modifier.percent * 100.0));
+    }
+    format!("{} {}", modifier.stat.label(), parts.join(" "))
+}
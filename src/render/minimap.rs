@@ -0,0 +1,122 @@
+﻿use macroquad::prelude::*;
+
+use crate::config::SCREEN_W;
+use crate::ui::{draw_text_ui, Ui};
+use crate::world::fog::{FogMap, FOG_CELL};
+use crate::world::map::MapConfig;
+use crate::world::progress::Progress;
+
+// 小地图的最大尺寸与距屏幕边缘的距离
+const MINIMAP_MAX: Vec2 = vec2(180.0, 110.0);
+const MINIMAP_MARGIN: f32 = 16.0;
+
+// 绘制右上角小地图：地形、迷雾、已探索区域内的传送点与 NPC、玩家与镜头范围
+pub fn draw_minimap(ui: &Ui, map: &MapConfig, progress: &Progress, player: Vec2, view: Rect) {
+    let world = map.world_size();
+    let scale = (MINIMAP_MAX.x / world.x).min(MINIMAP_MAX.y / world.y);
+    let size = world * scale;
+    let origin = vec2(SCREEN_W - MINIMAP_MARGIN - size.x, MINIMAP_MARGIN);
+    let to_screen = |p: Vec2| origin + p * scale;
+
+    draw_rectangle(
+        origin.x - 3.0,
+        origin.y - 3.0,
+        size.x + 6.0,
+        size.y + 6.0,
+        Color::new(0.02, 0.03, 0.06, 0.8),
+    );
+    draw_rectangle(
+        origin.x,
+        origin.y,
+        size.x,
+        size.y,
+        Color::new(0.16, 0.2, 0.18, 1.0),
+    );
+
+    // 地形：有颜色的瓦片按调色板，阻挡格为浅色
+    if let Some(grid) = &map.tiles {
+        let cell = grid.tile_size * scale;
+        for ty in 0..grid.height as i32 {
+            for tx in 0..grid.width as i32 {
+                let color = match grid.colors.get(&grid.tile(tx, ty)) {
+                    Some(color) => color.0,
+                    None if grid.is_solid(tx, ty) => Color::new(0.55, 0.52, 0.6, 1.0),
+                    None => continue,
+                };
+                let r = grid.tile_rect(tx, ty);
+                let p = to_screen(r.point());
+                draw_rectangle(p.x, p.y, cell, cell, color);
+            }
+        }
+    }
+
+    // 迷雾：未探索的格子涂暗
+    let fog = progress.fog.get(&map.id);
+    draw_fog(fog, origin, scale, size);
+
+    // 已探索区域内的传送点（锁定为灰色）与 NPC
+    let revealed = |p: Vec2| fog.is_some_and(|f| f.revealed_at(p));
+    for (idx, portal) in map.portals.iter().enumerate() {
+        let pos = portal.pos.to_vec2();
+        if !revealed(pos) {
+            continue;
+        }
+        let color = if portal.access(&portal.key(&map.id, idx), progress).is_ok() {
+            SKYBLUE
+        } else {
+            GRAY
+        };
+        let p = to_screen(pos);
+        draw_circle(p.x, p.y, 3.0, color);
+    }
+    let phase = progress.clock.phase();
+    for npc in map.npcs.iter().filter(|npc| npc.is_present(phase)) {
+        let pos = npc.pos.to_vec2();
+        if revealed(pos) {
+            let p = to_screen(pos);
+            draw_rectangle(p.x - 2.0, p.y - 2.0, 4.0, 4.0, YELLOW);
+        }
+    }
+
+    // 镜头范围与玩家位置
+    let v = to_screen(view.point());
+    draw_rectangle_lines(
+        v.x,
+        v.y,
+        view.w * scale,
+        view.h * scale,
+        1.0,
+        Color::new(1.0, 1.0, 1.0, 0.35),
+    );
+    let p = to_screen(player);
+    draw_circle(p.x, p.y, 2.5, WHITE);
+    draw_rectangle_lines(origin.x, origin.y, size.x, size.y, 1.0, LIGHTGRAY);
+
+    let ratio = fog.map_or(0.0, FogMap::explored_ratio);
+    let text = format!("探索 {:.0}%", ratio * 100.0);
+    draw_text_ui(ui, &text, origin.x, origin.y + size.y + 18.0, 14, GRAY);
+}
+
+// 逐格绘制迷雾；尚无记录时整张地图都是迷雾
+fn draw_fog(fog: Option<&FogMap>, origin: Vec2, scale: f32, size: Vec2) {
+    let color = Color::new(0.02, 0.03, 0.06, 0.92);
+    let Some(fog) = fog else {
+        draw_rectangle(origin.x, origin.y, size.x, size.y, color);
+        return;
+    };
+    let cell = FOG_CELL * scale;
+    let (width, height) = fog.size();
+    for y in 0..height {
+        for x in 0..width {
+            if fog.is_revealed(x, y) {
+                continue;
+            }
+            // 最后一行/列的格子可能超出地图，裁剪到小地图范围内
+            let px = x as f32 * cell;
+            let py = y as f32 * cell;
+            let w = cell.min(size.x - px);
+            let h = cell.min(size.y - py);
+            draw_rectangle(origin.x + px, origin.y + py, w, h, color);
+        }
+    }
+}
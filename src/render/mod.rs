@@ -1,31 +1,495 @@
 ﻿use macroquad::prelude::*;
 
 use crate::actors::player::Player;
-use crate::ui::{draw_centered_text, draw_text_ui, Ui};
+use crate::actors::stats::Stat;
+use crate::render::background::{draw_background, BackgroundTextures};
+use crate::systems::combat::Combat;
+use crate::systems::dialogue::DialogueState;
+use crate::systems::portal::PortalPrompt;
+use crate::ui::{
+    draw_centered_text, draw_text_ui, measure_text_ui, wrap_text, MapBanner, Notice, Ui,
+};
+use crate::world::interact::Interactable;
 use crate::world::map::MapConfig;
+use crate::world::progress::Progress;
 
-// 绘制地图背景与名称
-pub fn draw_map(ui: &Ui, map: &MapConfig) {
+pub mod background;
+pub mod camera;
+pub mod editor;
+pub mod inventory;
+pub mod minimap;
+pub mod transition;
+pub mod weather;
+pub mod world_map;
+
+// 绘制地图背景层与瓦片（view 为当前镜头视野）
+pub fn draw_map(ui: &Ui, map: &MapConfig, textures: &BackgroundTextures, view: Rect) {
     clear_background(Color::new(0.05, 0.05, 0.08, 1.0));
-    draw_centered_text(ui, &map.name, 80.0, 36, WHITE);
+    draw_background(ui, map, textures, view, get_time() as f32);
+    draw_tiles(map, view);
+}
+
+// 绘制视野内的瓦片：有颜色的按调色板填充，其余阻挡格按墙体样式绘制
+fn draw_tiles(map: &MapConfig, view: Rect) {
+    let Some(grid) = &map.tiles else {
+        return;
+    };
+    let x0 = grid.tile_coord(view.x).max(0);
+    let y0 = grid.tile_coord(view.y).max(0);
+    let x1 = grid.tile_coord(view.x + view.w).min(grid.width as i32 - 1);
+    let y1 = grid.tile_coord(view.y + view.h).min(grid.height as i32 - 1);
+    for ty in y0..=y1 {
+        for tx in x0..=x1 {
+            let r = grid.tile_rect(tx, ty);
+            let solid = grid.is_solid(tx, ty);
+            match grid.colors.get(&grid.tile(tx, ty)) {
+                Some(color) => draw_rectangle(r.x, r.y, r.w, r.h, color.0),
+                None if solid => {
+                    draw_rectangle(r.x, r.y, r.w, r.h, Color::new(0.22, 0.2, 0.26, 1.0))
+                }
+                None => continue,
+            }
+            if solid {
+                draw_rectangle_lines(r.x, r.y, r.w, r.h, 1.0, Color::new(0.32, 0.3, 0.38, 1.0));
+            }
+        }
+    }
 }
 
-// 绘制传送点位置
-pub fn draw_portals(_ui: &Ui, map: &MapConfig) {
-    for portal in &map.portals {
+// 绘制传送点位置（未解锁的显示为灰色并带锁）
+pub fn draw_portals(_ui: &Ui, map: &MapConfig, progress: &Progress) {
+    for (idx, portal) in map.portals.iter().enumerate() {
         let pos = portal.pos.to_vec2();
-        draw_circle_lines(pos.x, pos.y, portal.radius, 2.0, SKYBLUE);
-        draw_circle(pos.x, pos.y, 4.0, SKYBLUE);
+        let key = portal.key(&map.id, idx);
+        if portal.usable(&key, progress).is_ok() {
+            draw_circle_lines(pos.x, pos.y, portal.radius, 2.0, SKYBLUE);
+            draw_circle(pos.x, pos.y, 4.0, SKYBLUE);
+        } else if portal.access(&key, progress).is_ok() {
+            // 已解锁但不在开放时段或阵资不足：暗淡的阵纹
+            draw_circle_lines(
+                pos.x,
+                pos.y,
+                portal.radius,
+                2.0,
+                Color::new(0.4, 0.55, 0.7, 0.6),
+            );
+        } else {
+            draw_circle_lines(pos.x, pos.y, portal.radius, 2.0, DARKGRAY);
+            draw_lock(pos);
+        }
+    }
+}
+
+// 绘制当前时段在场的 NPC 与头顶名称
+pub fn draw_npcs(ui: &Ui, map: &MapConfig, progress: &Progress) {
+    let phase = progress.clock.phase();
+    for npc in map.npcs.iter().filter(|npc| npc.is_present(phase)) {
+        let pos = npc.pos.to_vec2();
+        ui.sprites.draw(&npc.sprite, pos, npc.scale, None);
+        let dims = measure_text_ui(ui, &npc.name, 16);
+        let y = pos.y - 24.0;
+        draw_text_ui(
+            ui,
+            &npc.name,
+            pos.x - dims.width * 0.5,
+            y,
+            16,
+            Color::new(0.7, 0.95, 0.75, 1.0),
+        );
+    }
+}
+
+// 绘制宝箱、告示牌与存档点
+pub fn draw_props(ui: &Ui, map: &MapConfig, progress: &Progress) {
+    for chest in &map.chests {
+        let sprite = if progress.opened_chests.contains(&chest.key(&map.id)) {
+            "chest_open"
+        } else {
+            "chest"
+        };
+        ui.sprites.draw(sprite, chest.pos.to_vec2(), 2.5, None);
+    }
+    for sign in &map.signs {
+        ui.sprites.draw("sign", sign.pos.to_vec2(), 2.5, None);
+    }
+    for point in &map.save_points {
+        let pos = point.pos.to_vec2();
+        let glow = 0.5 + 0.5 * (get_time() as f32 * 2.0).sin();
+        draw_circle(
+            pos.x,
+            pos.y + 6.0,
+            16.0,
+            Color::new(0.4, 0.8, 1.0, 0.15 + glow * 0.15),
+        );
+        ui.sprites.draw("save_stone", pos, 2.5, None);
+    }
+}
+
+// 在交互目标上方绘制按键提示
+pub fn draw_interact_prompt(ui: &Ui, target: &Interactable) {
+    let text = format!("E: {}", target.prompt);
+    let dims = measure_text_ui(ui, &text, 16);
+    let x = target.pos.x - dims.width * 0.5;
+    let y = target.pos.y - 40.0;
+    draw_rectangle(
+        x - 6.0,
+        y - dims.height - 4.0,
+        dims.width + 12.0,
+        dims.height + 10.0,
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+    draw_text_ui(ui, &text, x, y, 16, Color::new(1.0, 0.95, 0.7, 1.0));
+}
+
+// 绘制锁形图标
+fn draw_lock(pos: Vec2) {
+    let color = Color::new(0.85, 0.35, 0.3, 1.0);
+    draw_circle_lines(pos.x, pos.y - 4.0, 5.0, 2.0, color);
+    draw_rectangle(pos.x - 7.0, pos.y - 2.0, 14.0, 10.0, color);
+}
+
+// 绘制玩家（装备法宝时环绕灵光，受伤无敌时闪烁，挥砍时显示剑光）
+pub fn draw_player(ui: &Ui, player: &Player, combat: &Combat) {
+    let time = get_time() as f32;
+    if let Some(aura) = &player.aura {
+        // 法宝灵光：呼吸的光环，法宝图标绕身旋转
+        let pulse = (time * 3.0).sin() * 0.5 + 0.5;
+        let glow = Color {
+            a: 0.25 + pulse * 0.2,
+            ..aura.color.0
+        };
+        draw_circle(player.pos.x, player.pos.y, 14.0 + pulse * 3.0, glow);
+        draw_circle_lines(player.pos.x, player.pos.y, 18.0, 1.5, aura.color.0);
+        let orbit = player.pos + vec2(time.cos(), time.sin()) * 22.0;
+        ui.sprites.draw(&aura.icon, orbit, 1.5, None);
+    }
+    let blink = combat.is_invulnerable() && (get_time() * 12.0) as i64 % 2 == 0;
+    if !blink {
+        draw_circle(player.pos.x, player.pos.y, 6.0, YELLOW);
+    }
+    if combat.is_slashing() {
+        draw_circle_lines(
+            player.pos.x,
+            player.pos.y,
+            40.0,
+            3.0,
+            Color::new(0.8, 0.95, 1.0, 0.8),
+        );
+    }
+}
+
+// 绘制首领（受击闪白）与头顶名称
+pub fn draw_bosses(ui: &Ui, combat: &Combat) {
+    for boss in &combat.bosses {
+        let flash = boss.is_hurt().then_some(WHITE);
+        ui.sprites
+            .draw(&boss.def.sprite, boss.pos, boss.def.scale, flash);
+        let dims = measure_text_ui(ui, &boss.name, 16);
+        let y = boss.pos.y - boss.def.radius - 8.0;
+        draw_text_ui(
+            ui,
+            &boss.name,
+            boss.pos.x - dims.width * 0.5,
+            y,
+            16,
+            Color::new(1.0, 0.5, 0.4, 1.0),
+        );
+    }
+}
+
+// 绘制野外妖兽，受伤后在头顶显示血条
+pub fn draw_monsters(ui: &Ui, combat: &Combat) {
+    for monster in &combat.monsters {
+        let flash = monster.is_hurt().then_some(WHITE);
+        ui.sprites
+            .draw(&monster.def.sprite, monster.pos, monster.def.scale, flash);
+        let top = monster.pos.y - monster.def.radius - 6.0;
+        if monster.hp < monster.def.hp {
+            draw_bar(
+                monster.pos.x - 14.0,
+                top,
+                28.0,
+                3.0,
+                monster.hp_ratio(),
+                Color::new(0.9, 0.3, 0.25, 1.0),
+            );
+        } else {
+            let dims = measure_text_ui(ui, &monster.def.name, 12);
+            draw_text_ui(
+                ui,
+                &monster.def.name,
+                monster.pos.x - dims.width * 0.5,
+                top,
+                12,
+                Color::new(0.95, 0.75, 0.5, 1.0),
+            );
+        }
+    }
+}
+
+// 绘制敌方弹幕
+pub fn draw_projectiles(combat: &Combat) {
+    for projectile in &combat.projectiles {
+        let pos = projectile.pos;
+        draw_circle(
+            pos.x,
+            pos.y,
+            projectile.radius,
+            Color::new(1.0, 0.35, 0.2, 1.0),
+        );
+        draw_circle(
+            pos.x,
+            pos.y,
+            projectile.radius * 0.5,
+            Color::new(1.0, 0.85, 0.5, 1.0),
+        );
     }
 }
 
-// 绘制玩家
-pub fn draw_player(_ui: &Ui, player: &Player) {
-    draw_circle(player.pos.x, player.pos.y, 6.0, YELLOW);
+// 绘制血条
+fn draw_bar(x: f32, y: f32, w: f32, h: f32, ratio: f32, color: Color) {
+    draw_rectangle(x, y, w, h, Color::new(0.1, 0.1, 0.12, 0.9));
+    draw_rectangle(x, y, w * ratio.clamp(0.0, 1.0), h, color);
+    draw_rectangle_lines(x, y, w, h, 1.0, GRAY);
 }
 
-// 绘制HUD信息
-pub fn draw_hud(ui: &Ui, _map: &MapConfig) {
-    let hint = "E: 传送  方向键/WASD 移动";
-    draw_text_ui(ui, hint, 16.0, 520.0, 20, GRAY);
+// 绘制HUD信息与提示消息
+pub fn draw_hud(
+    ui: &Ui,
+    _map: &MapConfig,
+    player: &Player,
+    combat: &Combat,
+    notice: &Notice,
+    progress: &Progress,
+    meditating: bool,
+) {
+    // 左上角时辰与时段
+    let clock = &progress.clock;
+    let time = format!("{}·{}", clock.shichen(), clock.phase().label());
+    draw_text_ui(ui, &time, 16.0, 32.0, 20, Color::new(0.9, 0.85, 0.7, 1.0));
+
+    let hint =
+        "E: 交互  J/空格: 攻击  R: 打坐  Q: 服丹  B: 突破  C: 属性  I: 背包  M: 舆图  WASD 移动";
+    draw_text_ui(ui, hint, 16.0, 520.0, 16, GRAY);
+
+    // 境界与修为：瓶颈时修为条转为金色并提示突破
+    let cultivation = &progress.cultivation;
+    draw_text_ui(ui, "修为", 16.0, 446.0, 18, WHITE);
+    let (ratio, color) = if cultivation.at_bottleneck() {
+        (1.0, Color::new(1.0, 0.8, 0.3, 1.0))
+    } else {
+        (cultivation.ratio(), Color::new(0.4, 0.75, 1.0, 1.0))
+    };
+    draw_bar(60.0, 436.0, 180.0, 10.0, ratio, color);
+    let mut realm = cultivation.name().to_string();
+    if cultivation.at_bottleneck() {
+        realm.push_str("（瓶颈）");
+    } else if meditating {
+        realm.push_str("（打坐中）");
+    }
+    draw_text_ui(ui, &realm, 16.0, 420.0, 18, Color::new(0.75, 0.9, 1.0, 1.0));
+
+    // 玩家气血与灵力
+    let ratio = player.hp as f32 / player.max_hp() as f32;
+    draw_text_ui(ui, "气血", 16.0, 470.0, 18, WHITE);
+    draw_bar(
+        60.0,
+        458.0,
+        180.0,
+        14.0,
+        ratio,
+        Color::new(0.85, 0.2, 0.25, 1.0),
+    );
+    let ratio = player.mana as f32 / player.max_mana().max(1) as f32;
+    draw_text_ui(ui, "灵力", 16.0, 494.0, 18, WHITE);
+    draw_bar(
+        60.0,
+        484.0,
+        180.0,
+        10.0,
+        ratio,
+        Color::new(0.35, 0.55, 0.95, 1.0),
+    );
+
+    // 首领血条
+    if let Some(boss) = combat.bosses.first() {
+        draw_centered_text(ui, &boss.name, 112.0, 20, Color::new(1.0, 0.5, 0.4, 1.0));
+        let w = 420.0;
+        let x = (crate::config::SCREEN_W - w) * 0.5;
+        draw_bar(
+            x,
+            120.0,
+            w,
+            12.0,
+            boss.hp_ratio(),
+            Color::new(0.9, 0.35, 0.2, 1.0),
+        );
+    }
+
+    if let Some(text) = notice.text() {
+        draw_centered_text(ui, text, 470.0, 24, Color::new(1.0, 0.8, 0.4, 1.0));
+    }
+}
+
+// 绘制属性面板（屏幕坐标）：各项属性（括号内为修正值）、推算出的移速与会心率、生效中的增益
+pub fn draw_stat_panel(ui: &Ui, player: &Player, progress: &Progress) {
+    let stats = &player.stats;
+    let buffs: Vec<(&str, f32)> = stats.buffs().collect();
+    let (x, y, w) = (16.0, 48.0, 220.0);
+    let h = 92.0 + 22.0 * (Stat::ALL.len() + buffs.len()) as f32;
+    draw_rectangle(x, y, w, h, Color::new(0.04, 0.05, 0.1, 0.85));
+    draw_rectangle_lines(x, y, w, h, 2.0, Color::new(0.55, 0.7, 0.9, 1.0));
+    let title = progress.cultivation.name();
+    draw_text_ui(
+        ui,
+        title,
+        x + 14.0,
+        y + 26.0,
+        20,
+        Color::new(1.0, 0.85, 0.45, 1.0),
+    );
+
+    let mut line_y = y + 52.0;
+    for stat in Stat::ALL {
+        let value = stats.get(stat);
+        let bonus = value - stats.base.get(stat);
+        let text = match bonus {
+            0 => format!("{}  {value}", stat.label()),
+            b if b > 0 => format!("{}  {value}（+{b}）", stat.label()),
+            b => format!("{}  {value}（{b}）", stat.label()),
+        };
+        draw_text_ui(ui, &text, x + 14.0, line_y, 18, WHITE);
+        line_y += 22.0;
+    }
+    let derived = format!(
+        "移速 {:.0}  会心 {:.0}%",
+        stats.move_speed(),
+        stats.crit_chance() * 100.0
+    );
+    draw_text_ui(ui, &derived, x + 14.0, line_y, 18, LIGHTGRAY);
+    line_y += 22.0;
+    for (id, remaining) in buffs {
+        let text = format!("{id}  {remaining:.0}秒");
+        draw_text_ui(
+            ui,
+            &text,
+            x + 14.0,
+            line_y,
+            16,
+            Color::new(0.7, 0.95, 0.75, 1.0),
+        );
+        line_y += 22.0;
+    }
+}
+
+// 绘制地图名横幅：屏幕上方的半透明底条与居中地名
+pub fn draw_map_banner(ui: &Ui, banner: &MapBanner) {
+    let Some((text, alpha)) = banner.visible() else {
+        return;
+    };
+    let w = crate::config::SCREEN_W;
+    draw_rectangle(0.0, 48.0, w, 48.0, Color::new(0.0, 0.0, 0.0, 0.45 * alpha));
+    draw_line(
+        0.0,
+        48.0,
+        w,
+        48.0,
+        1.0,
+        Color::new(1.0, 0.85, 0.5, 0.6 * alpha),
+    );
+    draw_line(
+        0.0,
+        96.0,
+        w,
+        96.0,
+        1.0,
+        Color::new(1.0, 0.85, 0.5, 0.6 * alpha),
+    );
+    draw_centered_text(ui, text, 84.0, 36, Color::new(1.0, 1.0, 1.0, alpha));
+}
+
+// 绘制对话框：说话人、正文与可选项
+pub fn draw_dialogue(ui: &Ui, state: &DialogueState, progress: &Progress) {
+    let node = state.node();
+    let (x, y, w, h) = (40.0, 330.0, crate::config::SCREEN_W - 80.0, 190.0);
+    draw_rectangle(x, y, w, h, Color::new(0.04, 0.05, 0.1, 0.92));
+    draw_rectangle_lines(x, y, w, h, 2.0, Color::new(0.55, 0.7, 0.9, 1.0));
+
+    let speaker = node.speaker.as_deref().unwrap_or(&state.npc_name);
+    draw_text_ui(
+        ui,
+        speaker,
+        x + 20.0,
+        y + 32.0,
+        22,
+        Color::new(1.0, 0.85, 0.45, 1.0),
+    );
+
+    let mut line_y = y + 64.0;
+    for line in wrap_text(ui, &node.text, 20, w - 40.0) {
+        draw_text_ui(ui, &line, x + 20.0, line_y, 20, WHITE);
+        line_y += 26.0;
+    }
+
+    let choices = state.visible_choices(progress);
+    for (i, choice) in choices.iter().enumerate() {
+        let selected = i == state.selected;
+        let color = if selected {
+            Color::new(1.0, 0.9, 0.5, 1.0)
+        } else {
+            GRAY
+        };
+        let marker = if selected { "▶ " } else { "  " };
+        draw_text_ui(
+            ui,
+            &format!("{marker}{}", choice.text),
+            x + 36.0,
+            line_y + 4.0,
+            20,
+            color,
+        );
+        line_y += 26.0;
+    }
+
+    let hint = if choices.is_empty() {
+        "E 继续"
+    } else {
+        "↑↓ 选择  E 确认"
+    };
+    let dims = measure_text_ui(ui, hint, 16);
+    draw_text_ui(
+        ui,
+        hint,
+        x + w - dims.width - 16.0,
+        y + h - 12.0,
+        16,
+        DARKGRAY,
+    );
+}
+
+// 绘制传送确认框（屏幕坐标）
+pub fn draw_portal_prompt(ui: &Ui, prompt: &PortalPrompt) {
+    let (w, h) = (420.0, 96.0);
+    let x = (crate::config::SCREEN_W - w) * 0.5;
+    let y = (crate::config::SCREEN_H - h) * 0.5;
+    draw_rectangle(x, y, w, h, Color::new(0.04, 0.05, 0.1, 0.92));
+    draw_rectangle_lines(x, y, w, h, 2.0, Color::new(0.55, 0.7, 0.9, 1.0));
+    draw_centered_text(ui, &prompt.text, y + 40.0, 22, WHITE);
+    draw_centered_text(ui, "E 确认  Esc 取消", y + 74.0, 16, GRAY);
+}
+
+// 绘制地图热重载失败的错误横幅（仅显示前几行）
+pub fn draw_error_banner(ui: &Ui, message: &str) {
+    let lines: Vec<&str> = message.lines().take(4).collect();
+    let height = 16.0 + lines.len() as f32 * 22.0;
+    draw_rectangle(
+        0.0,
+        0.0,
+        crate::config::SCREEN_W,
+        height,
+        Color::new(0.45, 0.05, 0.05, 0.85),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text_ui(ui, line, 16.0, 28.0 + i as f32 * 22.0, 18, WHITE);
+    }
 }
@@ -0,0 +1,76 @@
+﻿use macroquad::prelude::*;
+
+use crate::config::{SCREEN_H, SCREEN_W};
+use crate::systems::transition::Transition;
+use crate::world::map::TransitionKind;
+
+// 圆形收缩的分段数
+const IRIS_SEGMENTS: usize = 48;
+
+// 像素溶解的方块边长
+const DISSOLVE_CELL: f32 = 24.0;
+
+// 绘制过场遮罩（屏幕坐标）；focus 为圆形收缩的圆心，通常是玩家在屏幕上的位置
+pub fn draw_transition(transition: &Transition, focus: Vec2) {
+    let coverage = transition.coverage();
+    if coverage <= 0.0 {
+        return;
+    }
+    match transition.kind {
+        TransitionKind::Fade => draw_rectangle(
+            0.0,
+            0.0,
+            SCREEN_W,
+            SCREEN_H,
+            Color::new(0.0, 0.0, 0.0, coverage),
+        ),
+        TransitionKind::Iris => draw_iris(focus, coverage),
+        TransitionKind::Dissolve => draw_dissolve(coverage),
+    }
+}
+
+// 圆形收缩：圆外涂黑，半径随遮挡程度缩小到零
+fn draw_iris(focus: Vec2, coverage: f32) {
+    let center = vec2(focus.x.clamp(0.0, SCREEN_W), focus.y.clamp(0.0, SCREEN_H));
+    // 外圈取屏幕对角线长度，圆心在屏幕内时足以盖住整个画面
+    let outer = vec2(SCREEN_W, SCREEN_H).length() * 1.1;
+    let inner = outer * (1.0 - coverage);
+    for i in 0..IRIS_SEGMENTS {
+        let a0 = i as f32 / IRIS_SEGMENTS as f32 * std::f32::consts::TAU;
+        let a1 = (i + 1) as f32 / IRIS_SEGMENTS as f32 * std::f32::consts::TAU;
+        let d0 = vec2(a0.cos(), a0.sin());
+        let d1 = vec2(a1.cos(), a1.sin());
+        let (i0, i1) = (center + d0 * inner, center + d1 * inner);
+        let (o0, o1) = (center + d0 * outer, center + d1 * outer);
+        draw_triangle(i0, o0, o1, BLACK);
+        draw_triangle(i0, o1, i1, BLACK);
+    }
+}
+
+// 像素溶解：每个方块有固定的随机阈值，遮挡程度超过阈值即涂黑
+fn draw_dissolve(coverage: f32) {
+    let cols = (SCREEN_W / DISSOLVE_CELL).ceil() as u32;
+    let rows = (SCREEN_H / DISSOLVE_CELL).ceil() as u32;
+    for y in 0..rows {
+        for x in 0..cols {
+            if cell_threshold(x, y) < coverage {
+                draw_rectangle(
+                    x as f32 * DISSOLVE_CELL,
+                    y as f32 * DISSOLVE_CELL,
+                    DISSOLVE_CELL,
+                    DISSOLVE_CELL,
+                    BLACK,
+                );
+            }
+        }
+    }
+}
+
+// 方块坐标散列为 [0, 1) 的阈值，保证每帧溶解顺序一致
+fn cell_threshold(x: u32, y: u32) -> f32 {
+    let mut h = x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0xffff) as f32 / 65_536.0
+}
@@ -0,0 +1,62 @@
+﻿use macroquad::prelude::*;
+
+use crate::pixel::PixelSprite;
+use crate::systems::weather::WeatherState;
+use crate::world::clock::GameClock;
+use crate::world::weather::WeatherKind;
+
+// 天气粒子的像素点阵
+const RAIN_SPRITE: PixelSprite = PixelSprite {
+    w: 1,
+    h: 3,
+    rows: &["*", "*", "*"],
+};
+const SNOW_SPRITE: PixelSprite = PixelSprite {
+    w: 3,
+    h: 3,
+    rows: &[".*.", "***", ".*."],
+};
+const MIST_SPRITE: PixelSprite = PixelSprite {
+    w: 7,
+    h: 4,
+    rows: &["..***..", ".*****.", "*******", ".*****."],
+};
+const PETAL_SPRITE: PixelSprite = PixelSprite {
+    w: 2,
+    h: 2,
+    rows: &["*.", "**"],
+};
+
+// 天气粒子的点阵与颜色
+fn look(kind: WeatherKind) -> (&'static PixelSprite, Color) {
+    match kind {
+        WeatherKind::Rain => (&RAIN_SPRITE, Color::new(0.62, 0.72, 0.95, 0.55)),
+        WeatherKind::Snow => (&SNOW_SPRITE, Color::new(0.95, 0.97, 1.0, 0.9)),
+        WeatherKind::Mist => (&MIST_SPRITE, Color::new(0.85, 0.9, 0.95, 0.1)),
+        WeatherKind::Petals => (&PETAL_SPRITE, Color::new(1.0, 0.72, 0.82, 0.9)),
+    }
+}
+
+// 绘制天气粒子（世界坐标，与像素粒子相同的逐点绘制与透明度衰减）
+pub fn draw_weather(state: &WeatherState) {
+    for particle in state.particles() {
+        let (sprite, color) = look(particle.kind);
+        let alpha = color.a * particle.alpha();
+        if alpha <= 0.0 {
+            continue;
+        }
+        sprite.draw_tinted(
+            particle.pos,
+            particle.scale,
+            Color::new(color.r, color.g, color.b, alpha),
+        );
+    }
+}
+
+// 在视野上叠加昼夜色调
+pub fn draw_day_tint(clock: &GameClock, view: Rect) {
+    let tint = clock.tint();
+    if tint.a > 0.0 {
+        draw_rectangle(view.x, view.y, view.w, view.h, tint);
+    }
+}
@@ -0,0 +1,124 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::config::{SCREEN_H, SCREEN_W};
+use crate::systems::world_map::WorldMapScreen;
+use crate::ui::{draw_centered_text, draw_text_ui, measure_text_ui, Ui};
+use crate::world::progress::Progress;
+use crate::world::World;
+
+// 节点绘制区域（屏幕坐标）与节点半径
+const GRAPH_AREA: Rect = Rect {
+    x: 80.0,
+    y: 90.0,
+    w: SCREEN_W - 160.0,
+    h: 300.0,
+};
+const NODE_RADIUS: f32 = 18.0;
+
+// 绘制世界地图：到访过的地图节点、传送连线、当前位置、选中项与最短路线
+pub fn draw_world_map(ui: &Ui, world: &World, progress: &Progress, screen: &WorldMapScreen) {
+    draw_rectangle(
+        0.0,
+        0.0,
+        SCREEN_W,
+        SCREEN_H,
+        Color::new(0.02, 0.03, 0.08, 0.94),
+    );
+    draw_centered_text(ui, "天下舆图", 56.0, 32, Color::new(1.0, 0.85, 0.5, 1.0));
+
+    // 按列、行均分绘制区域，计算各节点的屏幕位置
+    let cols = screen.nodes.iter().map(|n| n.col + 1).max().unwrap_or(1);
+    let rows = screen.nodes.iter().map(|n| n.row + 1).max().unwrap_or(1);
+    let cell = vec2(GRAPH_AREA.w / cols as f32, GRAPH_AREA.h / rows as f32);
+    let positions: HashMap<&str, Vec2> = screen
+        .nodes
+        .iter()
+        .map(|n| {
+            let pos = GRAPH_AREA.point() + vec2(n.col as f32 + 0.5, n.row as f32 + 0.5) * cell;
+            (n.id.as_str(), pos)
+        })
+        .collect();
+
+    // 传送连线：可通行为蓝色，锁定为灰色；靠近终点处的圆点表示方向
+    for map in world.maps() {
+        let Some(&from) = positions.get(map.id.as_str()) else {
+            continue;
+        };
+        for (idx, portal) in map.portals.iter().enumerate() {
+            let Some(&to) = positions.get(portal.to_map.as_str()) else {
+                continue;
+            };
+            let color = if portal.access(&portal.key(&map.id, idx), progress).is_ok() {
+                Color::new(0.45, 0.7, 0.95, 0.8)
+            } else {
+                Color::new(0.35, 0.35, 0.4, 0.8)
+            };
+            draw_line(from.x, from.y, to.x, to.y, 2.0, color);
+            let tip = from.lerp(to, 0.75);
+            draw_circle(tip.x, tip.y, 3.0, color);
+        }
+    }
+
+    // 选中地图的最短路线（金色加粗）或无法前往的原因
+    let selected = screen.selected_id();
+    let route = selected.map(|id| world.find_route(id, progress));
+    if let Some(Ok(route)) = &route {
+        for pair in route.maps.windows(2) {
+            if let (Some(a), Some(b)) = (
+                positions.get(pair[0].as_str()),
+                positions.get(pair[1].as_str()),
+            ) {
+                draw_line(a.x, a.y, b.x, b.y, 4.0, Color::new(1.0, 0.8, 0.3, 0.9));
+            }
+        }
+    }
+
+    // 地图节点：当前所在地图为金色实心，选中项带外圈
+    let current = world.current_map().id.as_str();
+    let pulse = (get_time() as f32 * 4.0).sin() * 0.5 + 0.5;
+    for node in &screen.nodes {
+        let pos = positions[node.id.as_str()];
+        let fill = if node.id == current {
+            Color::new(1.0, 0.78, 0.3, 1.0)
+        } else {
+            Color::new(0.12, 0.16, 0.26, 1.0)
+        };
+        draw_circle(pos.x, pos.y, NODE_RADIUS, fill);
+        draw_circle_lines(
+            pos.x,
+            pos.y,
+            NODE_RADIUS,
+            2.0,
+            Color::new(0.7, 0.8, 0.95, 1.0),
+        );
+        if Some(node.id.as_str()) == selected {
+            let ring = NODE_RADIUS + 5.0 + pulse * 2.0;
+            draw_circle_lines(pos.x, pos.y, ring, 2.0, WHITE);
+        }
+        let name = world.map_name(&node.id);
+        let dims = measure_text_ui(ui, name, 18);
+        draw_text_ui(
+            ui,
+            name,
+            pos.x - dims.width * 0.5,
+            pos.y + NODE_RADIUS + 22.0,
+            18,
+            WHITE,
+        );
+    }
+
+    // 底部说明：路线或原因，以及操作提示
+    if let (Some(id), Some(route)) = (selected, &route) {
+        let text = match route {
+            Ok(route) => {
+                let names: Vec<&str> = route.maps.iter().map(|m| world.map_name(m)).collect();
+                format!("路线：{}", names.join(" → "))
+            }
+            Err(reason) => format!("{}：{reason}", world.map_name(id)),
+        };
+        draw_centered_text(ui, &text, 446.0, 20, Color::new(1.0, 0.85, 0.5, 1.0));
+    }
+    draw_centered_text(ui, "←→ 选择  E 前往  M/Esc 关闭", 500.0, 18, GRAY);
+}
@@ -1,9 +1,12 @@
-﻿use std::fs;
+﻿use std::collections::BTreeMap;
+use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::items::equipment::EquipSlot;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PlayerProfile {
     pub username: String,
@@ -50,6 +53,64 @@ pub struct Leaderboard {
     pub entries: Vec<ScoreEntry>,
 }
 
+// 大地图存档：所在地图、位置与世界进度（集合按字典序保存，便于比对）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WorldSave {
+    pub map: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub locked_portals: Vec<String>,
+    #[serde(default)]
+    pub defeated_bosses: Vec<String>,
+    #[serde(default)]
+    pub opened_chests: Vec<String>,
+    #[serde(default)]
+    pub fired_triggers: Vec<String>,
+    #[serde(default)]
+    pub visited_maps: Vec<String>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub realm: usize,
+    // 当前境界已积累的修为
+    #[serde(default)]
+    pub exp: u32,
+    // 背包各格物品
+    #[serde(default)]
+    pub inventory: Vec<SlotSave>,
+    // 各装备栏位中的物品
+    #[serde(default)]
+    pub equipment: BTreeMap<EquipSlot, String>,
+    // 各地图的迷雾探索记录
+    #[serde(default)]
+    pub fog: BTreeMap<String, FogSave>,
+    // 游戏内时刻（小时）
+    #[serde(default = "default_time_of_day")]
+    pub time_of_day: f32,
+}
+
+// 旧存档没有时刻记录时从辰时开始
+fn default_time_of_day() -> f32 {
+    8.0
+}
+
+// 背包一格的记录：格子序号、物品 id 与数量
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SlotSave {
+    pub slot: usize,
+    pub item: String,
+    pub count: u32,
+}
+
+// 单张地图的迷雾记录：格子列数、行数与已探索位图（十六进制，逐行排列）
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FogSave {
+    pub width: usize,
+    pub height: usize,
+    pub cells: String,
+}
+
 pub struct SaveStore {
     root: PathBuf,
 }
@@ -77,6 +138,11 @@ impl SaveStore {
         self.root.join("leaderboard.json")
     }
 
+    // 大地图存档路径
+    pub fn world_path(&self) -> PathBuf {
+        self.root.join("world.json")
+    }
+
     // 读取玩家档案
     pub fn load_profile(&self) -> io::Result<Option<PlayerProfile>> {
         let path = self.profile_path();
@@ -118,19 +184,113 @@ impl SaveStore {
         let path = self.leaderboard_path();
         write_json_atomic(&path, leaderboard)
     }
+
+    // 读取大地图存档（不存在时返回 None）；损坏的存档改名为 world.json.bad 备份后返回解析错误
+    pub fn load_world(&self) -> io::Result<Option<WorldSave>> {
+        let path = self.world_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)?;
+        match serde_json::from_str(&text) {
+            Ok(save) => Ok(Some(save)),
+            Err(e) => {
+                let backup = path.with_extension("json.bad");
+                fs::rename(&path, &backup)?;
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{e}（已备份为 {}）", backup.display()),
+                ))
+            }
+        }
+    }
+
+    // 保存大地图存档
+    pub fn save_world(&self, save: &WorldSave) -> io::Result<()> {
+        self.ensure_dirs()?;
+        let path = self.world_path();
+        write_json_atomic(&path, save)
+    }
 }
 
 // 原子写入JSON：先写临时文件，再替换正式文件
 fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
     // 序列化为可读的JSON
     let json = serde_json::to_string_pretty(value)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-    // 临时文件写入成功后再替换，避免损坏
+        .map_err(io::Error::other)?;
+    write_text_atomic(path, &json)
+}
+
+// 原子写入文本：临时文件写入成功后再替换，避免损坏
+pub fn write_text_atomic(path: &Path, text: &str) -> io::Result<()> {
     let tmp = path.with_extension("json.tmp");
-    fs::write(&tmp, json)?;
+    fs::write(&tmp, text)?;
     if path.exists() {
         fs::remove_file(path)?;
     }
     fs::rename(tmp, path)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 临时目录中的存档管理器（每个测试独立目录）
+    fn store(name: &str) -> SaveStore {
+        let root =
+            std::env::temp_dir().join(format!("airfight_save_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        SaveStore { root }
+    }
+
+    #[test]
+    fn world_save_round_trip() {
+        let store = store("round_trip");
+        let save = WorldSave {
+            map: "town".to_string(),
+            x: 12.5,
+            y: 40.0,
+            opened_chests: vec!["town#chest".to_string()],
+            flags: vec!["met_elder".to_string()],
+            realm: 2,
+            exp: 30,
+            inventory: vec![SlotSave {
+                slot: 3,
+                item: "herb".to_string(),
+                count: 7,
+            }],
+            time_of_day: 21.5,
+            ..WorldSave::default()
+        };
+        store.save_world(&save).unwrap();
+        let loaded = store.load_world().unwrap().unwrap();
+        fs::remove_dir_all(&store.root).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&save).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_world_save_is_none() {
+        let store = store("missing");
+        assert!(store.load_world().unwrap().is_none());
+    }
+
+    #[test]
+    fn corrupt_world_save_is_backed_up_and_reported() {
+        let store = store("corrupt");
+        store.ensure_dirs().unwrap();
+        fs::write(store.world_path(), "{ \"map\": ").unwrap();
+        let err = store.load_world().unwrap_err();
+        let backup = store.root.join("world.json.bad");
+        let kept = fs::read_to_string(&backup);
+        let reloaded = store.load_world();
+        fs::remove_dir_all(&store.root).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("world.json.bad"), "{err}");
+        assert_eq!(kept.unwrap(), "{ \"map\": ");
+        assert!(reloaded.unwrap().is_none());
+    }
+}
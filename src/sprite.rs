@@ -0,0 +1,115 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::assets::load_json;
+
+// 十六进制颜色（#RRGGBB 或 #RRGGBBAA），用于数据文件中的调色板
+#[derive(Clone, Copy, Debug)]
+pub struct ColorDef(pub Color);
+
+impl<'de> Deserialize<'de> for ColorDef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse_hex_color(&text)
+            .map(ColorDef)
+            .ok_or_else(|| de::Error::custom(format!("无效颜色：{text}")))
+    }
+}
+
+impl Serialize for ColorDef {
+    // 写回为小写十六进制，不透明时省略透明度
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a]: [u8; 4] = self.0.into();
+        let text = if a == 255 {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        };
+        serializer.serialize_str(&text)
+    }
+}
+
+// 解析十六进制颜色字符串
+pub fn parse_hex_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let (rgb, a) = match hex.len() {
+        6 => (value, 255),
+        8 => (value >> 8, value & 0xff),
+        _ => return None,
+    };
+    Some(Color::from_rgba(
+        (rgb >> 16) as u8,
+        (rgb >> 8 & 0xff) as u8,
+        (rgb & 0xff) as u8,
+        a as u8,
+    ))
+}
+
+// 数据驱动的像素精灵：与 PixelSprite 相同的行字符点阵，调色板来自数据文件
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpriteDef {
+    pub rows: Vec<String>,
+    pub palette: HashMap<char, ColorDef>,
+}
+
+impl SpriteDef {
+    // 精灵宽高（像素格数）
+    pub fn size(&self) -> Vec2 {
+        let w = self.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        vec2(w as f32, self.rows.len() as f32)
+    }
+
+    // 按中心点绘制；flash 不为空时整体改用该颜色（受击闪白等）
+    pub fn draw(&self, pos: Vec2, scale: f32, flash: Option<Color>) {
+        let size = self.size() * scale;
+        let origin = pos - size * 0.5;
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, ch) in row.bytes().enumerate() {
+                if ch == b'.' {
+                    continue;
+                }
+                let Some(color) = self.palette.get(&(ch as char)) else {
+                    continue;
+                };
+                let px = origin.x + x as f32 * scale;
+                let py = origin.y + y as f32 * scale;
+                draw_rectangle(px, py, scale, scale, flash.unwrap_or(color.0));
+            }
+        }
+    }
+}
+
+// 精灵库：按 id 索引的数据精灵（data/sprites.json）
+#[derive(Clone, Debug, Default)]
+pub struct SpriteLibrary {
+    sprites: HashMap<String, SpriteDef>,
+}
+
+impl SpriteLibrary {
+    // 从JSON文件加载精灵库
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            sprites: load_json(path)?,
+        })
+    }
+
+    // 按 id 获取精灵
+    pub fn get(&self, id: &str) -> Option<&SpriteDef> {
+        self.sprites.get(id)
+    }
+
+    // 按 id 绘制精灵，缺失时绘制醒目的占位方块
+    pub fn draw(&self, id: &str, pos: Vec2, scale: f32, flash: Option<Color>) {
+        match self.get(id) {
+            Some(sprite) => sprite.draw(pos, scale, flash),
+            None => {
+                let half = 4.0 * scale;
+                draw_rectangle(pos.x - half, pos.y - half, half * 2.0, half * 2.0, MAGENTA);
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+﻿use macroquad::prelude::*;
+
+use crate::actors::boss::{Boss, BossBook};
+use crate::actors::monster::{Monster, MonsterBook};
+use crate::actors::player::Player;
+use crate::actors::projectile::Projectile;
+use crate::actors::stats::{Stat, CRIT_MULTIPLIER};
+use crate::items::loot::{grant, LootBook, LootContext, LootRng};
+use crate::ui::Notice;
+use crate::world::area::Area;
+use crate::world::clock::DayPhase;
+use crate::world::collision::overlaps_solid;
+use crate::world::map::{MapConfig, Vec2Def};
+use crate::world::progress::Progress;
+
+// 玩家近身攻击参数（伤害取攻击属性）
+const ATTACK_RANGE: f32 = 48.0;
+const ATTACK_COOLDOWN: f32 = 0.35;
+const SLASH_SHOW: f32 = 0.12;
+
+// 受伤后的无敌时间（秒）
+const HURT_INVULNERABLE: f32 = 0.8;
+
+// 妖兽生成：每次尝试的取点次数、与玩家的最小距离、失败后的重试间隔
+const SPAWN_ATTEMPTS: usize = 8;
+const SPAWN_MIN_DISTANCE: f32 = 120.0;
+const SPAWN_RETRY: f32 = 1.0;
+
+// 剧情生成妖兽的散布半径与距玩家的最小距离
+const SCRIPTED_SPREAD: f32 = 100.0;
+const SCRIPTED_MIN_DISTANCE: f32 = 60.0;
+
+// 战斗状态：当前地图的首领、妖兽、弹幕与玩家攻击/受伤计时
+#[derive(Default)]
+pub struct Combat {
+    map_id: Option<String>,
+    pub bosses: Vec<Boss>,
+    pub monsters: Vec<Monster>,
+    pub projectiles: Vec<Projectile>,
+    // 各刷怪区域距下次补充的剩余时间
+    zone_timers: Vec<f32>,
+    attack_cooldown: f32,
+    slash_timer: f32,
+    invulnerable: f32,
+}
+
+impl Combat {
+    // 进入新地图时生成尚未击败的首领，并把当前时段活跃的刷怪区域填满
+    pub fn sync_map(
+        &mut self,
+        map: &MapConfig,
+        book: &BossBook,
+        monsters: &MonsterBook,
+        player: Vec2,
+        progress: &Progress,
+    ) {
+        if self.map_id.as_deref() == Some(map.id.as_str()) {
+            return;
+        }
+        self.map_id = Some(map.id.clone());
+        self.projectiles.clear();
+        let center = map.world_size() * 0.5;
+        self.bosses = map
+            .bosses
+            .iter()
+            .filter(|spawn| !progress.boss_defeated(spawn.name()))
+            .filter_map(|spawn| {
+                let def = book.get(spawn.name())?;
+                Some(Boss::spawn(spawn.name(), def, spawn.pos(center)))
+            })
+            .collect();
+        self.monsters.clear();
+        let phase = progress.clock.phase();
+        for (index, zone) in map.spawn_zones.iter().enumerate() {
+            if !zone.is_active(phase) {
+                continue;
+            }
+            for _ in 0..zone.max_population {
+                if let Some(monster) = spawn_in_zone(map, index, monsters, player, phase) {
+                    self.monsters.push(monster);
+                }
+            }
+        }
+        self.zone_timers = map.spawn_zones.iter().map(|z| z.respawn).collect();
+    }
+
+    // 剧情生成妖兽：在 center 附近散布，活动范围以 center 为中心；返回实际生成数量
+    pub fn spawn_monsters(
+        &mut self,
+        map: &MapConfig,
+        book: &MonsterBook,
+        id: &str,
+        count: u32,
+        center: Vec2,
+        player: Vec2,
+    ) -> u32 {
+        let Some(def) = book.get(id) else {
+            return 0;
+        };
+        let area = Area::Circle {
+            center: Vec2Def::new(center.x, center.y),
+            radius: SCRIPTED_SPREAD,
+        };
+        let mut spawned = 0;
+        for _ in 0..count {
+            if let Some(pos) =
+                find_spawn_point(map, &area, def.radius, player, SCRIPTED_MIN_DISTANCE)
+            {
+                self.monsters
+                    .push(Monster::spawn(def, pos, None, area.clone()));
+                spawned += 1;
+            }
+        }
+        spawned
+    }
+
+    // 清空战斗状态，下次同步时按地图重新生成首领
+    pub fn reset(&mut self) {
+        self.map_id = None;
+    }
+
+    // 是否正在显示挥砍特效
+    pub fn is_slashing(&self) -> bool {
+        self.slash_timer > 0.0
+    }
+
+    // 玩家是否处于受伤无敌
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+}
+
+// 战斗更新：玩家攻击、首领与妖兽行动、弹幕命中、胜负结算与妖兽补充
+pub fn update_combat(
+    combat: &mut Combat,
+    map: &MapConfig,
+    monster_book: &MonsterBook,
+    loot: &LootBook,
+    player: &mut Player,
+    progress: &mut Progress,
+    notice: &mut Notice,
+) {
+    let dt = get_frame_time();
+    combat.attack_cooldown = (combat.attack_cooldown - dt).max(0.0);
+    combat.slash_timer = (combat.slash_timer - dt).max(0.0);
+    combat.invulnerable = (combat.invulnerable - dt).max(0.0);
+
+    // 玩家挥砍：命中范围内的所有首领与妖兽，按神识判定会心
+    let attack = is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Space);
+    if attack && combat.attack_cooldown <= 0.0 {
+        combat.attack_cooldown = ATTACK_COOLDOWN;
+        combat.slash_timer = SLASH_SHOW;
+        let mut damage = player.stats.get(Stat::Attack);
+        if rand::gen_range(0.0, 1.0) < player.stats.crit_chance() {
+            damage = (damage as f32 * CRIT_MULTIPLIER).round() as i32;
+        }
+        for boss in &mut combat.bosses {
+            if boss.pos.distance(player.pos) <= ATTACK_RANGE + boss.def.radius {
+                if let Some(shout) = boss.take_damage(damage) {
+                    notice.show(format!("{}：{shout}", boss.name));
+                }
+            }
+        }
+        for monster in &mut combat.monsters {
+            if monster.pos.distance(player.pos) <= ATTACK_RANGE + monster.def.radius {
+                monster.take_damage(damage);
+            }
+        }
+    }
+
+    // 首领行动
+    for boss in &mut combat.bosses {
+        boss.update(dt, player.pos, map.tiles.as_ref(), &mut combat.projectiles);
+    }
+    for monster in &mut combat.monsters {
+        monster.update(dt, player.pos, map.tiles.as_ref());
+    }
+
+    // 弹幕移动，撞墙或超时即消失
+    for projectile in &mut combat.projectiles {
+        projectile.update(dt);
+    }
+    combat
+        .projectiles
+        .retain(|p| p.life > 0.0 && !hits_wall(map, p.pos));
+
+    // 弹幕与接触伤害（无敌期间不受伤，命中的弹幕会被消耗）
+    if combat.invulnerable <= 0.0 {
+        let mut damage = 0;
+        combat.projectiles.retain(|p| {
+            let hit = p.pos.distance(player.pos) <= p.radius + player.radius;
+            if hit {
+                damage = damage.max(p.damage);
+            }
+            !hit
+        });
+        for boss in &combat.bosses {
+            if boss.pos.distance(player.pos) <= boss.def.radius + player.radius {
+                damage = damage.max(boss.contact_damage());
+            }
+        }
+        for monster in &combat.monsters {
+            if monster.pos.distance(player.pos) <= monster.def.radius + player.radius {
+                damage = damage.max(monster.contact_damage());
+            }
+        }
+        if damage > 0 {
+            player.take_damage(damage);
+            combat.invulnerable = HURT_INVULNERABLE;
+        }
+    }
+
+    // 结算被击败的首领：记录进度、发放掉落与修为
+    for boss in combat.bosses.iter().filter(|b| b.is_dead()) {
+        progress.defeated_bosses.insert(boss.name.clone());
+        notice.show(defeat_message(
+            &boss.name,
+            boss.def.loot.as_deref(),
+            boss.def.exp,
+            loot,
+            &map.id,
+            progress,
+        ));
+    }
+    if combat.bosses.iter().any(|b| b.is_dead()) {
+        combat.bosses.retain(|b| !b.is_dead());
+        combat.projectiles.clear();
+    }
+    for monster in combat.monsters.iter().filter(|m| m.is_dead()) {
+        notice.show(defeat_message(
+            &monster.def.name,
+            monster.def.loot.as_deref(),
+            monster.def.exp,
+            loot,
+            &map.id,
+            progress,
+        ));
+    }
+    combat.monsters.retain(|m| !m.is_dead());
+
+    // 刷怪区域未满时倒计时补充；不在活跃时段的区域妖兽退去
+    let phase = progress.clock.phase();
+    for (index, zone) in map.spawn_zones.iter().enumerate() {
+        if !zone.is_active(phase) {
+            combat.monsters.retain(|m| m.zone != Some(index));
+            continue;
+        }
+        let alive = combat
+            .monsters
+            .iter()
+            .filter(|m| m.zone == Some(index))
+            .count();
+        let Some(timer) = combat.zone_timers.get_mut(index) else {
+            continue;
+        };
+        if alive >= zone.max_population {
+            *timer = zone.respawn;
+            continue;
+        }
+        *timer -= dt;
+        if *timer <= 0.0 {
+            match spawn_in_zone(map, index, monster_book, player.pos, phase) {
+                Some(monster) => {
+                    combat.monsters.push(monster);
+                    *timer = zone.respawn;
+                }
+                None => *timer = SPAWN_RETRY,
+            }
+        }
+    }
+
+    // 玩家倒下：回到出生点调息，首领恢复满血
+    if player.hp <= 0 {
+        player.restore();
+        player.pos = map.spawn.to_vec2();
+        combat.reset();
+        notice.show("你被击败了，回到出生点调息");
+    }
+}
+
+// 掷掉落表、发放掉落与修为并生成击败提示（境界变化时附在末尾）
+fn defeat_message(
+    name: &str,
+    table: Option<&str>,
+    exp: u32,
+    loot: &LootBook,
+    map: &str,
+    progress: &mut Progress,
+) -> String {
+    let ctx = LootContext {
+        realm: progress.cultivation.realm,
+        map: Some(map),
+    };
+    let drops = table.map_or_else(Vec::new, |t| loot.roll(t, &ctx, &mut LootRng::random()));
+    let (mut gains, overflow) = grant(&drops, &mut progress.inventory);
+    if exp > 0 {
+        gains.push(format!("修为 +{exp}"));
+    }
+    let mut text = if gains.is_empty() {
+        format!("击败了{name}")
+    } else {
+        format!("击败了{name}，获得 {}", gains.join("、"))
+    };
+    if let Some(realm) = progress.cultivation.gain(exp) {
+        text.push_str(&format!("，{realm}"));
+    }
+    if overflow {
+        text.push_str("（背包已满）");
+    }
+    text
+}
+
+// 在刷怪区域内挑选不在墙内、远离玩家的位置生成一只当前时段出没的妖兽
+fn spawn_in_zone(
+    map: &MapConfig,
+    index: usize,
+    book: &MonsterBook,
+    avoid: Vec2,
+    phase: DayPhase,
+) -> Option<Monster> {
+    let zone = map.spawn_zones.get(index)?;
+    let def = book.get(zone.pick_monster(phase, &mut LootRng::random())?)?;
+    let pos = find_spawn_point(map, &zone.area, def.radius, avoid, SPAWN_MIN_DISTANCE)?;
+    Some(Monster::spawn(def, pos, Some(index), zone.area.clone()))
+}
+
+// 在区域内随机取一个不在墙内、且与 avoid 保持距离的生成点
+fn find_spawn_point(
+    map: &MapConfig,
+    area: &Area,
+    radius: f32,
+    avoid: Vec2,
+    min_distance: f32,
+) -> Option<Vec2> {
+    (0..SPAWN_ATTEMPTS)
+        .map(|_| area.random_point())
+        .find(|&pos| {
+            let blocked = map
+                .tiles
+                .as_ref()
+                .is_some_and(|grid| overlaps_solid(grid, pos, radius * 0.6));
+            !blocked && pos.distance(avoid) >= min_distance
+        })
+}
+
+// 判断坐标是否位于阻挡格内
+fn hits_wall(map: &MapConfig, pos: Vec2) -> bool {
+    map.tiles
+        .as_ref()
+        .is_some_and(|grid| grid.is_solid(grid.tile_coord(pos.x), grid.tile_coord(pos.y)))
+}
@@ -0,0 +1,174 @@
+﻿use macroquad::prelude::*;
+
+use crate::actors::player::Player;
+use crate::actors::stats::{Modifier, Stat};
+use crate::items::ItemUse;
+use crate::systems::inventory::use_item;
+use crate::ui::Notice;
+use crate::world::cultivation::{Breakthrough, FAIL_HP_LOSS};
+use crate::world::map::MapConfig;
+use crate::world::progress::Progress;
+
+// 打坐每秒所得修为与回复的气血、灵力
+const MEDITATE_EXP: f32 = 3.0;
+const MEDITATE_HEAL: f32 = 8.0;
+const MEDITATE_MANA: f32 = 6.0;
+
+// 突破成功后的增益与失败后的减益：(名称, 属性, 百分比, 持续秒数)
+const BREAKTHROUGH_BUFF: (&str, Stat, f32, f32) = ("境界稳固", Stat::Attack, 0.2, 120.0);
+const BACKLASH_DEBUFF: (&str, Stat, f32, f32) = ("经脉受损", Stat::Agility, -0.3, 60.0);
+
+// 在存档点（灵眼）附近打坐时的修为倍率与判定半径
+const SPRING_BONUS: f32 = 3.0;
+const SPRING_RADIUS: f32 = 60.0;
+
+// 打坐状态：是否在打坐、未满一点的修为、气血与灵力累计、上一帧气血（受伤即中断）
+#[derive(Default)]
+pub struct Meditation {
+    active: bool,
+    exp: f32,
+    heal: f32,
+    mana: f32,
+    hp: i32,
+}
+
+impl Meditation {
+    // 是否正在打坐（打坐时不能移动）
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+// 处理修炼输入与打坐：R 打坐/起身，Q 服用增长修为的丹药，B 冲击瓶颈；移动、攻击或受伤会中断打坐
+pub fn update_cultivation(
+    state: &mut Meditation,
+    player: &mut Player,
+    map: &MapConfig,
+    progress: &mut Progress,
+    notice: &mut Notice,
+) {
+    if is_key_pressed(KeyCode::R) {
+        state.active = !state.active;
+        state.hp = player.hp;
+        if state.active {
+            notice.show("盘膝打坐，吐纳灵气");
+        }
+    }
+    if state.active && (interrupted() || player.hp < state.hp) {
+        state.active = false;
+        notice.show("打坐中断");
+    }
+    if is_key_pressed(KeyCode::Q) {
+        take_pill(player, progress, notice);
+    }
+    if is_key_pressed(KeyCode::B) {
+        state.active = false;
+        attempt_breakthrough(player, progress, notice);
+    }
+    if !state.active {
+        return;
+    }
+
+    // 打坐：按秒累计修为与气血，灵眼旁修为加倍
+    let dt = get_frame_time();
+    let near_spring = map
+        .save_points
+        .iter()
+        .any(|p| p.pos.to_vec2().distance(player.pos) <= SPRING_RADIUS);
+    let rate = if near_spring {
+        MEDITATE_EXP * SPRING_BONUS
+    } else {
+        MEDITATE_EXP
+    };
+    state.exp += rate * dt;
+    state.heal += MEDITATE_HEAL * dt;
+    state.mana += MEDITATE_MANA * dt;
+    player.hp = (player.hp + take_whole(&mut state.heal)).min(player.max_hp());
+    player.mana = (player.mana + take_whole(&mut state.mana)).min(player.max_mana());
+    state.hp = player.hp;
+    let gained = take_whole(&mut state.exp);
+    if let Some(text) = progress.cultivation.gain(gained as u32) {
+        notice.show(text);
+    }
+}
+
+// 取出累计值的整数部分，保留小数部分
+fn take_whole(acc: &mut f32) -> i32 {
+    let whole = acc.floor();
+    *acc -= whole;
+    whole as i32
+}
+
+// 以 (名称, 属性, 百分比, 持续秒数) 为玩家添加限时增减益
+fn apply_buff(player: &mut Player, (id, stat, percent, duration): (&str, Stat, f32, f32)) {
+    let modifier = Modifier {
+        stat,
+        flat: 0,
+        percent,
+    };
+    player.stats.add_buff(id, vec![modifier], duration);
+}
+
+// 是否按下了移动或攻击键
+fn interrupted() -> bool {
+    [
+        KeyCode::Left,
+        KeyCode::Right,
+        KeyCode::Up,
+        KeyCode::Down,
+        KeyCode::W,
+        KeyCode::A,
+        KeyCode::S,
+        KeyCode::D,
+        KeyCode::J,
+        KeyCode::Space,
+    ]
+    .into_iter()
+    .any(is_key_down)
+}
+
+// 服用背包中第一枚增长修为的丹药
+fn take_pill(player: &mut Player, progress: &mut Progress, notice: &mut Notice) {
+    let pill = progress
+        .inventory
+        .find(|def| def.uses.iter().any(|u| matches!(u, ItemUse::Exp { .. })));
+    let Some(index) = pill else {
+        notice.show("身上没有可服用的丹药");
+        return;
+    };
+    match use_item(index, player, progress) {
+        Ok(text) | Err(text) => notice.show(text),
+    }
+}
+
+// 冲击瓶颈：持有辅助突破的丹药时自动服用一枚；失败损失修为与气血
+fn attempt_breakthrough(player: &mut Player, progress: &mut Progress, notice: &mut Notice) {
+    if !progress.cultivation.at_bottleneck() {
+        notice.show("修为未满，尚不能突破");
+        return;
+    }
+    let aid = progress.inventory.find(|def| def.breakthrough_bonus > 0.0);
+    let bonus = aid
+        .and_then(|index| progress.inventory.slot(index))
+        .and_then(|stack| progress.inventory.catalog().get(&stack.item))
+        .map_or(0.0, |def| def.breakthrough_bonus);
+    if let Some(index) = aid {
+        progress.inventory.take_one(index);
+    }
+    let roll = rand::gen_range(0.0, 1.0);
+    match progress.cultivation.breakthrough(roll, bonus) {
+        Ok(Breakthrough::Success) => {
+            player.sync_realm(progress.cultivation.realm);
+            player.restore();
+            apply_buff(player, BREAKTHROUGH_BUFF);
+            notice.show(format!("突破成功，晋入{}", progress.cultivation.name()));
+        }
+        Ok(Breakthrough::Failure { exp_lost }) => {
+            let loss = (player.max_hp() as f32 * FAIL_HP_LOSS) as i32;
+            player.hp = (player.hp - loss).max(1);
+            apply_buff(player, BACKLASH_DEBUFF);
+            notice.show(format!("突破失败，灵力反噬：修为 -{exp_lost}，气血受损"));
+        }
+        Err(reason) => notice.show(reason),
+    }
+}
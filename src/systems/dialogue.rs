@@ -0,0 +1,142 @@
+﻿use macroquad::prelude::*;
+
+use crate::ui::Notice;
+use crate::world::condition::first_unmet;
+use crate::world::dialogue::{DialogueBook, DialogueChoice, DialogueNode};
+use crate::world::effect::apply_each;
+use crate::world::progress::Progress;
+
+// 进行中的对话：所属对话树、当前节点、说话人与选中的选项
+pub struct DialogueState {
+    dialogue: Option<String>,
+    node: DialogueNode,
+    pub npc_name: String,
+    pub selected: usize,
+}
+
+impl DialogueState {
+    // 开始对话：进入起始节点并应用其效果，对话树缺失时返回 None
+    pub fn start(
+        book: &DialogueBook,
+        dialogue: &str,
+        npc_name: &str,
+        progress: &mut Progress,
+        notice: &mut Notice,
+    ) -> Option<Self> {
+        let def = book.get(dialogue)?;
+        let node = def.nodes.get(&def.start)?.clone();
+        let state = Self {
+            dialogue: Some(dialogue.to_string()),
+            node,
+            npc_name: npc_name.to_string(),
+            selected: 0,
+        };
+        state.apply_node_effects(progress, notice);
+        Some(state)
+    }
+
+    // 单页文本（告示牌等），不属于任何对话树
+    pub fn message(speaker: &str, text: &str) -> Self {
+        Self {
+            dialogue: None,
+            node: DialogueNode {
+                speaker: None,
+                text: text.to_string(),
+                effects: Vec::new(),
+                choices: Vec::new(),
+                next: None,
+            },
+            npc_name: speaker.to_string(),
+            selected: 0,
+        }
+    }
+
+    // 当前节点
+    pub fn node(&self) -> &DialogueNode {
+        &self.node
+    }
+
+    // 当前可选的选项（过滤掉条件未满足的）
+    pub fn visible_choices(&self, progress: &Progress) -> Vec<&DialogueChoice> {
+        self.node
+            .choices
+            .iter()
+            .filter(|c| first_unmet(&c.requires, progress).is_none())
+            .collect()
+    }
+
+    // 进入同一对话树中的节点，节点不存在时返回 false
+    fn enter(
+        &mut self,
+        node_id: &str,
+        book: &DialogueBook,
+        progress: &mut Progress,
+        notice: &mut Notice,
+    ) -> bool {
+        let node = self
+            .dialogue
+            .as_deref()
+            .and_then(|id| book.get(id))
+            .and_then(|def| def.nodes.get(node_id));
+        let Some(node) = node else {
+            return false;
+        };
+        self.node = node.clone();
+        self.selected = 0;
+        self.apply_node_effects(progress, notice);
+        true
+    }
+
+    // 应用当前节点的效果
+    fn apply_node_effects(&self, progress: &mut Progress, notice: &mut Notice) {
+        let texts = apply_each(&self.node.effects, progress);
+        if !texts.is_empty() {
+            notice.show(texts.join("，"));
+        }
+    }
+}
+
+// 处理对话输入：上下选择，E/回车/空格确认；对话结束时返回 false
+pub fn update_dialogue(
+    state: &mut DialogueState,
+    book: &DialogueBook,
+    progress: &mut Progress,
+    notice: &mut Notice,
+) -> bool {
+    let count = state.visible_choices(progress).len();
+    if count > 0 {
+        if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+            state.selected = (state.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+            state.selected = (state.selected + 1) % count;
+        }
+        state.selected = state.selected.min(count - 1);
+    }
+
+    let confirm = is_key_pressed(KeyCode::E)
+        || is_key_pressed(KeyCode::Enter)
+        || is_key_pressed(KeyCode::Space);
+    if !confirm {
+        return true;
+    }
+
+    let picked = state
+        .visible_choices(progress)
+        .get(state.selected)
+        .map(|c| (c.effects.clone(), c.next.clone()));
+    let next = match picked {
+        Some((effects, next)) => {
+            let texts = apply_each(&effects, progress);
+            if !texts.is_empty() {
+                notice.show(texts.join("，"));
+            }
+            next
+        }
+        None => state.node.next.clone(),
+    };
+    match next {
+        Some(node_id) => state.enter(&node_id, book, progress, notice),
+        None => false,
+    }
+}
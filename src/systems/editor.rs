@@ -0,0 +1,309 @@
+﻿use macroquad::prelude::*;
+
+use crate::ui::Notice;
+use crate::world::map::{PortalActivation, PortalConfig, TransitionKind, Vec2Def};
+use crate::world::World;
+
+// 编辑器镜头平移速度（像素/秒）
+const PAN_SPEED: f32 = 420.0;
+
+// 出生点的拾取半径、传送点半径的调整步长与范围
+const SPAWN_PICK_RADIUS: f32 = 14.0;
+const RADIUS_STEP: f32 = 2.0;
+const RADIUS_MIN: f32 = 8.0;
+const RADIUS_MAX: f32 = 200.0;
+
+// 新建传送点的默认半径
+const NEW_PORTAL_RADIUS: f32 = 26.0;
+
+// 可拖动的编辑对象
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Handle {
+    Spawn,
+    Portal(usize),
+}
+
+// 编辑器状态：镜头焦点、选中对象、拖动偏移与是否有未保存的修改
+pub struct EditorState {
+    pub focus: Vec2,
+    pub selected: Option<Handle>,
+    pub dirty: bool,
+    // 进入编辑器时所在的地图（退出时判断玩家是否需要回到出生点）
+    pub entry_map: String,
+    drag_offset: Option<Vec2>,
+}
+
+impl EditorState {
+    // 以玩家位置为镜头焦点打开编辑器
+    pub fn open(world: &World, player: Vec2) -> Self {
+        Self {
+            focus: player,
+            selected: None,
+            dirty: false,
+            entry_map: world.current_map().id.clone(),
+            drag_offset: None,
+        }
+    }
+
+    // 数据热重载后丢弃失效的选择（有未保存的修改时不会热重载）
+    pub fn reset(&mut self) {
+        self.selected = None;
+        self.drag_offset = None;
+    }
+}
+
+// 处理编辑器输入，cursor 为鼠标所在的世界坐标；返回 false 表示退出编辑器
+//   左键拖动出生点/传送点，滚轮或 [ ] 调整传送点半径，P 新增传送点，T 切换传送目标，
+//   Delete 删除传送点，Tab 切换地图，N 新建地图，Ctrl+Delete 删除地图，Ctrl+S 保存
+pub fn update_editor(
+    state: &mut EditorState,
+    world: &mut World,
+    cursor: Vec2,
+    notice: &mut Notice,
+) -> bool {
+    if is_key_pressed(KeyCode::F2) || is_key_pressed(KeyCode::Escape) {
+        if state.dirty {
+            notice.show("修改尚未保存（Ctrl+S 保存）");
+        }
+        return false;
+    }
+    let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+
+    if !ctrl {
+        pan_focus(state, world);
+    }
+
+    // 切换、新建与删除地图
+    if is_key_pressed(KeyCode::Tab) {
+        let ids = world.map_ids();
+        let index = ids
+            .iter()
+            .position(|id| *id == world.current_map().id)
+            .unwrap_or(0);
+        let next = if shift {
+            (index + ids.len() - 1) % ids.len()
+        } else {
+            (index + 1) % ids.len()
+        };
+        enter_map(state, world, ids[next].clone());
+    }
+    if is_key_pressed(KeyCode::N) {
+        match world.add_blank_map() {
+            Ok(id) => {
+                notice.show(format!("已新建地图 {id}"));
+                enter_map(state, world, id);
+                state.dirty = true;
+            }
+            Err(e) => notice.show(e),
+        }
+    }
+    if ctrl && is_key_pressed(KeyCode::Delete) {
+        let id = world.current_map().id.clone();
+        match world.remove_map(&id) {
+            Ok(removed) => {
+                notice.show(format!("已删除地图 {id}，移除通往它的传送点 {removed} 处"));
+                let to = world.current_map().id.clone();
+                enter_map(state, world, to);
+                state.dirty = true;
+            }
+            Err(e) => notice.show(e),
+        }
+    }
+    if ctrl && is_key_pressed(KeyCode::S) {
+        match world.save_maps() {
+            Ok(count) => {
+                notice.show(format!("已保存 {count} 张地图"));
+                state.dirty = false;
+            }
+            Err(e) => notice.show(e),
+        }
+    }
+
+    // 以下为修改当前地图的操作，来自其他文件的地图只能查看
+    let lock = world.edit_lock(&world.current_map().id);
+    let wants_edit = is_mouse_button_pressed(MouseButton::Left)
+        || is_key_pressed(KeyCode::P)
+        || (state.selected.is_some() && !ctrl && edit_keys_pressed());
+    if let Some(reason) = lock {
+        if wants_edit {
+            notice.show(reason);
+        }
+        state.drag_offset = None;
+        return true;
+    }
+
+    let map = world.current_map_mut();
+    if is_mouse_button_pressed(MouseButton::Left) {
+        state.selected = pick_handle(map.spawn, &map.portals, cursor);
+        state.drag_offset = state
+            .selected
+            .map(|handle| handle_pos(map.spawn, &map.portals, handle) - cursor);
+    }
+    if !is_mouse_button_down(MouseButton::Left) {
+        state.drag_offset = None;
+    }
+    if let (Some(handle), Some(offset)) = (state.selected, state.drag_offset) {
+        // 拖动时按整像素取值，写回的文件保持整齐
+        let size = map.world_size();
+        let pos = (cursor + offset).round().clamp(Vec2::ZERO, size);
+        let target = match handle {
+            Handle::Spawn => Some(&mut map.spawn),
+            Handle::Portal(i) => map.portals.get_mut(i).map(|p| &mut p.pos),
+        };
+        if let Some(target) = target {
+            if target.to_vec2() != pos {
+                *target = Vec2Def::new(pos.x, pos.y);
+                state.dirty = true;
+            }
+        }
+    }
+
+    // 新增传送点：默认通往排在当前地图之后的地图的出生点
+    if is_key_pressed(KeyCode::P) {
+        let to_map = next_map_id(world, &world.current_map().id);
+        let to_pos = world_spawn(world, &to_map);
+        let id = world.new_portal_id();
+        let map = world.current_map_mut();
+        let pos = cursor.round().clamp(Vec2::ZERO, map.world_size());
+        map.portals.push(PortalConfig {
+            id: Some(id),
+            pos: Vec2Def::new(pos.x, pos.y),
+            radius: NEW_PORTAL_RADIUS,
+            to_map,
+            to_pos,
+            is_unlocked: true,
+            requires: Vec::new(),
+            transition: TransitionKind::Fade,
+            activation: PortalActivation::Interact,
+            paired: false,
+            cost: None,
+            schedule: Vec::new(),
+            confirm: false,
+            generated: false,
+        });
+        state.selected = Some(Handle::Portal(map.portals.len() - 1));
+        state.dirty = true;
+    }
+
+    let Some(Handle::Portal(index)) = state.selected else {
+        return true;
+    };
+    if index >= world.current_map().portals.len() {
+        state.selected = None;
+        return true;
+    }
+
+    // 调整选中传送点的半径
+    let wheel = mouse_wheel().1;
+    let step = if is_key_pressed(KeyCode::RightBracket) || wheel > 0.0 {
+        RADIUS_STEP
+    } else if is_key_pressed(KeyCode::LeftBracket) || wheel < 0.0 {
+        -RADIUS_STEP
+    } else {
+        0.0
+    };
+    if step != 0.0 {
+        let portal = &mut world.current_map_mut().portals[index];
+        portal.radius = (portal.radius + step).clamp(RADIUS_MIN, RADIUS_MAX);
+        state.dirty = true;
+    }
+
+    // 切换传送目标地图，落点设为目标地图出生点
+    if is_key_pressed(KeyCode::T) {
+        let current = world.current_map().portals[index].to_map.clone();
+        let to_map = next_map_id(world, &current);
+        let to_pos = world_spawn(world, &to_map);
+        let portal = &mut world.current_map_mut().portals[index];
+        notice.show(format!("传送目标：{to_map}"));
+        portal.to_map = to_map;
+        portal.to_pos = to_pos;
+        state.dirty = true;
+    }
+
+    if !ctrl && (is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace)) {
+        match world.remove_portal(index) {
+            Ok(()) => {
+                state.selected = None;
+                state.dirty = true;
+            }
+            Err(e) => notice.show(e),
+        }
+    }
+    true
+}
+
+// 方向键/WASD 平移镜头焦点
+fn pan_focus(state: &mut EditorState, world: &World) {
+    let mut dir = vec2(0.0, 0.0);
+    if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
+        dir.x -= 1.0;
+    }
+    if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
+        dir.x += 1.0;
+    }
+    if is_key_down(KeyCode::Up) || is_key_down(KeyCode::W) {
+        dir.y -= 1.0;
+    }
+    if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
+        dir.y += 1.0;
+    }
+    let size = world.current_map().world_size();
+    state.focus = (state.focus + dir * PAN_SPEED * get_frame_time()).clamp(Vec2::ZERO, size);
+}
+
+// 是否按下了修改选中传送点的按键
+fn edit_keys_pressed() -> bool {
+    [
+        KeyCode::LeftBracket,
+        KeyCode::RightBracket,
+        KeyCode::T,
+        KeyCode::Delete,
+        KeyCode::Backspace,
+    ]
+    .into_iter()
+    .any(is_key_pressed)
+}
+
+// 切换到指定地图，镜头对准其出生点
+fn enter_map(state: &mut EditorState, world: &mut World, id: String) {
+    world.switch_map(id);
+    state.focus = world.current_spawn();
+    state.selected = None;
+    state.drag_offset = None;
+}
+
+// 光标下的编辑对象：传送点优先（后添加的在上层），其次为出生点；
+// 自动生成的回程传送点随来源传送点变化，不能单独选中
+fn pick_handle(spawn: Vec2Def, portals: &[PortalConfig], cursor: Vec2) -> Option<Handle> {
+    let portal = portals.iter().rposition(|p| {
+        !p.generated && p.pos.to_vec2().distance(cursor) <= p.radius.max(SPAWN_PICK_RADIUS)
+    });
+    match portal {
+        Some(i) => Some(Handle::Portal(i)),
+        None => (spawn.to_vec2().distance(cursor) <= SPAWN_PICK_RADIUS).then_some(Handle::Spawn),
+    }
+}
+
+// 编辑对象的当前位置
+fn handle_pos(spawn: Vec2Def, portals: &[PortalConfig], handle: Handle) -> Vec2 {
+    match handle {
+        Handle::Spawn => spawn.to_vec2(),
+        Handle::Portal(i) => portals.get(i).map_or(Vec2::ZERO, |p| p.pos.to_vec2()),
+    }
+}
+
+// 按 id 排序时排在 id 之后的地图（循环）
+fn next_map_id(world: &World, id: &str) -> String {
+    let ids = world.map_ids();
+    let index = ids.iter().position(|x| x == id).map_or(0, |i| i + 1);
+    ids[index % ids.len()].clone()
+}
+
+// 指定地图的出生点
+fn world_spawn(world: &World, id: &str) -> Vec2Def {
+    world
+        .maps()
+        .find(|m| m.id == id)
+        .map_or(Vec2Def::new(0.0, 0.0), |m| m.spawn)
+}
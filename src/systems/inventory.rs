@@ -0,0 +1,220 @@
+﻿use macroquad::prelude::*;
+
+use crate::actors::player::Player;
+use crate::items::equipment::EquipSlot;
+use crate::items::{ItemCategory, ItemUse, INVENTORY_SLOTS};
+use crate::model::plane_level_name;
+use crate::ui::Notice;
+use crate::world::progress::Progress;
+
+// 背包界面每行的格数
+pub const INVENTORY_COLS: usize = 6;
+
+// 背包界面：选中的背包格、是否在装备栏以及选中的装备栏位
+#[derive(Default)]
+pub struct InventoryScreen {
+    pub selected: usize,
+    pub on_equipment: bool,
+    pub equip_selected: usize,
+}
+
+impl InventoryScreen {
+    // 选中的装备栏位
+    pub fn equip_slot(&self) -> EquipSlot {
+        EquipSlot::ALL[self.equip_selected % EquipSlot::ALL.len()]
+    }
+}
+
+// 处理背包输入：Tab 切换背包/装备栏，方向键/WASD 选择，E/回车 使用、装备或卸下，X 丢弃一件，T 整理，I/Esc 关闭；返回 false 表示已关闭
+pub fn update_inventory(
+    screen: &mut InventoryScreen,
+    player: &mut Player,
+    progress: &mut Progress,
+    notice: &mut Notice,
+) -> bool {
+    if is_key_pressed(KeyCode::I) || is_key_pressed(KeyCode::Escape) {
+        return false;
+    }
+    if is_key_pressed(KeyCode::Tab) {
+        screen.on_equipment = !screen.on_equipment;
+    }
+    if screen.on_equipment {
+        update_equipment_bar(screen, player, progress, notice);
+        return true;
+    }
+    let col = screen.selected % INVENTORY_COLS;
+    if (is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A)) && col > 0 {
+        screen.selected -= 1;
+    }
+    if (is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D)) && col + 1 < INVENTORY_COLS {
+        screen.selected += 1;
+    }
+    if (is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W))
+        && screen.selected >= INVENTORY_COLS
+    {
+        screen.selected -= INVENTORY_COLS;
+    }
+    if (is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S))
+        && screen.selected + INVENTORY_COLS < INVENTORY_SLOTS
+    {
+        screen.selected += INVENTORY_COLS;
+    }
+
+    if is_key_pressed(KeyCode::E) || is_key_pressed(KeyCode::Enter) {
+        let equippable = progress
+            .inventory
+            .slot(screen.selected)
+            .is_some_and(|s| progress.inventory.catalog().equip(&s.item).is_some());
+        let result = if equippable {
+            equip_item(screen.selected, player, progress)
+        } else {
+            use_item(screen.selected, player, progress)
+        };
+        match result {
+            Ok(text) | Err(text) => notice.show(text),
+        }
+    }
+    if is_key_pressed(KeyCode::X) {
+        if let Some(item) = progress.inventory.take_one(screen.selected) {
+            notice.show(format!("丢弃了 {}×1", progress.inventory.name(&item)));
+        }
+    }
+    if is_key_pressed(KeyCode::T) {
+        progress.inventory.sort();
+        notice.show("背包已整理");
+    }
+    true
+}
+
+// 装备栏输入：左右选择栏位，E/回车 卸下
+fn update_equipment_bar(
+    screen: &mut InventoryScreen,
+    player: &mut Player,
+    progress: &mut Progress,
+    notice: &mut Notice,
+) {
+    let count = EquipSlot::ALL.len();
+    if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+        screen.equip_selected = (screen.equip_selected + count - 1) % count;
+    }
+    if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+        screen.equip_selected = (screen.equip_selected + 1) % count;
+    }
+    if is_key_pressed(KeyCode::E) || is_key_pressed(KeyCode::Enter) {
+        match unequip_item(screen.equip_slot(), player, progress) {
+            Ok(text) | Err(text) => notice.show(text),
+        }
+    }
+}
+
+// 装备背包中指定格子的物品：放入对应栏位，换下的物品放回背包，并更新玩家属性
+pub fn equip_item(
+    index: usize,
+    player: &mut Player,
+    progress: &mut Progress,
+) -> Result<String, String> {
+    let stack = progress.inventory.slot(index).ok_or("这一格是空的")?;
+    let name = progress.inventory.name(&stack.item).to_string();
+    let catalog = progress.inventory.catalog();
+    let def = catalog
+        .equip(&stack.item)
+        .ok_or(format!("{name}无法装备"))?;
+    if progress.cultivation.realm < def.min_realm {
+        let realm = plane_level_name(def.min_realm);
+        return Err(format!("修为不足，需{realm}方可驾驭{name}"));
+    }
+    let slot = progress.equipment.slot_for(def.kind);
+    let Some(item) = progress.inventory.take_one(index) else {
+        return Err("这一格是空的".to_string());
+    };
+    let mut text = format!("装备{name}（{}）", slot.label());
+    if let Some(old) = progress.equipment.equip(slot, &item) {
+        if progress.inventory.add(&old, 1) > 0 {
+            progress.equipment.equip(slot, &old);
+            progress.inventory.add(&item, 1);
+            return Err("背包已满，无法换下原有装备".to_string());
+        }
+        text.push_str(&format!("，换下{}", progress.inventory.name(&old)));
+    }
+    player.sync_equipment(&progress.equipment, progress.inventory.catalog());
+    Ok(text)
+}
+
+// 卸下指定栏位的装备放回背包，并更新玩家属性
+pub fn unequip_item(
+    slot: EquipSlot,
+    player: &mut Player,
+    progress: &mut Progress,
+) -> Result<String, String> {
+    let item = progress
+        .equipment
+        .get(slot)
+        .ok_or(format!("{}栏没有装备", slot.label()))?
+        .to_string();
+    if progress.inventory.add(&item, 1) > 0 {
+        return Err("背包已满，无法卸下".to_string());
+    }
+    progress.equipment.unequip(slot);
+    player.sync_equipment(&progress.equipment, progress.inventory.catalog());
+    Ok(format!("卸下{}", progress.inventory.name(&item)))
+}
+
+// 使用背包中指定格子的一件物品；没有可生效的效果时不消耗并返回原因
+pub fn use_item(
+    index: usize,
+    player: &mut Player,
+    progress: &mut Progress,
+) -> Result<String, String> {
+    let stack = progress.inventory.slot(index).ok_or("这一格是空的")?;
+    let name = progress.inventory.name(&stack.item).to_string();
+    let def = match progress.inventory.catalog().get(&stack.item) {
+        Some(def) if def.usable() => def.clone(),
+        _ => return Err(format!("{name}无法直接使用")),
+    };
+    let verb = if def.category == ItemCategory::Consumable {
+        "服用"
+    } else {
+        "使用"
+    };
+    let useful = def.uses.iter().any(|effect| match effect {
+        ItemUse::Heal { .. } => player.hp < player.max_hp(),
+        ItemUse::RestoreMana { .. } => player.mana < player.max_mana(),
+        ItemUse::Exp { .. } => {
+            !progress.cultivation.at_bottleneck() && !progress.cultivation.is_peak()
+        }
+        ItemUse::Buff { .. } => true,
+    });
+    if !useful {
+        return Err(format!("此时{verb}{name}无益"));
+    }
+
+    progress.inventory.take_one(index);
+    let mut parts = Vec::new();
+    for effect in &def.uses {
+        match effect {
+            ItemUse::Heal { amount } => {
+                let before = player.hp;
+                player.hp = (player.hp + amount).min(player.max_hp());
+                parts.push(format!("气血 +{}", player.hp - before));
+            }
+            ItemUse::RestoreMana { amount } => {
+                let before = player.mana;
+                player.mana = (player.mana + amount).min(player.max_mana());
+                parts.push(format!("灵力 +{}", player.mana - before));
+            }
+            ItemUse::Exp { amount } => match progress.cultivation.gain(*amount) {
+                Some(text) => parts.push(text),
+                None => parts.push(format!("修为 +{amount}")),
+            },
+            ItemUse::Buff {
+                name,
+                modifiers,
+                duration,
+            } => {
+                player.stats.add_buff(name, modifiers.clone(), *duration);
+                parts.push(format!("获得「{name}」{duration:.0} 秒"));
+            }
+        }
+    }
+    Ok(format!("{verb}{name}，{}", parts.join("，")))
+}
@@ -1,10 +1,31 @@
 ﻿use macroquad::prelude::*;
 
 use crate::actors::player::Player;
+use crate::items::loot::{grant, LootBook, LootContext, LootRng};
+use crate::systems::dialogue::DialogueState;
+use crate::ui::Notice;
+use crate::world::collision::move_and_slide;
+use crate::world::condition::first_unmet;
+use crate::world::dialogue::DialogueBook;
+use crate::world::effect::{apply_each, fits};
+use crate::world::interact::{InteractKind, Interactable};
+use crate::world::map::MapConfig;
+use crate::world::progress::Progress;
 use crate::world::World;
 
-// 处理玩家移动输入
-pub fn handle_movement(player: &mut Player) {
+pub mod combat;
+pub mod cultivation;
+pub mod dialogue;
+pub mod editor;
+pub mod inventory;
+pub mod portal;
+pub mod transition;
+pub mod trigger;
+pub mod weather;
+pub mod world_map;
+
+// 处理玩家移动输入（有瓦片网格时沿墙滑动）
+pub fn handle_movement(player: &mut Player, map: &MapConfig) {
     let mut dir = vec2(0.0, 0.0);
     if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
         dir.x -= 1.0;
@@ -21,22 +42,83 @@ pub fn handle_movement(player: &mut Player) {
     if dir.length_squared() > 0.0 {
         dir = dir.normalize();
     }
-    player.pos += dir * player.speed * get_frame_time();
+    let delta = dir * player.speed() * get_frame_time();
+    player.pos = match &map.tiles {
+        Some(grid) => move_and_slide(grid, player.pos, delta, player.radius),
+        None => player.pos + delta,
+    };
 
-    // 简单边界限制
-    player.pos.x = player.pos.x.clamp(12.0, crate::config::SCREEN_W - 12.0);
-    player.pos.y = player.pos.y.clamp(12.0, crate::config::SCREEN_H - 12.0);
+    // 地图边界限制；地图比玩家还窄时按玩家直径处理，避免上下限颠倒
+    let margin = player.radius;
+    let size = map.world_size().max(Vec2::splat(2.0 * margin));
+    player.pos.x = player.pos.x.clamp(margin, size.x - margin);
+    player.pos.y = player.pos.y.clamp(margin, size.y - margin);
 }
 
-// 处理交互输入（当前仅传送点）
-pub fn handle_interaction(world: &mut World, player: &mut Player) {
+// 处理交互输入：对当前选中的交互目标执行对应动作，返回本帧执行的交互类型
+pub fn handle_interaction(
+    target: Option<&Interactable>,
+    world: &World,
+    progress: &mut Progress,
+    notice: &mut Notice,
+    dialogues: &DialogueBook,
+    dialogue: &mut Option<DialogueState>,
+    loot: &LootBook,
+) -> Option<InteractKind> {
     if !is_key_pressed(KeyCode::E) {
-        return;
+        return None;
     }
+    let target = target?;
+    let map = world.current_map();
 
-    // 若在传送点范围内则切换地图
-    if let Some((target_map, target_pos)) = world.try_teleport(player.pos) {
-        player.pos = target_pos;
-        world.switch_map(target_map);
+    match target.kind {
+        InteractKind::Npc => {
+            let npc = &map.npcs[target.index];
+            *dialogue = DialogueState::start(dialogues, &npc.dialogue, &npc.name, progress, notice);
+        }
+        InteractKind::Chest => {
+            let chest = &map.chests[target.index];
+            if let Some(condition) = first_unmet(&chest.requires, progress) {
+                notice.show(condition.describe(progress));
+                return Some(target.kind);
+            }
+            // 先掷出掉落，背包放不下全部物品时不开启，以免物品丢失
+            let drops = match &chest.loot {
+                Some(table) => {
+                    let ctx = LootContext {
+                        realm: progress.cultivation.realm,
+                        map: Some(&map.id),
+                    };
+                    loot.roll(table, &ctx, &mut LootRng::random())
+                }
+                None => Vec::new(),
+            };
+            let dropped = drops.iter().map(|d| (d.item.as_str(), d.count));
+            if !fits(&chest.contents, &progress.inventory, dropped) {
+                notice.show("背包已满，腾出空位再开启宝箱");
+                return Some(target.kind);
+            }
+            progress.opened_chests.insert(chest.key(&map.id));
+            let mut texts = apply_each(&chest.contents, progress);
+            let (gains, _) = grant(&drops, &mut progress.inventory);
+            if !gains.is_empty() {
+                texts.push(format!("获得 {}", gains.join("、")));
+            }
+            if texts.is_empty() {
+                notice.show("宝箱是空的");
+            } else {
+                notice.show(texts.join("，"));
+            }
+        }
+        // 存档点的回复与存档由调用方处理
+        InteractKind::SavePoint => {}
+        InteractKind::Sign => {
+            let sign = &map.signs[target.index];
+            let title = sign.title.as_deref().unwrap_or("告示");
+            *dialogue = Some(DialogueState::message(title, &sign.text));
+        }
+        // 传送（确认框、阵资与过场）由调用方处理
+        InteractKind::Portal => {}
     }
+    Some(target.kind)
 }
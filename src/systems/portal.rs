@@ -0,0 +1,106 @@
+﻿use macroquad::prelude::*;
+
+use crate::systems::transition::Transition;
+use crate::ui::Notice;
+use crate::world::map::PortalActivation;
+use crate::world::progress::Progress;
+use crate::world::{Teleport, World};
+
+// 传送确认框：传送点在当前地图中的序号与询问文字
+pub struct PortalPrompt {
+    pub index: usize,
+    pub text: String,
+}
+
+// 踩踏式传送点的状态：玩家当前所在的传送点，只在踏入时触发一次
+#[derive(Default)]
+pub struct PortalTouch {
+    map: Option<String>,
+    inside: Option<usize>,
+}
+
+// 使用传送点：不可用时提示原因，需要确认时打开确认框，否则直接开始过场
+pub fn use_portal(
+    index: usize,
+    world: &World,
+    progress: &mut Progress,
+    notice: &mut Notice,
+    prompt: &mut Option<PortalPrompt>,
+    transition: &mut Option<Transition>,
+) {
+    let Some(portal) = world.current_map().portals.get(index) else {
+        return;
+    };
+    match world.teleport_via(index, progress) {
+        Some(Teleport::Go { .. }) if portal.confirm => {
+            let mut text = format!("前往{}？", world.map_name(&portal.to_map));
+            if let Some(cost) = &portal.cost {
+                let name = progress.inventory.name(&cost.item);
+                text.push_str(&format!("（消耗 {name}×{}）", cost.count));
+            }
+            *prompt = Some(PortalPrompt { index, text });
+        }
+        Some(Teleport::Go { .. }) => commit(index, world, progress, notice, transition),
+        Some(Teleport::Locked { reason }) => notice.show(reason),
+        None => {}
+    }
+}
+
+// 处理确认框输入：E/Enter/Y 确认，Esc/N 取消；返回 false 表示确认框已关闭
+pub fn update_portal_prompt(
+    prompt: &PortalPrompt,
+    world: &World,
+    progress: &mut Progress,
+    notice: &mut Notice,
+    transition: &mut Option<Transition>,
+) -> bool {
+    let confirm = [KeyCode::E, KeyCode::Enter, KeyCode::Y]
+        .into_iter()
+        .any(is_key_pressed);
+    if confirm {
+        commit(prompt.index, world, progress, notice, transition);
+        return false;
+    }
+    !(is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::N))
+}
+
+// 检查踩踏式传送点：返回玩家本帧踏入的传送点序号；刚进入地图时站在传送点上不触发
+pub fn update_portal_touch(touch: &mut PortalTouch, world: &World, pos: Vec2) -> Option<usize> {
+    let map = world.current_map();
+    let inside = map
+        .portals
+        .iter()
+        .position(|p| p.activation == PortalActivation::Touch && p.contains(pos));
+    let entered = touch.map.as_deref() == Some(map.id.as_str()) && touch.inside != inside;
+    touch.map = Some(map.id.clone());
+    touch.inside = inside;
+    inside.filter(|_| entered)
+}
+
+// 确认传送：重新判定并扣除阵资后开始过场；解锁状态不写入进度（存档只记录被剧情封锁的传送点）
+fn commit(
+    index: usize,
+    world: &World,
+    progress: &mut Progress,
+    notice: &mut Notice,
+    transition: &mut Option<Transition>,
+) {
+    match world.try_teleport(index, progress) {
+        Some((
+            Teleport::Go {
+                to_map,
+                to_pos,
+                transition: kind,
+                ..
+            },
+            paid,
+        )) => {
+            if let Some(text) = paid {
+                notice.show(text);
+            }
+            *transition = Some(Transition::new(kind, to_map, to_pos));
+        }
+        Some((Teleport::Locked { reason }, _)) => notice.show(reason),
+        None => {}
+    }
+}
@@ -0,0 +1,144 @@
+﻿use macroquad::prelude::*;
+
+use crate::world::map::TransitionKind;
+
+// 过场各阶段时长（秒）：遮住画面、黑屏载入、揭开画面
+const COVER_SECS: f32 = 0.45;
+const HOLD_SECS: f32 = 0.15;
+const REVEAL_SECS: f32 = 0.45;
+
+// 过场阶段
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Phase {
+    Cover,
+    Hold,
+    Reveal,
+}
+
+// 切换地图过场：遮住画面后载入目标地图，再揭开画面；进行中冻结输入
+pub struct Transition {
+    pub kind: TransitionKind,
+    phase: Phase,
+    elapsed: f32,
+    // 尚未载入的目标地图与落点
+    destination: Option<(String, Vec2)>,
+}
+
+// 过场每帧推进的结果
+pub enum TransitionStep {
+    Running,
+    // 画面刚好完全遮住，应在此时切换到目标地图
+    Load { to_map: String, to_pos: Vec2 },
+    Finished,
+}
+
+impl Transition {
+    // 开始前往目标地图的过场
+    pub fn new(kind: TransitionKind, to_map: String, to_pos: Vec2) -> Self {
+        Self {
+            kind,
+            phase: Phase::Cover,
+            elapsed: 0.0,
+            destination: Some((to_map, to_pos)),
+        }
+    }
+
+    // 尚未载入的目标地图
+    pub fn destination(&self) -> Option<&str> {
+        self.destination.as_ref().map(|(to_map, _)| to_map.as_str())
+    }
+
+    // 推进计时并在阶段切换时返回载入或结束事件
+    pub fn update(&mut self, dt: f32) -> TransitionStep {
+        self.elapsed += dt;
+        match self.phase {
+            Phase::Cover if self.elapsed >= COVER_SECS => {
+                self.phase = Phase::Hold;
+                self.elapsed = 0.0;
+                if let Some((to_map, to_pos)) = self.destination.take() {
+                    return TransitionStep::Load { to_map, to_pos };
+                }
+            }
+            Phase::Hold if self.elapsed >= HOLD_SECS => {
+                self.phase = Phase::Reveal;
+                self.elapsed = 0.0;
+            }
+            Phase::Reveal if self.elapsed >= REVEAL_SECS => return TransitionStep::Finished,
+            _ => {}
+        }
+        TransitionStep::Running
+    }
+
+    // 画面遮挡程度：0 为完全可见，1 为完全遮住
+    pub fn coverage(&self) -> f32 {
+        match self.phase {
+            Phase::Cover => smoothstep(self.elapsed / COVER_SECS),
+            Phase::Hold => 1.0,
+            Phase::Reveal => 1.0 - smoothstep(self.elapsed / REVEAL_SECS),
+        }
+    }
+}
+
+// 平滑插值曲线，让遮挡在首尾放缓
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按固定步长推进，返回各步结果的简写：r 进行中、l 载入、f 结束
+    fn run(transition: &mut Transition, steps: usize, dt: f32) -> String {
+        (0..steps)
+            .map(|_| match transition.update(dt) {
+                TransitionStep::Running => 'r',
+                TransitionStep::Load { .. } => 'l',
+                TransitionStep::Finished => 'f',
+            })
+            .collect()
+    }
+
+    fn fade() -> Transition {
+        Transition::new(TransitionKind::Fade, "cave".to_string(), vec2(10.0, 20.0))
+    }
+
+    #[test]
+    fn loads_once_when_fully_covered_then_finishes() {
+        let mut transition = fade();
+        assert_eq!(transition.coverage(), 0.0);
+        assert_eq!(transition.destination(), Some("cave"));
+        // 0.1 秒一步：遮住 0.45 秒、黑屏 0.15 秒、揭开 0.45 秒
+        assert_eq!(run(&mut transition, 4, 0.1), "rrrr");
+        assert!(transition.coverage() > 0.5 && transition.coverage() < 1.0);
+        match transition.update(0.1) {
+            TransitionStep::Load { to_map, to_pos } => {
+                assert_eq!(to_map, "cave");
+                assert_eq!(to_pos, vec2(10.0, 20.0));
+            }
+            _ => panic!("expected the map to load once fully covered"),
+        }
+        assert_eq!(transition.coverage(), 1.0);
+        assert_eq!(transition.destination(), None);
+        assert_eq!(run(&mut transition, 2, 0.1), "rr");
+        assert_eq!(transition.coverage(), 1.0);
+        assert_eq!(run(&mut transition, 5, 0.1), "rrrrf");
+    }
+
+    #[test]
+    fn a_long_frame_still_loads_before_revealing() {
+        let mut transition = fade();
+        assert_eq!(run(&mut transition, 1, 5.0), "l");
+        assert_eq!(run(&mut transition, 2, 5.0), "rf");
+    }
+
+    #[test]
+    fn coverage_eases_in_and_out() {
+        assert_eq!(smoothstep(-1.0), 0.0);
+        assert_eq!(smoothstep(0.5), 0.5);
+        assert_eq!(smoothstep(2.0), 1.0);
+        assert!(smoothstep(0.1) < 0.1);
+        assert!(smoothstep(0.9) > 0.9);
+    }
+}
@@ -0,0 +1,346 @@
+﻿use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::actors::monster::MonsterBook;
+use crate::actors::player::Player;
+use crate::systems::combat::Combat;
+use crate::systems::dialogue::DialogueState;
+use crate::systems::transition::Transition;
+use crate::ui::Notice;
+use crate::world::condition::first_unmet;
+use crate::world::dialogue::DialogueBook;
+use crate::world::map::{MapConfig, Vec2Def};
+use crate::world::progress::Progress;
+use crate::world::trigger::{TriggerAction, TriggerEvent};
+use crate::world::World;
+
+// 触发区域运行状态：玩家是否在各区域内、停留计时与尚未执行完的动作（切换地图时重置）
+#[derive(Default)]
+pub struct TriggerState {
+    map_id: Option<String>,
+    inside: Vec<bool>,
+    stay: Vec<f32>,
+    pending: VecDeque<FiredTrigger>,
+}
+
+// 已命中的触发区域：剩余动作与一次性触发的进度键（动作全部执行后才记入进度）
+struct FiredTrigger {
+    once_key: Option<String>,
+    actions: VecDeque<TriggerAction>,
+}
+
+impl TriggerState {
+    // 清空状态，下次更新时按地图重新初始化；未执行完的一次性触发未记入进度，下次命中时重新触发
+    pub fn reset(&mut self) {
+        self.map_id = None;
+        self.pending.clear();
+    }
+
+    // 一次性触发是否仍在等待执行
+    fn is_pending(&self, key: &str) -> bool {
+        self.pending
+            .iter()
+            .any(|fired| fired.once_key.as_deref() == Some(key))
+    }
+
+    // 按顺序执行等待中的动作；run 返回 false 表示动作暂时无法执行，连同其后的动作留到下一帧重试。
+    // 返回动作已全部执行的一次性触发键
+    fn drain(&mut self, mut run: impl FnMut(&TriggerAction) -> bool) -> Vec<String> {
+        let mut done = Vec::new();
+        while let Some(fired) = self.pending.front_mut() {
+            while let Some(action) = fired.actions.front() {
+                if !run(action) {
+                    return done;
+                }
+                fired.actions.pop_front();
+            }
+            if let Some(key) = self.pending.pop_front().and_then(|fired| fired.once_key) {
+                done.push(key);
+            }
+        }
+        done
+    }
+}
+
+// 执行触发动作时可修改的游戏状态
+pub struct TriggerContext<'a> {
+    pub world: &'a World,
+    pub player: &'a mut Player,
+    pub progress: &'a mut Progress,
+    pub notice: &'a mut Notice,
+    pub combat: &'a mut Combat,
+    pub monsters: &'a MonsterBook,
+    pub dialogues: &'a DialogueBook,
+    pub dialogue: &'a mut Option<DialogueState>,
+    pub transition: &'a mut Option<Transition>,
+}
+
+// 检测玩家进出触发区域，命中的动作（已按条件与一次性规则过滤）排入等待队列
+pub fn update_triggers(
+    state: &mut TriggerState,
+    map: &MapConfig,
+    player: Vec2,
+    progress: &Progress,
+    dt: f32,
+) {
+    if state.map_id.as_deref() != Some(map.id.as_str()) || state.inside.len() != map.triggers.len()
+    {
+        // 进入地图时视为在所有区域之外，落点位于区域内会立即触发进入事件；上一张地图未执行完的动作作废
+        state.map_id = Some(map.id.clone());
+        state.pending.clear();
+        state.inside = vec![false; map.triggers.len()];
+        state.stay = vec![0.0; map.triggers.len()];
+    }
+
+    for (i, trigger) in map.triggers.iter().enumerate() {
+        let now = trigger.area.contains(player);
+        let was = std::mem::replace(&mut state.inside[i], now);
+        let hit = match trigger.on {
+            TriggerEvent::Enter => now && !was,
+            TriggerEvent::Leave => !now && was,
+            TriggerEvent::Stay => {
+                state.stay[i] = if now { state.stay[i] + dt } else { 0.0 };
+                if state.stay[i] >= trigger.interval {
+                    state.stay[i] = 0.0;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        let once_key = trigger.once.then(|| trigger.key(&map.id));
+        if !hit
+            || once_key
+                .as_ref()
+                .is_some_and(|key| progress.fired_triggers.contains(key) || state.is_pending(key))
+            || first_unmet(&trigger.requires, progress).is_some()
+        {
+            continue;
+        }
+        state.pending.push_back(FiredTrigger {
+            once_key,
+            actions: trigger.actions.iter().cloned().collect(),
+        });
+    }
+}
+
+// 按顺序执行等待中的触发动作；对话或过场正在进行时留到之后的帧，一次性触发在动作全部执行后才记入进度
+pub fn run_trigger_actions(state: &mut TriggerState, ctx: &mut TriggerContext) {
+    let done = state.drain(|action| run_action(action, ctx));
+    ctx.progress.fired_triggers.extend(done);
+}
+
+// 执行单个触发动作；返回 false 表示暂时无法执行
+fn run_action(action: &TriggerAction, ctx: &mut TriggerContext) -> bool {
+    match action.clone() {
+        TriggerAction::ShowText { text } => ctx.notice.show(text),
+        TriggerAction::SetFlag { flag } => {
+            ctx.progress.flags.insert(flag);
+        }
+        TriggerAction::ClearFlag { flag } => {
+            ctx.progress.flags.remove(&flag);
+        }
+        TriggerAction::SpawnMonsters { monster, count, at } => {
+            let center = at.map_or(ctx.player.pos, Vec2Def::to_vec2);
+            ctx.combat.spawn_monsters(
+                ctx.world.current_map(),
+                ctx.monsters,
+                &monster,
+                count,
+                center,
+                ctx.player.pos,
+            );
+        }
+        TriggerAction::Teleport {
+            to_map,
+            to_pos,
+            transition,
+        } => {
+            // 同一帧已有过场（如传送点）时不覆盖它
+            if ctx.transition.is_some() {
+                return false;
+            }
+            if ctx.world.has_map(&to_map) {
+                *ctx.transition = Some(Transition::new(transition, to_map, to_pos.to_vec2()));
+            }
+        }
+        TriggerAction::Cutscene { dialogue, speaker } => {
+            // 已有对话打开时等它结束再播放
+            if ctx.dialogue.is_some() {
+                return false;
+            }
+            *ctx.dialogue =
+                DialogueState::start(ctx.dialogues, &dialogue, &speaker, ctx.progress, ctx.notice);
+        }
+        TriggerAction::LockPortal { portal } => {
+            ctx.progress.locked_portals.insert(portal);
+        }
+        TriggerAction::UnlockPortal { portal } => {
+            ctx.progress.locked_portals.remove(&portal);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    // 地图：每个触发区域是以 (100, 100) 为圆心、半径 20 的圆，动作为显示区域 id 与显示 "end"
+    fn map(triggers: Vec<Value>) -> MapConfig {
+        let triggers: Vec<Value> = triggers
+            .into_iter()
+            .map(|extra| {
+                let id = extra["id"].clone();
+                let mut trigger = json!({
+                    "shape": "circle",
+                    "center": { "x": 100.0, "y": 100.0 },
+                    "radius": 20.0,
+                    "actions": [
+                        { "type": "show_text", "text": id },
+                        { "type": "show_text", "text": "end" }
+                    ]
+                });
+                trigger
+                    .as_object_mut()
+                    .unwrap()
+                    .extend(extra.as_object().unwrap().clone());
+                trigger
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "id": "a",
+            "name": "a",
+            "spawn": { "x": 0.0, "y": 0.0 },
+            "portals": [],
+            "bosses": [],
+            "triggers": triggers
+        }))
+        .unwrap()
+    }
+
+    const INSIDE: Vec2 = vec2(100.0, 100.0);
+    const OUTSIDE: Vec2 = vec2(300.0, 300.0);
+
+    // 依次走过各位置（每步 dt 秒），执行全部动作，返回显示过的区域 id
+    fn walk(
+        state: &mut TriggerState,
+        map: &MapConfig,
+        progress: &mut Progress,
+        path: &[Vec2],
+        dt: f32,
+    ) -> Vec<String> {
+        let mut shown = Vec::new();
+        for pos in path {
+            update_triggers(state, map, *pos, progress, dt);
+            let done = state.drain(|action| {
+                if let TriggerAction::ShowText { text } = action {
+                    if text != "end" {
+                        shown.push(text.clone());
+                    }
+                }
+                true
+            });
+            progress.fired_triggers.extend(done);
+        }
+        shown
+    }
+
+    #[test]
+    fn enter_and_leave_fire_on_edges_only() {
+        let map = map(vec![
+            json!({ "id": "in" }),
+            json!({ "id": "out", "on": "leave" }),
+        ]);
+        let mut state = TriggerState::default();
+        let mut progress = Progress::default();
+        let path = [OUTSIDE, INSIDE, INSIDE, OUTSIDE, OUTSIDE, INSIDE];
+        let shown = walk(&mut state, &map, &mut progress, &path, 0.1);
+        assert_eq!(shown, ["in", "out", "in"]);
+    }
+
+    #[test]
+    fn landing_inside_a_region_counts_as_entering() {
+        let map = map(vec![json!({ "id": "in" })]);
+        let mut state = TriggerState::default();
+        let mut progress = Progress::default();
+        let shown = walk(&mut state, &map, &mut progress, &[INSIDE], 0.1);
+        assert_eq!(shown, ["in"]);
+    }
+
+    #[test]
+    fn stay_fires_every_interval_and_restarts_after_leaving() {
+        let map = map(vec![json!({ "id": "stay", "on": "stay", "interval": 1.0 })]);
+        let mut state = TriggerState::default();
+        let mut progress = Progress::default();
+        let shown = walk(&mut state, &map, &mut progress, &[INSIDE; 5], 0.5);
+        assert_eq!(shown, ["stay", "stay"]);
+        // 离开时计时清零：再次进入后要停满一个间隔
+        let path = [OUTSIDE, INSIDE];
+        assert!(walk(&mut state, &map, &mut progress, &path, 0.5).is_empty());
+        let shown = walk(&mut state, &map, &mut progress, &[INSIDE], 0.5);
+        assert_eq!(shown, ["stay"]);
+    }
+
+    #[test]
+    fn once_triggers_fire_a_single_time_and_others_repeat() {
+        let map = map(vec![
+            json!({ "id": "once", "once": true }),
+            json!({ "id": "every" }),
+        ]);
+        let mut state = TriggerState::default();
+        let mut progress = Progress::default();
+        let path = [INSIDE, OUTSIDE, INSIDE];
+        let shown = walk(&mut state, &map, &mut progress, &path, 0.1);
+        assert_eq!(shown, ["once", "every", "every"]);
+        assert!(progress.fired_triggers.contains("a#once"));
+    }
+
+    #[test]
+    fn once_trigger_is_recorded_only_after_blocked_actions_run() {
+        let map = map(vec![json!({ "id": "once", "once": true })]);
+        let mut state = TriggerState::default();
+        let progress = Progress::default();
+        update_triggers(&mut state, &map, INSIDE, &progress, 0.1);
+
+        // 第二个动作受阻：第一个动作已执行，触发仍在等待且未记入进度
+        let mut runs = Vec::new();
+        let done = state.drain(|action| {
+            let TriggerAction::ShowText { text } = action else {
+                unreachable!()
+            };
+            runs.push(text.clone());
+            text != "end"
+        });
+        assert!(done.is_empty());
+        assert!(state.is_pending("a#once"));
+
+        // 等待期间离开再进入不会重复排队；下一帧只重试受阻的动作
+        update_triggers(&mut state, &map, OUTSIDE, &progress, 0.1);
+        update_triggers(&mut state, &map, INSIDE, &progress, 0.1);
+        let done = state.drain(|action| {
+            let TriggerAction::ShowText { text } = action else {
+                unreachable!()
+            };
+            runs.push(text.clone());
+            true
+        });
+        assert_eq!(runs, ["once", "end", "end"]);
+        assert_eq!(done, ["a#once"]);
+    }
+
+    #[test]
+    fn reset_drops_unfinished_once_triggers_so_they_fire_again() {
+        let map = map(vec![json!({ "id": "once", "once": true })]);
+        let mut state = TriggerState::default();
+        let mut progress = Progress::default();
+        update_triggers(&mut state, &map, INSIDE, &progress, 0.1);
+        state.reset();
+        assert!(!state.is_pending("a#once"));
+        let shown = walk(&mut state, &map, &mut progress, &[INSIDE], 0.1);
+        assert_eq!(shown, ["once"]);
+    }
+}
@@ -0,0 +1,160 @@
+﻿use macroquad::prelude::*;
+
+use crate::world::clock::DayPhase;
+use crate::world::map::MapConfig;
+use crate::world::weather::{WeatherConfig, WeatherKind};
+
+// 各种天气的粒子参数：基础数量、寿命范围、下落速度范围、风力倍率、左右摆动幅度、淡入淡出时长
+struct KindParams {
+    count: f32,
+    life: (f32, f32),
+    fall: (f32, f32),
+    drift: f32,
+    sway: f32,
+    fade: f32,
+}
+
+// 按种类取粒子参数
+fn params(kind: WeatherKind) -> KindParams {
+    match kind {
+        WeatherKind::Rain => KindParams {
+            count: 120.0,
+            life: (0.5, 0.9),
+            fall: (480.0, 560.0),
+            drift: 120.0,
+            sway: 0.0,
+            fade: 0.08,
+        },
+        WeatherKind::Snow => KindParams {
+            count: 80.0,
+            life: (4.0, 7.0),
+            fall: (30.0, 60.0),
+            drift: 40.0,
+            sway: 18.0,
+            fade: 0.45,
+        },
+        WeatherKind::Mist => KindParams {
+            count: 10.0,
+            life: (6.0, 10.0),
+            fall: (-2.0, 2.0),
+            drift: 20.0,
+            sway: 0.0,
+            fade: 1.5,
+        },
+        WeatherKind::Petals => KindParams {
+            count: 30.0,
+            life: (4.0, 7.0),
+            fall: (30.0, 50.0),
+            drift: 40.0,
+            sway: 24.0,
+            fade: 0.45,
+        },
+    }
+}
+
+// 单个天气粒子（世界坐标）
+pub struct WeatherParticle {
+    pub kind: WeatherKind,
+    pub pos: Vec2,
+    pub scale: f32,
+    vel: Vec2,
+    age: f32,
+    life: f32,
+    // 摆动相位，让同层粒子错开
+    phase: f32,
+}
+
+impl WeatherParticle {
+    // 在视野内随机位置生成；prewarm 为真时随机推进年龄，避免进入地图时所有粒子同时淡入
+    fn spawn(config: &WeatherConfig, view: Rect, prewarm: bool) -> Self {
+        let p = params(config.kind);
+        let life = rand::gen_range(p.life.0, p.life.1);
+        let drift = config.wind * p.drift
+            + match config.kind {
+                WeatherKind::Mist => rand::gen_range(8.0, 16.0),
+                WeatherKind::Petals => 15.0,
+                _ => 0.0,
+            };
+        let scale = match config.kind {
+            WeatherKind::Rain | WeatherKind::Petals => 2.0,
+            WeatherKind::Snow => rand::gen_range(1.5, 2.5),
+            WeatherKind::Mist => rand::gen_range(10.0, 16.0),
+        };
+        Self {
+            kind: config.kind,
+            pos: vec2(
+                rand::gen_range(view.x, view.x + view.w),
+                rand::gen_range(view.y, view.y + view.h),
+            ),
+            scale,
+            vel: vec2(drift, rand::gen_range(p.fall.0, p.fall.1)),
+            age: if prewarm {
+                rand::gen_range(0.0, life)
+            } else {
+                0.0
+            },
+            life,
+            phase: rand::gen_range(0.0, std::f32::consts::TAU),
+        }
+    }
+
+    // 透明度倍率：出生时淡入、消失前淡出
+    pub fn alpha(&self) -> f32 {
+        let fade = params(self.kind).fade;
+        (self.age / fade)
+            .min((self.life - self.age) / fade)
+            .clamp(0.0, 1.0)
+    }
+}
+
+// 天气运行状态：当前地图各天气层的粒子（切换地图时重建）
+#[derive(Default)]
+pub struct WeatherState {
+    map_id: Option<String>,
+    layers: Vec<Vec<WeatherParticle>>,
+}
+
+impl WeatherState {
+    // 全部粒子
+    pub fn particles(&self) -> impl Iterator<Item = &WeatherParticle> {
+        self.layers.iter().flatten()
+    }
+}
+
+// 推进天气粒子：移出视野的从另一侧绕回，寿命结束的在视野内重生；不在时段内的天气层不再补充
+pub fn update_weather(
+    state: &mut WeatherState,
+    map: &MapConfig,
+    view: Rect,
+    phase: DayPhase,
+    dt: f32,
+) {
+    let fresh =
+        state.map_id.as_deref() != Some(map.id.as_str()) || state.layers.len() != map.weather.len();
+    if fresh {
+        state.map_id = Some(map.id.clone());
+        state.layers = map.weather.iter().map(|_| Vec::new()).collect();
+    }
+
+    for (particles, config) in state.layers.iter_mut().zip(&map.weather) {
+        let p = params(config.kind);
+        for particle in particles.iter_mut() {
+            particle.age += dt;
+            let sway = (particle.age * 2.0 + particle.phase).sin() * p.sway;
+            particle.pos += (particle.vel + vec2(sway, 0.0)) * dt;
+            // 视野内环绕，镜头移动时粒子密度保持均匀
+            let offset = particle.pos - view.point();
+            particle.pos =
+                view.point() + vec2(offset.x.rem_euclid(view.w), offset.y.rem_euclid(view.h));
+        }
+        particles.retain(|particle| particle.age < particle.life);
+
+        if !config.is_active(phase) {
+            continue;
+        }
+        let target = (p.count * config.intensity.max(0.0)).round() as usize;
+        while particles.len() < target {
+            particles.push(WeatherParticle::spawn(config, view, fresh));
+        }
+    }
+}
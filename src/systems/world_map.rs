@@ -0,0 +1,74 @@
+﻿use macroquad::prelude::*;
+
+use crate::world::progress::Progress;
+use crate::world::travel::MapNode;
+use crate::world::World;
+
+// 世界地图界面：到访过的地图节点（按布局列、行排序）与选中项
+pub struct WorldMapScreen {
+    pub nodes: Vec<MapNode>,
+    pub selected: usize,
+}
+
+// 世界地图界面的操作结果
+pub enum WorldMapAction {
+    Close,
+    // 沿最短路线快速旅行到目标地图
+    Travel { to_map: String, to_pos: Vec2 },
+}
+
+impl WorldMapScreen {
+    // 打开世界地图，默认选中当前所在地图
+    pub fn open(world: &World, progress: &Progress) -> Self {
+        let current = &world.current_map().id;
+        let mut nodes: Vec<MapNode> = world
+            .overworld_layout()
+            .into_iter()
+            .filter(|node| &node.id == current || progress.visited_maps.contains(&node.id))
+            .collect();
+        nodes.sort_by_key(|node| (node.col, node.row));
+        let selected = nodes.iter().position(|n| &n.id == current).unwrap_or(0);
+        Self { nodes, selected }
+    }
+
+    // 选中的地图 id
+    pub fn selected_id(&self) -> Option<&str> {
+        self.nodes.get(self.selected).map(|n| n.id.as_str())
+    }
+}
+
+// 处理世界地图输入：方向键切换地图，E/回车/空格沿最短路线前往，M/Esc 关闭
+pub fn update_world_map(
+    screen: &mut WorldMapScreen,
+    world: &World,
+    progress: &Progress,
+) -> Option<WorldMapAction> {
+    if is_key_pressed(KeyCode::M) || is_key_pressed(KeyCode::Escape) {
+        return Some(WorldMapAction::Close);
+    }
+    let count = screen.nodes.len();
+    if count > 0 {
+        let prev = [KeyCode::Up, KeyCode::Left, KeyCode::W, KeyCode::A];
+        let next = [KeyCode::Down, KeyCode::Right, KeyCode::S, KeyCode::D];
+        if prev.into_iter().any(is_key_pressed) {
+            screen.selected = (screen.selected + count - 1) % count;
+        }
+        if next.into_iter().any(is_key_pressed) {
+            screen.selected = (screen.selected + 1) % count;
+        }
+    }
+
+    let confirm = is_key_pressed(KeyCode::E)
+        || is_key_pressed(KeyCode::Enter)
+        || is_key_pressed(KeyCode::Space);
+    if !confirm {
+        return None;
+    }
+    // 无法前往的原因已显示在界面上，这里直接忽略
+    let to_map = screen.selected_id()?.to_string();
+    let route = world.find_route(&to_map, progress).ok()?;
+    Some(WorldMapAction::Travel {
+        to_map,
+        to_pos: route.arrival,
+    })
+}
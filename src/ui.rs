@@ -1,8 +1,11 @@
 ﻿use macroquad::prelude::*;
 
+use crate::sprite::SpriteLibrary;
+
 #[derive(Default)]
 pub struct Ui {
     pub font: Option<Font>,
+    pub sprites: SpriteLibrary,
 }
 
 impl Ui {
@@ -12,6 +15,70 @@ impl Ui {
     }
 }
 
+// 提示消息的显示时长（秒）
+const NOTICE_SECS: f32 = 2.5;
+
+// 屏幕提示消息：显示一段时间后自动消失
+#[derive(Default)]
+pub struct Notice {
+    text: String,
+    remaining: f32,
+}
+
+impl Notice {
+    // 显示新的提示
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.remaining = NOTICE_SECS;
+    }
+
+    // 推进计时
+    pub fn update(&mut self, dt: f32) {
+        self.remaining = (self.remaining - dt).max(0.0);
+    }
+
+    // 当前仍在显示的提示文本
+    pub fn text(&self) -> Option<&str> {
+        (self.remaining > 0.0).then_some(self.text.as_str())
+    }
+}
+
+// 地图名横幅的显示时长与淡入淡出时长（秒）
+const BANNER_SECS: f32 = 3.0;
+const BANNER_FADE: f32 = 0.6;
+
+// 地图名横幅：进入地图时显示，淡入后停留再淡出
+#[derive(Default)]
+pub struct MapBanner {
+    text: String,
+    remaining: f32,
+}
+
+impl MapBanner {
+    // 显示新的地图名
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.remaining = BANNER_SECS;
+    }
+
+    // 推进计时
+    pub fn update(&mut self, dt: f32) {
+        self.remaining = (self.remaining - dt).max(0.0);
+    }
+
+    // 当前显示的文本与不透明度
+    pub fn visible(&self) -> Option<(&str, f32)> {
+        if self.remaining <= 0.0 {
+            return None;
+        }
+        let shown = BANNER_SECS - self.remaining;
+        let alpha = (shown / BANNER_FADE)
+            .min(self.remaining / BANNER_FADE)
+            .min(1.0);
+        Some((self.text.as_str(), alpha))
+    }
+}
+
 // 绘制UI文字，优先使用加载的字体
 pub fn draw_text_ui(ui: &Ui, text: &str, x: f32, y: f32, size: u16, color: Color) {
     if let Some(font) = ui.font() {
@@ -41,3 +108,21 @@ pub fn draw_centered_text(ui: &Ui, text: &str, y: f32, size: u16, color: Color)
     let dims = measure_text_ui(ui, text, size);
     draw_text_ui(ui, text, (crate::config::SCREEN_W - dims.width) * 0.5, y, size, color);
 }
+
+// 按最大宽度逐字换行（适用于中文等无空格文本），保留原有换行
+pub fn wrap_text(ui: &Ui, text: &str, size: u16, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for ch in paragraph.chars() {
+            line.push(ch);
+            if measure_text_ui(ui, &line, size).width > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::take(&mut line));
+                line.push(ch);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
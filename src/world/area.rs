@@ -0,0 +1,45 @@
+﻿use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::world::map::Vec2Def;
+
+// 地图上的区域：矩形（左上角 + 尺寸）或圆形
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum Area {
+    Rect { pos: Vec2Def, size: Vec2Def },
+    Circle { center: Vec2Def, radius: f32 },
+}
+
+impl Area {
+    // 判断坐标是否位于区域内
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self {
+            Area::Rect { pos, size } => Rect::new(pos.x, pos.y, size.x, size.y).contains(p),
+            Area::Circle { center, radius } => p.distance(center.to_vec2()) <= *radius,
+        }
+    }
+
+    // 区域中心
+    pub fn center(&self) -> Vec2 {
+        match self {
+            Area::Rect { pos, size } => pos.to_vec2() + size.to_vec2() * 0.5,
+            Area::Circle { center, .. } => center.to_vec2(),
+        }
+    }
+
+    // 区域内均匀分布的随机点
+    pub fn random_point(&self) -> Vec2 {
+        match self {
+            Area::Rect { pos, size } => vec2(
+                rand::gen_range(pos.x, pos.x + size.x),
+                rand::gen_range(pos.y, pos.y + size.y),
+            ),
+            Area::Circle { center, radius } => {
+                let angle = rand::gen_range(0.0, std::f32::consts::TAU);
+                let dist = radius * rand::gen_range(0.0f32, 1.0).sqrt();
+                center.to_vec2() + vec2(angle.cos(), angle.sin()) * dist
+            }
+        }
+    }
+}
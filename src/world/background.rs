@@ -0,0 +1,152 @@
+﻿use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sprite::ColorDef;
+use crate::world::map::{is_default, Vec2Def};
+
+// 背景层：按声明顺序由远及近绘制在瓦片之下
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackgroundLayer {
+    #[serde(flatten)]
+    pub kind: LayerKind,
+    // 视差系数：0 固定在屏幕上，1 与地面同步移动，介于其间为远景
+    #[serde(
+        default = "default_parallax",
+        skip_serializing_if = "is_default_parallax"
+    )]
+    pub parallax: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation: Option<LayerAnimation>,
+}
+
+// 默认与地面同步移动
+fn default_parallax() -> f32 {
+    1.0
+}
+
+// 写回时省略默认视差系数
+fn is_default_parallax(parallax: &f32) -> bool {
+    *parallax == default_parallax()
+}
+
+// 背景层内容
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerKind {
+    // 铺满视野的纯色
+    Solid {
+        color: ColorDef,
+    },
+    // 自上而下的分段渐变（像素风色带）
+    Gradient {
+        top: ColorDef,
+        bottom: ColorDef,
+        #[serde(default = "default_bands", skip_serializing_if = "is_default_bands")]
+        bands: u32,
+    },
+    // 图片，可沿横向/纵向平铺
+    Image {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<Vec2Def>,
+        #[serde(
+            default = "default_image_scale",
+            skip_serializing_if = "is_default_image_scale"
+        )]
+        scale: f32,
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        repeat_x: bool,
+        #[serde(default, skip_serializing_if = "is_default")]
+        repeat_y: bool,
+    },
+    // 像素精灵装饰，spacing 不为空时沿横向按间距重复
+    Sprite {
+        sprite: String,
+        pos: Vec2Def,
+        #[serde(default = "default_sprite_scale")]
+        scale: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        spacing: Option<f32>,
+    },
+}
+
+// 渐变默认色带数
+fn default_bands() -> u32 {
+    16
+}
+
+// 图片默认不缩放
+fn default_image_scale() -> f32 {
+    1.0
+}
+
+// 装饰精灵默认放大倍数
+fn default_sprite_scale() -> f32 {
+    2.0
+}
+
+// 图片默认横向平铺
+fn default_true() -> bool {
+    true
+}
+
+// 写回时省略默认色带数
+fn is_default_bands(bands: &u32) -> bool {
+    *bands == default_bands()
+}
+
+// 写回时省略默认图片缩放
+fn is_default_image_scale(scale: &f32) -> bool {
+    *scale == default_image_scale()
+}
+
+// 写回时省略默认开启的开关
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+// 背景层动画
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerAnimation {
+    // 匀速漂移（像素/秒），配合平铺可做流云
+    Scroll {
+        speed: Vec2Def,
+    },
+    // 上下浮动
+    Bob {
+        amplitude: f32,
+        period: f32,
+    },
+    // 透明度呼吸（对纯色、渐变与图片生效）
+    Pulse {
+        period: f32,
+        #[serde(default, skip_serializing_if = "is_default")]
+        min_alpha: f32,
+    },
+}
+
+impl LayerAnimation {
+    // 指定时刻的位移
+    pub fn offset(&self, time: f32) -> Vec2 {
+        match self {
+            LayerAnimation::Scroll { speed } => speed.to_vec2() * time,
+            LayerAnimation::Bob { amplitude, period } => vec2(
+                0.0,
+                (time / period.max(0.01) * std::f32::consts::TAU).sin() * amplitude,
+            ),
+            LayerAnimation::Pulse { .. } => Vec2::ZERO,
+        }
+    }
+
+    // 指定时刻的透明度系数
+    pub fn alpha(&self, time: f32) -> f32 {
+        match self {
+            LayerAnimation::Pulse { period, min_alpha } => {
+                let wave = (time / period.max(0.01) * std::f32::consts::TAU).sin() * 0.5 + 0.5;
+                min_alpha + (1.0 - min_alpha) * wave
+            }
+            _ => 1.0,
+        }
+    }
+}
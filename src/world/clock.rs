@@ -0,0 +1,177 @@
+﻿use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// 游戏内一昼夜对应的现实时长（秒）
+const DAY_SECONDS: f32 = 480.0;
+
+// 新游戏的起始时刻（辰时）
+pub const START_HOUR: f32 = 8.0;
+
+// 十二时辰名称，子时从 23 点开始
+const SHICHEN: [&str; 12] = [
+    "子时", "丑时", "寅时", "卯时", "辰时", "巳时", "午时", "未时", "申时", "酉时", "戌时", "亥时",
+];
+
+// 昼夜色调关键帧：(小时, 叠加颜色)，相邻关键帧之间线性插值
+const TINT_KEYS: [(f32, [f32; 4]); 8] = [
+    (0.0, [0.04, 0.06, 0.2, 0.55]),
+    (5.0, [0.08, 0.08, 0.28, 0.45]),
+    (6.5, [1.0, 0.6, 0.4, 0.18]),
+    (8.0, [1.0, 1.0, 1.0, 0.0]),
+    (16.5, [1.0, 1.0, 1.0, 0.0]),
+    (18.0, [1.0, 0.45, 0.25, 0.25]),
+    (20.0, [0.06, 0.07, 0.24, 0.5]),
+    (24.0, [0.04, 0.06, 0.2, 0.55]),
+];
+
+// 时段：用于 NPC、妖兽与天气的作息
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    // 时段名称
+    pub fn label(self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "黎明",
+            DayPhase::Day => "白昼",
+            DayPhase::Dusk => "黄昏",
+            DayPhase::Night => "夜晚",
+        }
+    }
+}
+
+// 作息是否允许在指定时段出现（为空表示全天）
+pub fn in_schedule(schedule: &[DayPhase], phase: DayPhase) -> bool {
+    schedule.is_empty() || schedule.contains(&phase)
+}
+
+// 游戏内时钟：按现实时间推进的小时数 [0, 24)
+#[derive(Clone, Copy, Debug)]
+pub struct GameClock {
+    hour: f32,
+}
+
+impl Default for GameClock {
+    // 新游戏从辰时开始
+    fn default() -> Self {
+        Self::at(START_HOUR)
+    }
+}
+
+impl GameClock {
+    // 指定时刻的时钟
+    pub fn at(hour: f32) -> Self {
+        Self {
+            hour: hour.rem_euclid(24.0),
+        }
+    }
+
+    // 推进现实时间 dt 秒
+    pub fn update(&mut self, dt: f32) {
+        self.hour = (self.hour + dt * 24.0 / DAY_SECONDS).rem_euclid(24.0);
+    }
+
+    // 当前小时数
+    pub fn hour(&self) -> f32 {
+        self.hour
+    }
+
+    // 当前时段：5-7 点黎明，7-17 点白昼，17-19 点黄昏，其余为夜晚
+    pub fn phase(&self) -> DayPhase {
+        match self.hour {
+            h if (5.0..7.0).contains(&h) => DayPhase::Dawn,
+            h if (7.0..17.0).contains(&h) => DayPhase::Day,
+            h if (17.0..19.0).contains(&h) => DayPhase::Dusk,
+            _ => DayPhase::Night,
+        }
+    }
+
+    // 当前时辰名称
+    pub fn shichen(&self) -> &'static str {
+        let index = ((self.hour + 1.0) / 2.0) as usize % SHICHEN.len();
+        SHICHEN[index]
+    }
+
+    // 覆盖在画面上的昼夜色调
+    pub fn tint(&self) -> Color {
+        let next = TINT_KEYS
+            .iter()
+            .position(|(hour, _)| *hour > self.hour)
+            .unwrap_or(TINT_KEYS.len() - 1)
+            .max(1);
+        let (h0, c0) = TINT_KEYS[next - 1];
+        let (h1, c1) = TINT_KEYS[next];
+        let t = ((self.hour - h0) / (h1 - h0)).clamp(0.0, 1.0);
+        let mix = |i: usize| c0[i] + (c1[i] - c0[i]) * t;
+        Color::new(mix(0), mix(1), mix(2), mix(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 现实中推进一小时游戏时间所需的秒数
+    const HOUR_SECS: f32 = DAY_SECONDS / 24.0;
+
+    #[test]
+    fn hours_wrap_around_midnight() {
+        let mut clock = GameClock::at(23.5);
+        clock.update(HOUR_SECS);
+        assert!((clock.hour() - 0.5).abs() < 1e-3, "{}", clock.hour());
+        clock.update(HOUR_SECS * 48.0);
+        assert!((clock.hour() - 0.5).abs() < 1e-2, "{}", clock.hour());
+        assert_eq!(GameClock::at(-1.0).hour(), 23.0);
+        assert_eq!(GameClock::at(25.0).hour(), 1.0);
+        assert_eq!(GameClock::at(24.0).hour(), 0.0);
+    }
+
+    #[test]
+    fn phases_cover_the_whole_day() {
+        let phase = |hour: f32| GameClock::at(hour).phase();
+        assert_eq!(phase(4.99), DayPhase::Night);
+        assert_eq!(phase(5.0), DayPhase::Dawn);
+        assert_eq!(phase(7.0), DayPhase::Day);
+        assert_eq!(phase(16.99), DayPhase::Day);
+        assert_eq!(phase(17.0), DayPhase::Dusk);
+        assert_eq!(phase(19.0), DayPhase::Night);
+        assert_eq!(phase(0.0), DayPhase::Night);
+    }
+
+    #[test]
+    fn night_schedule_spans_midnight() {
+        let night = [DayPhase::Night];
+        let open = |hour: f32| in_schedule(&night, GameClock::at(hour).phase());
+        assert!(open(22.0) && open(23.9) && open(0.0) && open(4.5));
+        assert!(!open(5.0) && !open(12.0) && !open(18.5));
+        assert!(in_schedule(&[], DayPhase::Day));
+        assert!(!in_schedule(
+            &[DayPhase::Dawn, DayPhase::Dusk],
+            DayPhase::Night
+        ));
+    }
+
+    #[test]
+    fn shichen_starts_at_eleven() {
+        let name = |hour: f32| GameClock::at(hour).shichen();
+        assert_eq!(name(22.99), "亥时");
+        assert_eq!(name(23.0), "子时");
+        assert_eq!(name(0.5), "子时");
+        assert_eq!(name(1.0), "丑时");
+        assert_eq!(name(START_HOUR), "辰时");
+    }
+
+    #[test]
+    fn tint_is_continuous_across_midnight() {
+        let late = GameClock::at(23.999).tint();
+        let early = GameClock::at(0.0).tint();
+        assert!((late.a - early.a).abs() < 1e-3);
+        assert_eq!(GameClock::at(12.0).tint().a, 0.0);
+    }
+}
@@ -0,0 +1,169 @@
+﻿use macroquad::prelude::*;
+
+use crate::world::map::TileGrid;
+
+// 贴墙时留出的微小间隙，避免浮点误差导致卡进墙里
+const SKIN: f32 = 0.01;
+
+// 判断以 pos 为中心、半边长为 half 的方框是否与阻挡格重叠
+pub fn overlaps_solid(grid: &TileGrid, pos: Vec2, half: f32) -> bool {
+    let (x0, x1) = (
+        grid.tile_coord(pos.x - half),
+        grid.tile_coord(pos.x + half - SKIN),
+    );
+    let (y0, y1) = (
+        grid.tile_coord(pos.y - half),
+        grid.tile_coord(pos.y + half - SKIN),
+    );
+    (y0..=y1).any(|ty| (x0..=x1).any(|tx| grid.is_solid(tx, ty)))
+}
+
+// 方框与阻挡格重叠时移到最近的空地格中心，已在空地上或没有空地时原样返回
+pub fn nearest_open(grid: &TileGrid, pos: Vec2, half: f32) -> Vec2 {
+    if !overlaps_solid(grid, pos, half) {
+        return pos;
+    }
+    let mut best: Option<(f32, Vec2)> = None;
+    for ty in 0..grid.height as i32 {
+        for tx in 0..grid.width as i32 {
+            let center = grid.tile_rect(tx, ty).center();
+            if overlaps_solid(grid, center, half) {
+                continue;
+            }
+            let dist = center.distance_squared(pos);
+            if best.is_none_or(|(d, _)| dist < d) {
+                best = Some((dist, center));
+            }
+        }
+    }
+    best.map_or(pos, |(_, center)| center)
+}
+
+// 按轴分离移动：先走X再走Y，撞墙时贴住墙面，另一轴继续滑动；
+// 位移按不超过半格的步长分段处理，卡顿造成的大位移也不会穿墙
+pub fn move_and_slide(grid: &TileGrid, pos: Vec2, delta: Vec2, half: f32) -> Vec2 {
+    let max_step = (grid.tile_size * 0.5).max(1.0);
+    let steps = (delta.abs().max_element() / max_step).ceil().max(1.0) as usize;
+    let step = delta / steps as f32;
+    (0..steps).fold(pos, |p, _| slide_step(grid, p, step, half))
+}
+
+// 单步移动（位移不超过半格）
+fn slide_step(grid: &TileGrid, pos: Vec2, delta: Vec2, half: f32) -> Vec2 {
+    let mut p = pos;
+
+    if delta.x != 0.0 {
+        p.x += delta.x;
+        if overlaps_solid(grid, p, half) {
+            // 贴到阻挡格的边缘
+            let tx = grid.tile_coord(if delta.x > 0.0 {
+                p.x + half
+            } else {
+                p.x - half
+            });
+            let rect = grid.tile_rect(tx, 0);
+            p.x = if delta.x > 0.0 {
+                rect.x - half - SKIN
+            } else {
+                rect.x + rect.w + half + SKIN
+            };
+            if overlaps_solid(grid, p, half) {
+                p.x = pos.x;
+            }
+        }
+    }
+
+    if delta.y != 0.0 {
+        p.y += delta.y;
+        if overlaps_solid(grid, p, half) {
+            let ty = grid.tile_coord(if delta.y > 0.0 {
+                p.y + half
+            } else {
+                p.y - half
+            });
+            let rect = grid.tile_rect(0, ty);
+            p.y = if delta.y > 0.0 {
+                rect.y - half - SKIN
+            } else {
+                rect.y + rect.h + half + SKIN
+            };
+            if overlaps_solid(grid, p, half) {
+                p.y = pos.y;
+            }
+        }
+    }
+
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用网格：每格 32 像素
+    fn grid(rows: &[&str]) -> TileGrid {
+        TileGrid {
+            tile_size: 32.0,
+            width: rows[0].chars().count(),
+            height: rows.len(),
+            rows: rows.iter().map(|r| r.to_string()).collect(),
+            solid: "#".to_string(),
+            colors: Default::default(),
+        }
+    }
+
+    #[test]
+    fn slides_along_a_wall() {
+        let grid = grid(&["....", "....", "####"]);
+        let p = move_and_slide(&grid, vec2(48.0, 40.0), vec2(20.0, 30.0), 10.0);
+        assert_eq!(p.x, 68.0);
+        assert!(p.y < 54.0 && p.y > 53.9, "{p}");
+        assert!(!overlaps_solid(&grid, p, 10.0));
+    }
+
+    #[test]
+    fn stops_at_a_corner() {
+        let grid = grid(&["...#", "...#", "####"]);
+        let p = move_and_slide(&grid, vec2(70.0, 40.0), vec2(30.0, 30.0), 10.0);
+        assert!(p.x < 86.0 && p.x > 85.9, "{p}");
+        assert!(p.y < 54.0 && p.y > 53.9, "{p}");
+        assert!(!overlaps_solid(&grid, p, 10.0));
+    }
+
+    #[test]
+    fn large_delta_does_not_tunnel_through_a_thin_wall() {
+        let grid = grid(&["..#....."]);
+        // 一次卡顿：180 像素/秒 × 0.5 秒
+        let p = move_and_slide(&grid, vec2(16.0, 16.0), vec2(90.0, 0.0), 6.0);
+        assert!(p.x < 64.0 - 6.0, "{p}");
+        let p = move_and_slide(&grid, vec2(200.0, 16.0), vec2(-150.0, 0.0), 6.0);
+        assert!(p.x > 96.0 + 6.0, "{p}");
+    }
+
+    #[test]
+    fn snaps_out_of_a_wall_to_the_nearest_open_tile() {
+        let room = grid(&["....", ".##.", "...."]);
+        assert_eq!(
+            nearest_open(&room, vec2(16.0, 16.0), 10.0),
+            vec2(16.0, 16.0)
+        );
+        assert_eq!(
+            nearest_open(&room, vec2(40.0, 44.0), 10.0),
+            vec2(16.0, 48.0)
+        );
+        assert_eq!(
+            nearest_open(&room, vec2(90.0, 52.0), 10.0),
+            vec2(112.0, 48.0)
+        );
+        let walls = grid(&["##"]);
+        assert_eq!(nearest_open(&walls, vec2(5.0, 5.0), 10.0), vec2(5.0, 5.0));
+    }
+
+    #[test]
+    fn tiles_are_indexed_by_character() {
+        let grid = grid(&["石.#"]);
+        assert_eq!(grid.tile(0, 0), '石');
+        assert!(grid.is_solid(2, 0));
+        assert!(!grid.is_solid(1, 0));
+    }
+}
@@ -0,0 +1,155 @@
+﻿use serde::{Deserialize, Serialize};
+
+use crate::model::plane_level_name;
+use crate::world::clock::DayPhase;
+use crate::world::progress::Progress;
+
+// 解锁条件：针对持久进度求值
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    BossDefeated {
+        boss: String,
+    },
+    Flag {
+        flag: String,
+    },
+    NotFlag {
+        flag: String,
+    },
+    MinRealm {
+        realm: usize,
+    },
+    HasItem {
+        item: String,
+        #[serde(default = "default_count", skip_serializing_if = "is_default_count")]
+        count: u32,
+    },
+    // 仅在指定时段满足
+    TimeOfDay {
+        phases: Vec<DayPhase>,
+    },
+}
+
+// 物品条件默认数量
+fn default_count() -> u32 {
+    1
+}
+
+// 写回时省略默认数量
+fn is_default_count(count: &u32) -> bool {
+    *count == default_count()
+}
+
+impl Condition {
+    // 判断条件是否满足
+    pub fn check(&self, progress: &Progress) -> bool {
+        match self {
+            Condition::BossDefeated { boss } => progress.boss_defeated(boss),
+            Condition::Flag { flag } => progress.has_flag(flag),
+            Condition::NotFlag { flag } => !progress.has_flag(flag),
+            Condition::MinRealm { realm } => progress.cultivation.realm >= *realm,
+            Condition::HasItem { item, count } => progress.item_count(item) >= *count,
+            Condition::TimeOfDay { phases } => phases.contains(&progress.clock.phase()),
+        }
+    }
+
+    // 条件未满足时给玩家的提示（物品显示背包物品表中的名称）
+    pub fn describe(&self, progress: &Progress) -> String {
+        match self {
+            Condition::BossDefeated { boss } => format!("需先击败{boss}"),
+            Condition::Flag { flag } => format!("时机未到（{flag}）"),
+            Condition::NotFlag { flag } => format!("已无法通行（{flag}）"),
+            Condition::MinRealm { realm } => {
+                format!("境界不足，需达到{}", plane_level_name(*realm))
+            }
+            Condition::HasItem { item, count } => {
+                format!("需持有{}×{count}", progress.inventory.name(item))
+            }
+            Condition::TimeOfDay { phases } => {
+                let names: Vec<&str> = phases.iter().map(|p| p.label()).collect();
+                format!("时辰未到，需在{}", names.join("或"))
+            }
+        }
+    }
+}
+
+// 返回第一条未满足的条件
+pub fn first_unmet<'a>(conditions: &'a [Condition], progress: &Progress) -> Option<&'a Condition> {
+    conditions.iter().find(|c| !c.check(progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::world::clock::GameClock;
+
+    // 由 JSON 解析条件列表
+    fn conditions(value: serde_json::Value) -> Vec<Condition> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn each_condition_checks_its_progress() {
+        let mut progress = Progress {
+            clock: GameClock::at(23.0),
+            ..Progress::default()
+        };
+        progress.flags.insert("met_elder".to_string());
+        progress.defeated_bosses.insert("wolf_king".to_string());
+        progress.cultivation.realm = 2;
+        progress.inventory.add("jade", 2);
+
+        let met = conditions(json!([
+            { "type": "boss_defeated", "boss": "wolf_king" },
+            { "type": "flag", "flag": "met_elder" },
+            { "type": "not_flag", "flag": "banished" },
+            { "type": "min_realm", "realm": 2 },
+            { "type": "has_item", "item": "jade", "count": 2 },
+            { "type": "time_of_day", "phases": ["dusk", "night"] }
+        ]));
+        assert!(met.iter().all(|c| c.check(&progress)));
+
+        let unmet = conditions(json!([
+            { "type": "boss_defeated", "boss": "serpent" },
+            { "type": "flag", "flag": "banished" },
+            { "type": "not_flag", "flag": "met_elder" },
+            { "type": "min_realm", "realm": 3 },
+            { "type": "has_item", "item": "jade", "count": 3 },
+            { "type": "time_of_day", "phases": ["day"] }
+        ]));
+        assert!(unmet.iter().all(|c| !c.check(&progress)));
+    }
+
+    #[test]
+    fn a_list_requires_every_condition_in_order() {
+        let mut progress = Progress::default();
+        let list = conditions(json!([
+            { "type": "flag", "flag": "a" },
+            { "type": "not_flag", "flag": "b" },
+            { "type": "has_item", "item": "jade" }
+        ]));
+        assert!(first_unmet(&[], &progress).is_none());
+
+        let unmet =
+            |progress: &Progress| first_unmet(&list, progress).map(|c| c.describe(progress));
+        assert_eq!(unmet(&progress).as_deref(), Some("时机未到（a）"));
+        progress.flags.insert("a".to_string());
+        assert_eq!(unmet(&progress).as_deref(), Some("需持有jade×1"));
+        progress.flags.insert("b".to_string());
+        assert_eq!(unmet(&progress).as_deref(), Some("已无法通行（b）"));
+        progress.flags.remove("b");
+        progress.inventory.add("jade", 1);
+        assert_eq!(unmet(&progress), None);
+    }
+
+    #[test]
+    fn time_of_day_lists_every_phase_when_unmet() {
+        let progress = Progress::default();
+        let list = conditions(json!([{ "type": "time_of_day", "phases": ["dusk", "night"] }]));
+        let unmet = first_unmet(&list, &progress).unwrap();
+        assert_eq!(unmet.describe(&progress), "时辰未到，需在黄昏或夜晚");
+    }
+}
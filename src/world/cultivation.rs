@@ -0,0 +1,169 @@
+﻿use crate::config::PLANE_LEVELS;
+use crate::model::plane_level_name;
+
+// 修为需求基数：升到第 n+1 重（境界序号 n，从 0 计）需要 EXP_BASE × (n+1)²
+const EXP_BASE: u32 = 40;
+
+// 每个大境界的小境界数（初期、中期、后期）
+const STAGES_PER_MAJOR: usize = 3;
+
+// 突破到筑基、结丹、元婴、化神的基础成功率
+const BREAKTHROUGH_CHANCE: [f32; 4] = [0.8, 0.65, 0.5, 0.35];
+
+// 突破失败的代价：损失的修为比例与气血比例
+const FAIL_EXP_LOSS: f32 = 0.3;
+pub const FAIL_HP_LOSS: f32 = 0.3;
+
+// 修炼进度：境界序号（对应 plane_level_name）与当前境界已积累的修为
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cultivation {
+    pub realm: usize,
+    pub exp: u32,
+}
+
+// 突破结果
+pub enum Breakthrough {
+    Success,
+    Failure { exp_lost: u32 },
+}
+
+impl Cultivation {
+    // 境界名称
+    pub fn name(&self) -> &'static str {
+        plane_level_name(self.realm)
+    }
+
+    // 是否已达最高境界
+    pub fn is_peak(&self) -> bool {
+        self.realm + 1 >= PLANE_LEVELS
+    }
+
+    // 升入下一重所需修为
+    pub fn exp_needed(&self) -> u32 {
+        let n = self.realm as u32 + 1;
+        EXP_BASE * n * n
+    }
+
+    // 当前修为占所需修为的比例
+    pub fn ratio(&self) -> f32 {
+        self.exp as f32 / self.exp_needed() as f32
+    }
+
+    // 下一重是否跨越大境界（后期 → 下一境界初期）
+    fn next_is_major(&self) -> bool {
+        (self.realm + 1).is_multiple_of(STAGES_PER_MAJOR)
+    }
+
+    // 修为已满、须突破才能进入下一大境界
+    pub fn at_bottleneck(&self) -> bool {
+        !self.is_peak() && self.next_is_major() && self.exp >= self.exp_needed()
+    }
+
+    // 增长修为：小境界修为满时自动晋升（多余修为保留），大境界前停在瓶颈；返回需要提示玩家的文本
+    pub fn gain(&mut self, amount: u32) -> Option<String> {
+        if amount == 0 {
+            return None;
+        }
+        let before = self.realm;
+        self.exp = self.exp.saturating_add(amount);
+        while !self.is_peak() && !self.next_is_major() && self.exp >= self.exp_needed() {
+            self.exp -= self.exp_needed();
+            self.realm += 1;
+        }
+        if self.is_peak() || self.next_is_major() {
+            self.exp = self.exp.min(self.exp_needed());
+        }
+        if self.realm > before {
+            Some(format!("境界提升至{}", self.name()))
+        } else if self.at_bottleneck() {
+            Some("修为已至瓶颈，需突破".to_string())
+        } else {
+            None
+        }
+    }
+
+    // 突破成功率（bonus 为丹药加成）
+    pub fn breakthrough_chance(&self, bonus: f32) -> f32 {
+        let major = (self.realm + 1) / STAGES_PER_MAJOR;
+        let base = BREAKTHROUGH_CHANCE
+            .get(major.saturating_sub(1))
+            .copied()
+            .unwrap_or(0.0);
+        (base + bonus).clamp(0.0, 1.0)
+    }
+
+    // 尝试突破：roll 为 [0, 1) 的随机数，小于成功率即进入下一大境界，失败损失部分修为
+    pub fn breakthrough(&mut self, roll: f32, bonus: f32) -> Result<Breakthrough, String> {
+        if !self.at_bottleneck() {
+            return Err("修为未满，尚不能突破".to_string());
+        }
+        if roll < self.breakthrough_chance(bonus) {
+            self.realm += 1;
+            self.exp = 0;
+            return Ok(Breakthrough::Success);
+        }
+        let exp_lost = (self.exp as f32 * FAIL_EXP_LOSS) as u32;
+        self.exp -= exp_lost;
+        Ok(Breakthrough::Failure { exp_lost })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossing_one_threshold_keeps_the_leftover() {
+        let mut c = Cultivation::default();
+        assert_eq!(c.exp_needed(), 40);
+        assert!(c.gain(30).is_none());
+        let text = c.gain(20).unwrap();
+        assert_eq!((c.realm, c.exp), (1, 10));
+        assert!(text.contains(plane_level_name(1)), "{text}");
+    }
+
+    #[test]
+    fn one_gain_can_cross_several_thresholds() {
+        let mut c = Cultivation::default();
+        c.gain(40 + 160 + 100);
+        assert_eq!((c.realm, c.exp), (2, 100));
+        assert!(!c.at_bottleneck());
+    }
+
+    #[test]
+    fn major_realms_stop_at_the_bottleneck() {
+        let mut c = Cultivation::default();
+        c.gain(10_000);
+        assert_eq!((c.realm, c.exp), (2, 360));
+        assert!(c.at_bottleneck());
+        assert_eq!(c.gain(5).as_deref(), Some("修为已至瓶颈，需突破"));
+        assert_eq!(c.exp, 360);
+
+        assert!(matches!(
+            c.breakthrough(0.99, 0.0),
+            Ok(Breakthrough::Failure { exp_lost: 108 })
+        ));
+        assert_eq!((c.realm, c.exp), (2, 252));
+        assert!(c.breakthrough(0.0, 0.0).is_err());
+        c.gain(200);
+        assert!(matches!(
+            c.breakthrough(0.0, 0.0),
+            Ok(Breakthrough::Success)
+        ));
+        assert_eq!((c.realm, c.exp), (3, 0));
+    }
+
+    #[test]
+    fn peak_realm_caps_experience() {
+        let mut c = Cultivation {
+            realm: PLANE_LEVELS - 1,
+            exp: 0,
+        };
+        assert!(c.is_peak());
+        c.gain(u32::MAX);
+        c.gain(u32::MAX);
+        assert_eq!((c.realm, c.exp), (PLANE_LEVELS - 1, c.exp_needed()));
+        assert!(!c.at_bottleneck());
+        assert!(c.breakthrough(0.0, 1.0).is_err());
+    }
+}
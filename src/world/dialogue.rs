@@ -0,0 +1,60 @@
+﻿use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::assets::load_json;
+use crate::world::condition::Condition;
+use crate::world::effect::Effect;
+
+// 对话库：按 id 索引的对话树（data/dialogues.json）
+#[derive(Clone, Debug, Default)]
+pub struct DialogueBook {
+    dialogues: HashMap<String, DialogueDef>,
+}
+
+impl DialogueBook {
+    // 从JSON文件加载对话库
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            dialogues: load_json(path)?,
+        })
+    }
+
+    // 按 id 获取对话树
+    pub fn get(&self, id: &str) -> Option<&DialogueDef> {
+        self.dialogues.get(id)
+    }
+}
+
+// 对话树：起始节点与节点表
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueDef {
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+// 对话节点：进入时应用 effects；有选项时由玩家选择，否则跳到 next 或结束
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueNode {
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+// 对话选项：条件不满足时不显示
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    #[serde(default)]
+    pub requires: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
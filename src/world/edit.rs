@@ -0,0 +1,284 @@
+﻿use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{SCREEN_H, SCREEN_W};
+use crate::save::write_text_atomic;
+use crate::world::format::to_data_json;
+use crate::world::map::{MapConfig, MapsConfig, Vec2Def};
+use crate::world::source::Origin;
+use crate::world::trigger::TriggerAction;
+use crate::world::validate::trigger_portal_refs;
+use crate::world::World;
+
+impl World {
+    // 地图能否在编辑器中修改并写回主地图文件，不能时返回原因
+    pub fn edit_lock(&self, id: &str) -> Option<String> {
+        let Some(main_file) = &self.main_file else {
+            return Some("内置默认地图不能写回文件".to_string());
+        };
+        match self.origins.get(id) {
+            Some(origin) if origin.file != *main_file => {
+                Some(format!("来自 {}，请在源文件中修改", origin.label()))
+            }
+            _ if self.patched.contains(id) => {
+                Some("已被地图目录或 mod 中的补丁修改，无法写回".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    // 当前地图（编辑器修改用）
+    pub fn current_map_mut(&mut self) -> &mut MapConfig {
+        self.maps
+            .get_mut(&self.current)
+            .expect("current map missing")
+    }
+
+    // 按 id 排序的全部地图 id
+    pub fn map_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.maps.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    // 新建一屏大小的空白地图（出生点居中），返回新地图 id
+    pub fn add_blank_map(&mut self) -> Result<String, String> {
+        let main_file = self.main_file.clone().ok_or("内置默认地图不能写回文件")?;
+        let n = (1..)
+            .find(|n| !self.maps.contains_key(&format!("map_{n}")))
+            .unwrap_or_default();
+        let id = format!("map_{n}");
+        let map = MapConfig {
+            id: id.clone(),
+            name: format!("新地图{n}"),
+            size: Some(Vec2Def::new(SCREEN_W, SCREEN_H)),
+            spawn: Vec2Def::new(SCREEN_W * 0.5, SCREEN_H * 0.5),
+            portals: Vec::new(),
+            bosses: Vec::new(),
+            npcs: Vec::new(),
+            chests: Vec::new(),
+            signs: Vec::new(),
+            save_points: Vec::new(),
+            tiles: None,
+            background: Vec::new(),
+            spawn_zones: Vec::new(),
+            triggers: Vec::new(),
+            weather: Vec::new(),
+        };
+        self.origins.insert(id.clone(), Origin::base(main_file));
+        self.maps.insert(id.clone(), map);
+        Ok(id)
+    }
+
+    // 删除地图并移除其他地图中指向它的传送点，返回移除的传送点数量；
+    // 删除的是当前地图时回到起始地图
+    pub fn remove_map(&mut self, id: &str) -> Result<usize, String> {
+        if id == self.start {
+            return Err("起始地图不能删除".to_string());
+        }
+        if let Some(reason) = self.edit_lock(id) {
+            return Err(reason);
+        }
+        for map in self.maps.values().filter(|m| m.id != id) {
+            let portal_in = map.portals.iter().any(|p| p.to_map == id);
+            if portal_in {
+                if let Some(reason) = self.edit_lock(&map.id) {
+                    return Err(format!("地图 {} 有传送点通往此图，但{reason}", map.id));
+                }
+            }
+            let trigger = map.triggers.iter().find(|t| {
+                t.actions
+                    .iter()
+                    .any(|a| matches!(a, TriggerAction::Teleport { to_map, .. } if to_map == id))
+            });
+            if let Some(trigger) = trigger {
+                return Err(format!(
+                    "地图 {} 的触发区域 {} 会传送到此图，请先修改",
+                    map.id, trigger.id
+                ));
+            }
+        }
+
+        self.maps.remove(id);
+        self.origins.remove(id);
+        let mut removed = 0;
+        for map in self.maps.values_mut() {
+            let before = map.portals.len();
+            map.portals.retain(|p| p.to_map != id);
+            removed += before - map.portals.len();
+        }
+        if self.current == id {
+            self.current = self.start.clone();
+        }
+        Ok(removed)
+    }
+
+    // 为当前地图的新传送点生成全世界唯一的 id（“地图_portal_序号”）
+    pub fn new_portal_id(&self) -> String {
+        let keys: HashSet<String> = self
+            .maps
+            .values()
+            .flat_map(|map| {
+                map.portals
+                    .iter()
+                    .enumerate()
+                    .map(|(i, portal)| portal.key(&map.id, i))
+            })
+            .collect();
+        (1..)
+            .map(|n| format!("{}_portal_{n}", self.current))
+            .find(|id| !keys.contains(id))
+            .unwrap_or_default()
+    }
+
+    // 删除当前地图的传送点；它或其后没有 id 的传送点（键随序号变化）被触发区域引用时拒绝删除
+    pub fn remove_portal(&mut self, index: usize) -> Result<(), String> {
+        let map = self.current_map();
+        if index >= map.portals.len() {
+            return Err("传送点不存在".to_string());
+        }
+        let affected: Vec<String> = map
+            .portals
+            .iter()
+            .enumerate()
+            .filter(|(i, portal)| *i == index || (*i > index && portal.id.is_none()))
+            .map(|(i, portal)| portal.key(&map.id, i))
+            .collect();
+        for other in self.maps.values() {
+            let used =
+                trigger_portal_refs(other).find(|(_, key)| affected.iter().any(|k| k == key));
+            if let Some((trigger, key)) = used {
+                return Err(format!(
+                    "地图 {} 的触发区域 {trigger} 引用了传送点 {key}，请先修改",
+                    other.id
+                ));
+            }
+        }
+        self.current_map_mut().portals.remove(index);
+        Ok(())
+    }
+
+    // 写回主地图文件（先写临时文件再替换），返回写入的地图数
+    pub fn save_maps(&self) -> Result<usize, String> {
+        let path = self
+            .main_file
+            .as_deref()
+            .ok_or("内置默认地图不能写回文件")?;
+        if let Some(issue) = self.validate().errors().next() {
+            return Err(format!("校验未通过：{issue}"));
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("读取 {path} 失败：{e}"))?;
+        let (text, written) = self
+            .maps_file_text(&text)
+            .map_err(|e| format!("解析 {path} 失败：{e}"))?;
+        write_text_atomic(Path::new(path), &text).map_err(|e| format!("写入 {path} 失败：{e}"))?;
+        Ok(written)
+    }
+
+    // 由主地图文件原文生成写回的文本：按文件中原有顺序替换可编辑的地图、去掉已删除的地图，
+    // 新建的地图按 id 追加在末尾；来自其他文件的地图保持文件中的原样。同时返回写入的地图数
+    fn maps_file_text(&self, text: &str) -> Result<(String, usize), String> {
+        let mut config: MapsConfig =
+            serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())?;
+        let mut written = HashSet::new();
+        config.maps = std::mem::take(&mut config.maps)
+            .into_iter()
+            .filter_map(|old| match self.maps.get(&old.id) {
+                Some(map) if self.edit_lock(&map.id).is_none() => {
+                    written.insert(map.id.clone());
+                    Some(saved_copy(map))
+                }
+                Some(_) => Some(old),
+                None => None,
+            })
+            .collect();
+        for id in self.map_ids() {
+            if !written.contains(&id) && self.edit_lock(&id).is_none() {
+                config.maps.push(saved_copy(&self.maps[&id]));
+                written.insert(id);
+            }
+        }
+
+        let json = to_data_json(&config)?;
+        Ok((format!("\u{feff}{json}\n"), written.len()))
+    }
+}
+
+// 写回文件的地图副本：去掉加载时自动生成的回程传送点
+fn saved_copy(map: &MapConfig) -> MapConfig {
+    let mut map = map.clone();
+    map.portals.retain(|p| !p.generated);
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAPS_PATH;
+
+    // 加载随游戏发布的地图数据
+    fn shipped_world() -> World {
+        World::load_from_file(MAPS_PATH).unwrap().0
+    }
+
+    #[test]
+    fn saving_unchanged_maps_keeps_the_file_identical() {
+        let text = fs::read_to_string(MAPS_PATH).unwrap();
+        let world = shipped_world();
+        let (saved, written) = world.maps_file_text(&text).unwrap();
+        assert_eq!(saved, text);
+        assert_eq!(
+            written,
+            world
+                .maps()
+                .filter(|m| world.edit_lock(&m.id).is_none())
+                .count()
+        );
+    }
+
+    #[test]
+    fn new_portals_get_unique_ids() {
+        let mut world = shipped_world();
+        world.switch_map("qingtian".to_string());
+        let first = world.new_portal_id();
+        assert_eq!(first, "qingtian_portal_1");
+        let mut portal = world.current_map().portals[0].clone();
+        portal.id = Some(first.clone());
+        world.current_map_mut().portals.push(portal);
+        assert_eq!(world.new_portal_id(), "qingtian_portal_2");
+    }
+
+    #[test]
+    fn removing_a_referenced_portal_is_refused() {
+        let mut world = shipped_world();
+        world.switch_map("tianyi".to_string());
+        let count = world.current_map().portals.len();
+        let err = world.remove_portal(0).unwrap_err();
+        assert!(err.contains("tianyi_arena_seal"), "{err}");
+        assert_eq!(world.current_map().portals.len(), count);
+
+        // 删除前面的传送点会让后面没有 id 的传送点换键，同样拒绝
+        let mut unnamed = world.current_map().portals[0].clone();
+        unnamed.id = None;
+        let map = world.current_map_mut();
+        map.portals[0].id = None;
+        map.portals.insert(0, unnamed);
+        for trigger in &mut map.triggers {
+            for action in &mut trigger.actions {
+                if let TriggerAction::LockPortal { portal }
+                | TriggerAction::UnlockPortal { portal } = action
+                {
+                    *portal = "tianyi#1".to_string();
+                }
+            }
+        }
+        assert!(world.remove_portal(0).is_err());
+
+        world.switch_map("qingtian".to_string());
+        let count = world.current_map().portals.len();
+        world.remove_portal(0).unwrap();
+        assert_eq!(world.current_map().portals.len(), count - 1);
+    }
+}
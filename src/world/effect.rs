@@ -0,0 +1,154 @@
+﻿use serde::{Deserialize, Serialize};
+
+use crate::items::Inventory;
+use crate::world::progress::Progress;
+
+// 剧情效果：修改持久进度（设置标记、发放或收取物品）
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    SetFlag {
+        flag: String,
+    },
+    ClearFlag {
+        flag: String,
+    },
+    GiveItem {
+        item: String,
+        #[serde(default = "default_count", skip_serializing_if = "is_default_count")]
+        count: u32,
+    },
+    TakeItem {
+        item: String,
+        #[serde(default = "default_count", skip_serializing_if = "is_default_count")]
+        count: u32,
+    },
+}
+
+// 物品效果默认数量
+fn default_count() -> u32 {
+    1
+}
+
+// 写回时省略默认数量
+fn is_default_count(count: &u32) -> bool {
+    *count == default_count()
+}
+
+impl Effect {
+    // 应用效果，返回需要提示玩家的文本
+    pub fn apply(&self, progress: &mut Progress) -> Option<String> {
+        match self {
+            Effect::SetFlag { flag } => {
+                progress.flags.insert(flag.clone());
+                None
+            }
+            Effect::ClearFlag { flag } => {
+                progress.flags.remove(flag);
+                None
+            }
+            Effect::GiveItem { item, count } => {
+                let overflow = progress.inventory.add(item, *count);
+                let name = progress.inventory.name(item);
+                Some(match overflow {
+                    0 => format!("获得 {name}×{count}"),
+                    _ if overflow == *count => format!("背包已满，{name}×{count}未能收下"),
+                    _ => format!("获得 {name}×{}，背包已满", count - overflow),
+                })
+            }
+            Effect::TakeItem { item, count } => {
+                let removed = progress.inventory.remove(item, *count);
+                let name = progress.inventory.name(item);
+                (removed > 0).then(|| format!("失去 {name}×{removed}"))
+            }
+        }
+    }
+}
+
+// 依次应用一组效果，返回全部提示
+pub fn apply_each(effects: &[Effect], progress: &mut Progress) -> Vec<String> {
+    effects.iter().filter_map(|e| e.apply(progress)).collect()
+}
+
+// 在背包副本上按顺序试用一组效果，再放入 extra 中的物品，返回是否全部放得下
+pub fn fits<'a>(
+    effects: &[Effect],
+    inventory: &Inventory,
+    extra: impl IntoIterator<Item = (&'a str, u32)>,
+) -> bool {
+    let mut probe = inventory.clone();
+    for effect in effects {
+        match effect {
+            Effect::GiveItem { item, count } => {
+                if probe.add(item, *count) > 0 {
+                    return false;
+                }
+            }
+            Effect::TakeItem { item, count } => {
+                probe.remove(item, *count);
+            }
+            Effect::SetFlag { .. } | Effect::ClearFlag { .. } => {}
+        }
+    }
+    probe.can_fit(extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::INVENTORY_SLOTS;
+
+    // 每格放一种物品的满背包
+    fn full() -> Progress {
+        let mut progress = Progress::default();
+        for i in 0..INVENTORY_SLOTS {
+            assert_eq!(progress.inventory.add(&format!("junk{i}"), 1), 0);
+        }
+        progress
+    }
+
+    fn give(item: &str) -> Effect {
+        Effect::GiveItem {
+            item: item.to_string(),
+            count: 1,
+        }
+    }
+
+    #[test]
+    fn every_message_is_reported() {
+        let mut progress = Progress::default();
+        let effects = [
+            give("herb"),
+            Effect::SetFlag {
+                flag: "opened".to_string(),
+            },
+            give("stone"),
+        ];
+        assert_eq!(
+            apply_each(&effects, &mut progress),
+            ["获得 herb×1", "获得 stone×1"]
+        );
+        assert!(progress.has_flag("opened"));
+    }
+
+    #[test]
+    fn fit_check_follows_effect_order() {
+        let progress = full();
+        assert!(!fits(&[give("herb")], &progress.inventory, []));
+        let take = Effect::TakeItem {
+            item: "junk0".to_string(),
+            count: 1,
+        };
+        assert!(fits(&[take.clone(), give("herb")], &progress.inventory, []));
+        assert!(!fits(
+            &[give("herb"), take.clone()],
+            &progress.inventory,
+            []
+        ));
+        assert!(!fits(
+            &[take],
+            &progress.inventory,
+            [("herb", 1), ("stone", 1)]
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+﻿use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::save::FogSave;
+use crate::world::map::MapConfig;
+
+// 迷雾格子边长与玩家周围的探索半径（世界坐标）
+pub const FOG_CELL: f32 = 32.0;
+pub const REVEAL_RADIUS: f32 = 160.0;
+
+// 单张地图的迷雾：按格记录是否已探索
+#[derive(Clone, Debug)]
+pub struct FogMap {
+    width: usize,
+    height: usize,
+    bits: Vec<u8>,
+}
+
+impl FogMap {
+    // 按地图尺寸创建全未探索的迷雾
+    pub fn new(world_size: Vec2) -> Self {
+        let (width, height) = grid_size(world_size);
+        Self {
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(8)],
+        }
+    }
+
+    // 从存档恢复，记录损坏时返回 None（与地图尺寸不符的记录在探索时重建）
+    pub fn from_save(save: &FogSave) -> Option<Self> {
+        let mut fog = Self {
+            width: save.width.max(1),
+            height: save.height.max(1),
+            bits: Vec::new(),
+        };
+        fog.bits = vec![0; (fog.width * fog.height).div_ceil(8)];
+        let hex = save.cells.as_bytes();
+        if hex.len() != fog.bits.len() * 2 {
+            return None;
+        }
+        for (byte, pair) in fog.bits.iter_mut().zip(hex.chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        Some(fog)
+    }
+
+    // 生成存档记录
+    pub fn to_save(&self) -> FogSave {
+        FogSave {
+            width: self.width,
+            height: self.height,
+            cells: self.bits.iter().map(|b| format!("{b:02x}")).collect(),
+        }
+    }
+
+    // 格子列数与行数
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // 指定格子是否已探索
+    pub fn is_revealed(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && {
+            let i = y * self.width + x;
+            self.bits[i / 8] & (1 << (i % 8)) != 0
+        }
+    }
+
+    // 揭开以 pos 为圆心、radius 为半径范围内的格子
+    pub fn reveal(&mut self, pos: Vec2, radius: f32) {
+        let x0 = ((pos.x - radius) / FOG_CELL).floor().max(0.0) as usize;
+        let y0 = ((pos.y - radius) / FOG_CELL).floor().max(0.0) as usize;
+        let x1 = (((pos.x + radius) / FOG_CELL).floor().max(0.0) as usize).min(self.width - 1);
+        let y1 = (((pos.y + radius) / FOG_CELL).floor().max(0.0) as usize).min(self.height - 1);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let center = (vec2(x as f32, y as f32) + 0.5) * FOG_CELL;
+                if center.distance(pos) <= radius {
+                    let i = y * self.width + x;
+                    self.bits[i / 8] |= 1 << (i % 8);
+                }
+            }
+        }
+    }
+
+    // 坐标所在格子是否已探索
+    pub fn revealed_at(&self, pos: Vec2) -> bool {
+        pos.x >= 0.0
+            && pos.y >= 0.0
+            && self.is_revealed((pos.x / FOG_CELL) as usize, (pos.y / FOG_CELL) as usize)
+    }
+
+    // 已探索格子所占比例
+    pub fn explored_ratio(&self) -> f32 {
+        let revealed: u32 = self.bits.iter().map(|b| b.count_ones()).sum();
+        revealed as f32 / (self.width * self.height).max(1) as f32
+    }
+}
+
+// 地图尺寸对应的迷雾格子数（至少 1×1）
+fn grid_size(world_size: Vec2) -> (usize, usize) {
+    (
+        ((world_size.x / FOG_CELL).ceil() as usize).max(1),
+        ((world_size.y / FOG_CELL).ceil() as usize).max(1),
+    )
+}
+
+// 揭开玩家周围的迷雾；地图尚无记录或尺寸已变化时重新创建
+pub fn reveal_around(fog: &mut HashMap<String, FogMap>, map: &MapConfig, pos: Vec2) {
+    let size = grid_size(map.world_size());
+    let entry = fog
+        .entry(map.id.clone())
+        .or_insert_with(|| FogMap::new(map.world_size()));
+    if entry.size() != size {
+        *entry = FogMap::new(map.world_size());
+    }
+    entry.reveal(pos, REVEAL_RADIUS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 格子中心
+    fn center(x: usize, y: usize) -> Vec2 {
+        (vec2(x as f32, y as f32) + 0.5) * FOG_CELL
+    }
+
+    #[test]
+    fn reveals_cells_whose_center_is_in_range() {
+        let mut fog = FogMap::new(vec2(320.0, 320.0));
+        assert_eq!(fog.size(), (10, 10));
+        let pos = vec2(160.0, 160.0);
+        fog.reveal(pos, 64.0);
+        for y in 0..10 {
+            for x in 0..10 {
+                let inside = center(x, y).distance(pos) <= 64.0;
+                assert_eq!(fog.is_revealed(x, y), inside, "({x}, {y})");
+            }
+        }
+        assert!(fog.revealed_at(vec2(170.0, 150.0)));
+        assert!(!fog.revealed_at(vec2(10.0, 10.0)));
+        assert!(!fog.revealed_at(vec2(-10.0, 150.0)));
+        assert!(!fog.is_revealed(10, 0));
+    }
+
+    #[test]
+    fn reveal_near_or_past_the_edge_stays_in_bounds() {
+        let mut fog = FogMap::new(vec2(320.0, 320.0));
+        fog.reveal(vec2(-50.0, -50.0), 100.0);
+        fog.reveal(vec2(5000.0, 5000.0), 100.0);
+        assert!(fog.is_revealed(0, 0));
+        assert!(!fog.is_revealed(9, 9));
+    }
+
+    #[test]
+    fn bits_pack_into_hex_and_round_trip() {
+        // 3×5 格共 15 位，占 2 字节
+        let mut fog = FogMap::new(vec2(96.0, 160.0));
+        fog.reveal(center(0, 0), 1.0);
+        fog.reveal(center(0, 3), 1.0);
+        let save = fog.to_save();
+        assert_eq!((save.width, save.height), (3, 5));
+        assert_eq!(save.cells, "0102");
+
+        let loaded = FogMap::from_save(&save).unwrap();
+        for y in 0..5 {
+            for x in 0..3 {
+                assert_eq!(loaded.is_revealed(x, y), fog.is_revealed(x, y));
+            }
+        }
+        assert_eq!(loaded.explored_ratio(), 2.0 / 15.0);
+    }
+
+    #[test]
+    fn corrupt_fog_records_are_dropped() {
+        let save = |cells: &str| FogSave {
+            width: 3,
+            height: 5,
+            cells: cells.to_string(),
+        };
+        assert!(FogMap::from_save(&save("01")).is_none());
+        assert!(FogMap::from_save(&save("zz02")).is_none());
+        assert!(FogMap::from_save(&save("ff7f")).is_some());
+    }
+}
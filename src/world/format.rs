@@ -0,0 +1,196 @@
+﻿use serde::Serialize;
+
+// 单行书写的最大字符数：只含标量的对象/数组不超过该长度时写在一行内
+const INLINE_WIDTH: usize = 60;
+
+// JSON 节点：保留字段顺序，标量与键保持序列化时的原文
+enum Node {
+    Scalar(String),
+    Object(Vec<(String, Node)>),
+    Array(Vec<Node>),
+}
+
+// 按数据文件的书写风格输出 JSON：两空格缩进，短小的标量对象/数组写在一行，字段保持声明顺序
+pub fn to_data_json<T: Serialize>(value: &T) -> Result<String, String> {
+    let compact = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    let mut parser = Parser {
+        text: &compact,
+        pos: 0,
+    };
+    let node = parser.node()?;
+    let mut out = String::new();
+    write_node(&node, 0, &mut out);
+    Ok(out)
+}
+
+// 紧凑 JSON 文本的解析器（输入来自 serde_json，只需处理合法 JSON）
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    // 跳过空白后的下一个字节
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.text.as_bytes();
+        while bytes.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        bytes.get(self.pos).copied()
+    }
+
+    // 读取指定字节
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(format!(
+                "JSON 第 {} 字节处应为 '{}'",
+                self.pos, byte as char
+            ));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // 解析一个节点
+    fn node(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Node::Object(fields));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.node()?));
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b'}')?;
+                        return Ok(Node::Object(fields));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Node::Array(items));
+                }
+                loop {
+                    items.push(self.node()?);
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b']')?;
+                        return Ok(Node::Array(items));
+                    }
+                }
+            }
+            Some(b'"') => Ok(Node::Scalar(self.string()?)),
+            Some(_) => {
+                let start = self.pos;
+                let bytes = self.text.as_bytes();
+                while bytes
+                    .get(self.pos)
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                Ok(Node::Scalar(self.text[start..self.pos].to_string()))
+            }
+            None => Err("JSON 意外结束".to_string()),
+        }
+    }
+
+    // 读取字符串原文（含引号与转义）
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(format!("JSON 第 {} 字节处应为字符串", self.pos));
+        }
+        let bytes = self.text.as_bytes();
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(&b) = bytes.get(self.pos) {
+            self.pos += if b == b'\\' { 2 } else { 1 };
+            if b == b'"' {
+                return Ok(self.text[start..self.pos].to_string());
+            }
+        }
+        Err("JSON 字符串未结束".to_string())
+    }
+}
+
+// 只含标量的对象/数组在长度允许时的单行写法
+fn inline(node: &Node) -> Option<String> {
+    let line = match node {
+        Node::Scalar(text) => return Some(text.clone()),
+        Node::Object(fields) if !fields.is_empty() => {
+            let parts = fields
+                .iter()
+                .map(|(key, value)| match value {
+                    Node::Scalar(text) => Some(format!("{key}: {text}")),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format!("{{ {} }}", parts.join(", "))
+        }
+        Node::Array(items) if !items.is_empty() => {
+            let parts = items
+                .iter()
+                .map(|item| match item {
+                    Node::Scalar(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format!("[{}]", parts.join(", "))
+        }
+        _ => return None,
+    };
+    (line.chars().count() <= INLINE_WIDTH).then_some(line)
+}
+
+// 写出节点，indent 为当前缩进层级
+fn write_node(node: &Node, indent: usize, out: &mut String) {
+    if let Some(line) = inline(node) {
+        out.push_str(&line);
+        return;
+    }
+    let pad = "  ".repeat(indent + 1);
+    match node {
+        Node::Object(fields) if fields.is_empty() => out.push_str("{}"),
+        Node::Array(items) if items.is_empty() => out.push_str("[]"),
+        Node::Object(fields) => {
+            out.push_str("{\n");
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                out.push_str(key);
+                out.push_str(": ");
+                write_node(value, indent + 1, out);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+            out.push('}');
+        }
+        Node::Array(items) => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                write_node(item, indent + 1, out);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+            out.push(']');
+        }
+        Node::Scalar(text) => out.push_str(text),
+    }
+}
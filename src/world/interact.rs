@@ -0,0 +1,250 @@
+﻿use std::cmp::Ordering;
+
+use macroquad::prelude::*;
+
+use crate::world::map::PortalActivation;
+use crate::world::progress::Progress;
+use crate::world::World;
+
+// 各类对象的交互半径（传送点使用自身半径）
+const NPC_RADIUS: f32 = 36.0;
+const CHEST_RADIUS: f32 = 30.0;
+const SAVE_POINT_RADIUS: f32 = 34.0;
+const SIGN_RADIUS: f32 = 30.0;
+
+// 可交互对象类型
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InteractKind {
+    Npc,
+    Chest,
+    SavePoint,
+    Sign,
+    Portal,
+}
+
+impl InteractKind {
+    // 重叠时的优先级：数值越大越优先（范围较大的传送点让位于其他对象）
+    pub fn priority(self) -> u8 {
+        match self {
+            InteractKind::Npc => 4,
+            InteractKind::Chest => 3,
+            InteractKind::SavePoint => 2,
+            InteractKind::Sign => 1,
+            InteractKind::Portal => 0,
+        }
+    }
+}
+
+// 可交互目标：类型、在当前地图对应列表中的序号、位置与提示文字
+#[derive(Clone, Debug)]
+pub struct Interactable {
+    pub kind: InteractKind,
+    pub index: usize,
+    pub pos: Vec2,
+    pub prompt: String,
+}
+
+impl World {
+    // 选出玩家可触及的交互目标：先比优先级，同级取最近
+    pub fn nearest_interactable(&self, pos: Vec2, progress: &Progress) -> Option<Interactable> {
+        let map = self.current_map();
+        let mut candidates = Vec::new();
+        let mut consider = |kind, index, at: Vec2, radius: f32, prompt: String| {
+            let dist = pos.distance(at);
+            if dist <= radius {
+                let target = Interactable {
+                    kind,
+                    index,
+                    pos: at,
+                    prompt,
+                };
+                candidates.push((target, dist));
+            }
+        };
+
+        let phase = progress.clock.phase();
+        for (i, npc) in map.npcs.iter().enumerate() {
+            if !npc.is_present(phase) {
+                continue;
+            }
+            let prompt = format!("交谈·{}", npc.name);
+            consider(InteractKind::Npc, i, npc.pos.to_vec2(), NPC_RADIUS, prompt);
+        }
+        for (i, chest) in map.chests.iter().enumerate() {
+            if !progress.opened_chests.contains(&chest.key(&map.id)) {
+                let prompt = "打开宝箱".to_string();
+                consider(
+                    InteractKind::Chest,
+                    i,
+                    chest.pos.to_vec2(),
+                    CHEST_RADIUS,
+                    prompt,
+                );
+            }
+        }
+        for (i, point) in map.save_points.iter().enumerate() {
+            let prompt = "调息存档".to_string();
+            consider(
+                InteractKind::SavePoint,
+                i,
+                point.pos.to_vec2(),
+                SAVE_POINT_RADIUS,
+                prompt,
+            );
+        }
+        for (i, sign) in map.signs.iter().enumerate() {
+            let prompt = match &sign.title {
+                Some(title) => format!("查看·{title}"),
+                None => "查看告示".to_string(),
+            };
+            consider(
+                InteractKind::Sign,
+                i,
+                sign.pos.to_vec2(),
+                SIGN_RADIUS,
+                prompt,
+            );
+        }
+        // 踩踏式传送点踏入即触发，不作为交互目标
+        let portals = map
+            .portals
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.activation == PortalActivation::Interact);
+        for (i, portal) in portals {
+            let key = portal.key(&map.id, i);
+            let prompt = match portal.usable(&key, progress) {
+                Ok(()) => format!("前往{}", self.map_name(&portal.to_map)),
+                Err(_) if portal.access(&key, progress).is_ok() => "传送阵（未开启）".to_string(),
+                Err(_) => "传送阵（未解锁）".to_string(),
+            };
+            consider(
+                InteractKind::Portal,
+                i,
+                portal.pos.to_vec2(),
+                portal.radius,
+                prompt,
+            );
+        }
+
+        candidates
+            .into_iter()
+            .max_by(|(a, da), (b, db)| {
+                a.kind
+                    .priority()
+                    .cmp(&b.kind.priority())
+                    .then(db.partial_cmp(da).unwrap_or(Ordering::Equal))
+            })
+            .map(|(target, _)| target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::world::clock::GameClock;
+    use crate::world::map::MapsConfig;
+
+    const AT: Vec2 = vec2(100.0, 100.0);
+
+    // 各类对象都摆在 (100, 100) 的地图，extra 中的字段覆盖默认配置
+    fn world(extra: Value) -> World {
+        let pos = json!({ "x": 100.0, "y": 100.0 });
+        let mut map = json!({
+            "id": "a",
+            "name": "a",
+            "spawn": { "x": 10.0, "y": 10.0 },
+            "portals": [{
+                "pos": pos,
+                "radius": 40.0,
+                "to_map": "a",
+                "to_pos": { "x": 10.0, "y": 10.0 }
+            }],
+            "bosses": [],
+            "npcs": [{
+                "id": "elder",
+                "name": "长老",
+                "pos": pos,
+                "sprite": "elder",
+                "dialogue": "elder",
+                "schedule": ["day"]
+            }],
+            "chests": [{ "id": "chest", "pos": pos }],
+            "save_points": [{ "pos": pos }],
+            "signs": [{ "pos": pos, "text": "此路不通" }]
+        });
+        map.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let config: MapsConfig = serde_json::from_value(json!({
+            "start_map": "a",
+            "maps": [map]
+        }))
+        .unwrap();
+        World::from_config(config)
+    }
+
+    fn kind(world: &World, pos: Vec2, progress: &Progress) -> Option<InteractKind> {
+        world
+            .nearest_interactable(pos, progress)
+            .map(|target| target.kind)
+    }
+
+    #[test]
+    fn overlapping_targets_follow_priority() {
+        let progress = Progress::default();
+        let removed = [
+            ("npcs", InteractKind::Npc),
+            ("chests", InteractKind::Chest),
+            ("save_points", InteractKind::SavePoint),
+            ("signs", InteractKind::Sign),
+            ("portals", InteractKind::Portal),
+        ];
+        let mut extra = json!({});
+        for (field, expected) in removed {
+            assert_eq!(kind(&world(extra.clone()), AT, &progress), Some(expected));
+            extra[field] = json!([]);
+        }
+        assert_eq!(kind(&world(extra), AT, &progress), None);
+    }
+
+    #[test]
+    fn same_priority_picks_the_nearest() {
+        let progress = Progress::default();
+        let signs = json!({
+            "npcs": [],
+            "chests": [],
+            "save_points": [],
+            "signs": [
+                { "pos": { "x": 90.0, "y": 100.0 }, "text": "西" },
+                { "pos": { "x": 120.0, "y": 100.0 }, "text": "东" }
+            ]
+        });
+        let world = world(signs);
+        let pick = |x: f32, y: f32| {
+            let target = world.nearest_interactable(vec2(x, y), &progress).unwrap();
+            (target.kind, target.index)
+        };
+        assert_eq!(pick(100.0, 100.0), (InteractKind::Sign, 0));
+        assert_eq!(pick(112.0, 100.0), (InteractKind::Sign, 1));
+        // 告示牌都不在范围内时才轮到传送点
+        assert_eq!(pick(100.0, 130.0), (InteractKind::Portal, 0));
+    }
+
+    #[test]
+    fn skips_opened_chests_absent_npcs_and_touch_portals() {
+        let mut progress = Progress {
+            clock: GameClock::at(23.0),
+            ..Progress::default()
+        };
+        progress.opened_chests.insert("a#chest".to_string());
+        let world = world(json!({ "save_points": [], "signs": [] }));
+        assert_eq!(kind(&world, AT, &progress), Some(InteractKind::Portal));
+
+        let mut touch = world;
+        touch.current_map_mut().portals[0].activation = PortalActivation::Touch;
+        assert_eq!(kind(&touch, AT, &progress), None);
+    }
+}
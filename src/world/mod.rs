@@ -1,55 +1,127 @@
-﻿use std::collections::HashMap;
-use std::fs;
+﻿use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 use macroquad::prelude::*;
 
-use crate::world::map::{MapConfig, MapsConfig};
+use crate::world::effect::Effect;
+use crate::world::map::{MapConfig, MapsConfig, TransitionKind};
+use crate::world::progress::Progress;
+use crate::world::source::{assemble, Assembled, Assembler, Origin};
+use crate::world::validate::{MapIssue, ValidationReport, WorldError};
 
+pub mod area;
+pub mod background;
+pub mod clock;
+pub mod collision;
+pub mod condition;
+pub mod cultivation;
+pub mod dialogue;
+pub mod edit;
+pub mod effect;
+pub mod fog;
+pub mod format;
+pub mod interact;
 pub mod map;
+pub mod progress;
+pub mod source;
+pub mod tiled;
+pub mod travel;
+pub mod trigger;
+pub mod validate;
+pub mod watch;
+pub mod weather;
+
+// 传送判定结果：可通行（附带过场效果）或被锁定（附带原因）
+pub enum Teleport {
+    Go {
+        to_map: String,
+        to_pos: Vec2,
+        transition: TransitionKind,
+    },
+    Locked {
+        reason: String,
+    },
+}
 
 // 世界状态：当前地图与地图表
 pub struct World {
+    // 起始地图（世界地图布局的根）与当前地图
+    start: String,
     current: String,
     maps: HashMap<String, MapConfig>,
+    // 各地图的来源文件与被其他文件打过补丁的地图（编辑器据此判断能否写回）
+    origins: HashMap<String, Origin>,
+    patched: HashSet<String>,
+    // 主地图文件（maps.json），内置默认地图为 None
+    main_file: Option<String>,
+    // 组装地图数据时发现的问题：重复 id、mod 覆盖与补丁冲突等（后加载者生效）
+    load_issues: Vec<MapIssue>,
+    // 导入的 Tiled 地图与图块集文件（热重载时一并监视）
+    tiled_files: Vec<PathBuf>,
 }
 
 impl Default for World {
     // 默认世界：内置三张地图与两处传送点（闭环示例）
     fn default() -> Self {
-        let config = MapsConfig::default();
-        let maps = config
-            .maps
-            .into_iter()
-            .map(|m| (m.id.clone(), m))
-            .collect();
-        Self {
-            current: config.start_map,
-            maps,
-        }
+        Self::from_config(MapsConfig::default())
     }
 }
 
 impl World {
-    // 从JSON文件加载地图配置
-    pub fn load_from_file(path: &str) -> Result<Self, String> {
-        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
-        let config: MapsConfig = serde_json::from_str(&text).map_err(|e| e.to_string())?;
-        let maps = config
-            .maps
-            .into_iter()
-            .map(|m| (m.id.clone(), m))
-            .collect();
-        Ok(Self {
-            current: config.start_map,
-            maps,
-        })
+    // 由地图配置集合构建世界，记录重复 id 供校验使用
+    pub fn from_config(config: MapsConfig) -> Self {
+        let mut assembler = Assembler::default();
+        assembler.add_maps(config.maps, &Origin::base("内置地图"));
+        Self::from_assembled(assembler.finish(config.start_map), None)
+    }
+
+    // 由组装结果构建世界
+    fn from_assembled(assembled: Assembled, main_file: Option<String>) -> Self {
+        Self {
+            start: assembled.start_map.clone(),
+            current: assembled.start_map,
+            maps: assembled.maps,
+            origins: assembled.origins,
+            patched: assembled.patched,
+            main_file,
+            load_issues: assembled.issues,
+            tiled_files: assembled.tiled_files,
+        }
+    }
+
+    // 从 maps.json 及同目录下的地图目录、mod 目录组装世界（见 source 模块），存在错误时返回完整校验报告
+    pub fn load_from_file(path: &str) -> Result<(Self, ValidationReport), WorldError> {
+        let world = Self::from_assembled(assemble(path)?, Some(path.to_string()));
+        let report = world.validate();
+        if report.has_errors() {
+            return Err(WorldError::Invalid(report));
+        }
+        Ok((world, report))
     }
 
     // 获取当前地图配置
     pub fn current_map(&self) -> &MapConfig {
-        self.maps
-            .get(&self.current)
-            .expect("current map missing")
+        self.maps.get(&self.current).expect("current map missing")
+    }
+
+    // 是否存在指定地图
+    pub fn has_map(&self, id: &str) -> bool {
+        self.maps.contains_key(id)
+    }
+
+    // 按 id 获取地图显示名称，缺失时返回 id
+    pub fn map_name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.maps.get(id).map_or(id, |m| m.name.as_str())
+    }
+
+    // 导入的 Tiled 地图与图块集文件
+    pub fn tiled_files(&self) -> &[PathBuf] {
+        &self.tiled_files
+    }
+
+    // 遍历全部地图
+    pub fn maps(&self) -> impl Iterator<Item = &MapConfig> {
+        self.maps.values()
     }
 
     // 获取当前地图出生点
@@ -57,6 +129,18 @@ impl World {
         self.current_map().spawn.to_vec2()
     }
 
+    // 热重载：替换地图数据，当前地图仍存在时保留，返回是否保留
+    pub fn hot_swap(&mut self, fresh: World) -> bool {
+        let keep = fresh.maps.contains_key(&self.current);
+        let current = if keep {
+            std::mem::take(&mut self.current)
+        } else {
+            fresh.current
+        };
+        *self = Self { current, ..fresh };
+        keep
+    }
+
     // 切换当前地图
     pub fn switch_map(&mut self, map_id: String) {
         if self.maps.contains_key(&map_id) {
@@ -64,13 +148,41 @@ impl World {
         }
     }
 
-    // 传送点判定：在范围内并且解锁则返回目标地图与目标坐标
-    pub fn try_teleport(&self, pos: Vec2) -> Option<(String, Vec2)> {
-        for portal in &self.current_map().portals {
-            if portal.is_unlocked && portal.contains(pos) {
-                return Some((portal.to_map.clone(), portal.to_pos.to_vec2()));
-            }
+    // 查询当前地图的指定传送点：按解锁条件、开放时段与阵资返回目标或不可用的原因
+    pub fn teleport_via(&self, index: usize, progress: &Progress) -> Option<Teleport> {
+        let map = self.current_map();
+        let portal = map.portals.get(index)?;
+        let key = portal.key(&map.id, index);
+        Some(match portal.usable(&key, progress) {
+            Ok(()) => Teleport::Go {
+                to_map: portal.to_map.clone(),
+                to_pos: portal.to_pos.to_vec2(),
+                transition: portal.transition,
+            },
+            Err(reason) => Teleport::Locked { reason },
+        })
+    }
+
+    // 确认使用传送点：再次判定后扣除阵资，返回判定结果与扣费提示
+    pub fn try_teleport(
+        &self,
+        index: usize,
+        progress: &mut Progress,
+    ) -> Option<(Teleport, Option<String>)> {
+        let teleport = self.teleport_via(index, progress)?;
+        if !matches!(teleport, Teleport::Go { .. }) {
+            return Some((teleport, None));
         }
-        None
+        let paid = self.current_map().portals[index]
+            .cost
+            .as_ref()
+            .and_then(|cost| {
+                let take = Effect::TakeItem {
+                    item: cost.item.clone(),
+                    count: cost.count,
+                };
+                take.apply(progress)
+            });
+        Some((teleport, paid))
     }
 }
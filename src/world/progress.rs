@@ -0,0 +1,155 @@
+﻿use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::items::equipment::Equipment;
+use crate::items::{Inventory, ItemCatalog};
+use crate::save::WorldSave;
+use crate::world::clock::GameClock;
+use crate::world::cultivation::Cultivation;
+use crate::world::fog::FogMap;
+
+// 持久进度：剧情标记、已击败首领、被封锁的传送点、已开宝箱、已触发的一次性事件、到访过的地图与迷雾、游戏内时刻、境界修为、背包与装备
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    pub flags: HashSet<String>,
+    pub defeated_bosses: HashSet<String>,
+    pub locked_portals: HashSet<String>,
+    pub opened_chests: HashSet<String>,
+    pub fired_triggers: HashSet<String>,
+    pub visited_maps: HashSet<String>,
+    pub fog: HashMap<String, FogMap>,
+    pub clock: GameClock,
+    pub cultivation: Cultivation,
+    pub inventory: Inventory,
+    pub equipment: Equipment,
+}
+
+impl Progress {
+    // 是否设置了剧情标记
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    // 是否击败过指定首领
+    pub fn boss_defeated(&self, boss: &str) -> bool {
+        self.defeated_bosses.contains(boss)
+    }
+
+    // 持有指定物品的数量
+    pub fn item_count(&self, item: &str) -> u32 {
+        self.inventory.count(item)
+    }
+}
+
+impl Progress {
+    // 新的进度（背包按物品表堆叠）
+    pub fn new(catalog: Rc<ItemCatalog>) -> Self {
+        Self {
+            inventory: Inventory::new(catalog),
+            ..Self::default()
+        }
+    }
+
+    // 从存档记录恢复进度
+    pub fn from_save(save: &WorldSave, catalog: Rc<ItemCatalog>) -> Self {
+        Self {
+            flags: save.flags.iter().cloned().collect(),
+            defeated_bosses: save.defeated_bosses.iter().cloned().collect(),
+            locked_portals: save.locked_portals.iter().cloned().collect(),
+            opened_chests: save.opened_chests.iter().cloned().collect(),
+            fired_triggers: save.fired_triggers.iter().cloned().collect(),
+            visited_maps: save.visited_maps.iter().cloned().collect(),
+            fog: save
+                .fog
+                .iter()
+                .filter_map(|(map, fog)| Some((map.clone(), FogMap::from_save(fog)?)))
+                .collect(),
+            clock: GameClock::at(save.time_of_day),
+            cultivation: Cultivation {
+                realm: save.realm,
+                exp: save.exp,
+            },
+            inventory: Inventory::from_save(catalog, &save.inventory),
+            equipment: Equipment::from_save(&save.equipment),
+        }
+    }
+
+    // 生成存档记录（附带所在地图与位置）
+    pub fn to_save(&self, map: &str, pos: Vec2) -> WorldSave {
+        WorldSave {
+            map: map.to_string(),
+            x: pos.x,
+            y: pos.y,
+            locked_portals: sorted(&self.locked_portals),
+            defeated_bosses: sorted(&self.defeated_bosses),
+            opened_chests: sorted(&self.opened_chests),
+            fired_triggers: sorted(&self.fired_triggers),
+            visited_maps: sorted(&self.visited_maps),
+            flags: sorted(&self.flags),
+            realm: self.cultivation.realm,
+            exp: self.cultivation.exp,
+            inventory: self.inventory.to_save(),
+            equipment: self.equipment.to_save(),
+            fog: self
+                .fog
+                .iter()
+                .map(|(map, fog)| (map.clone(), fog.to_save()))
+                .collect(),
+            time_of_day: self.clock.hour(),
+        }
+    }
+}
+
+// 集合转为有序列表
+fn sorted(set: &HashSet<String>) -> Vec<String> {
+    let mut list: Vec<String> = set.iter().cloned().collect();
+    list.sort();
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::world::map::MapsConfig;
+    use crate::world::World;
+
+    // 两张地图都有 id 为 chest 的宝箱，另有一张地图有 id 为 lone 的宝箱
+    fn world() -> World {
+        let chest = |id: &str| json!({ "id": id, "pos": { "x": 48.0, "y": 48.0 } });
+        let map = |id: &str, chests: Vec<serde_json::Value>| {
+            json!({
+                "id": id,
+                "name": id,
+                "spawn": { "x": 48.0, "y": 48.0 },
+                "portals": [],
+                "bosses": [],
+                "chests": chests
+            })
+        };
+        let config: MapsConfig = serde_json::from_value(json!({
+            "start_map": "a",
+            "maps": [
+                map("a", vec![chest("chest")]),
+                map("b", vec![chest("chest"), chest("lone")])
+            ]
+        }))
+        .unwrap();
+        World::from_config(config)
+    }
+
+    #[test]
+    fn chests_with_the_same_id_are_tracked_per_map() {
+        let world = world();
+        let mut progress = Progress::default();
+        let map = world.current_map();
+        progress.opened_chests.insert(map.chests[0].key(&map.id));
+        let other = world.maps().find(|m| m.id == "b").unwrap();
+        assert!(!progress
+            .opened_chests
+            .contains(&other.chests[0].key(&other.id)));
+    }
+}
@@ -0,0 +1,704 @@
+﻿use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::world::clock::DayPhase;
+use crate::world::condition::Condition;
+use crate::world::map::{
+    MapConfig, MapsConfig, PortalActivation, PortalConfig, PortalCost, TransitionKind, Vec2Def,
+};
+use crate::world::tiled::import_tiled;
+use crate::world::validate::{MapIssue, WorldError};
+
+// 地图目录与 mod 目录（相对 maps.json 所在目录）
+pub const MAP_DIR: &str = "maps";
+pub const MOD_DIR: &str = "mods";
+
+// mod 清单文件名（可选）
+const MOD_MANIFEST: &str = "mod.json";
+
+// 地图片段：地图目录或 mod 目录中的单个 JSON 文件
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MapFragment {
+    #[serde(default)]
+    pub maps: Vec<MapConfig>,
+    // 从 Tiled 导入的地图文件（路径相对片段所在目录）
+    #[serde(default)]
+    pub tiled: Vec<String>,
+    #[serde(default)]
+    pub portal_patches: Vec<PortalPatch>,
+}
+
+// mod 清单：显示名、加载优先级（小的先加载，后加载的覆盖先加载的）与开关
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModManifest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+// 未声明时 mod 默认启用
+fn default_enabled() -> bool {
+    true
+}
+
+// 传送点补丁：修改已有传送点的部分字段，或向地图追加新传送点
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PortalPatch {
+    // portal 为传送点键（见 PortalConfig::key）
+    Modify {
+        map: String,
+        portal: String,
+        #[serde(flatten)]
+        changes: PortalChanges,
+    },
+    Add {
+        map: String,
+        #[serde(flatten)]
+        portal: PortalConfig,
+    },
+}
+
+// 传送点的可修改字段，未填写的保持原值（阵资只能设置或替换，不能移除）
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PortalChanges {
+    #[serde(default)]
+    pub pos: Option<Vec2Def>,
+    #[serde(default)]
+    pub radius: Option<f32>,
+    #[serde(default)]
+    pub to_map: Option<String>,
+    #[serde(default)]
+    pub to_pos: Option<Vec2Def>,
+    #[serde(default)]
+    pub is_unlocked: Option<bool>,
+    #[serde(default)]
+    pub requires: Option<Vec<Condition>>,
+    #[serde(default)]
+    pub transition: Option<TransitionKind>,
+    #[serde(default)]
+    pub activation: Option<PortalActivation>,
+    #[serde(default)]
+    pub paired: Option<bool>,
+    #[serde(default)]
+    pub cost: Option<PortalCost>,
+    #[serde(default)]
+    pub schedule: Option<Vec<DayPhase>>,
+    #[serde(default)]
+    pub confirm: Option<bool>,
+}
+
+impl PortalChanges {
+    // 把填写了的字段写入传送点
+    fn apply(self, portal: &mut PortalConfig) {
+        if let Some(pos) = self.pos {
+            portal.pos = pos;
+        }
+        if let Some(radius) = self.radius {
+            portal.radius = radius;
+        }
+        if let Some(to_map) = self.to_map {
+            portal.to_map = to_map;
+        }
+        if let Some(to_pos) = self.to_pos {
+            portal.to_pos = to_pos;
+        }
+        if let Some(is_unlocked) = self.is_unlocked {
+            portal.is_unlocked = is_unlocked;
+        }
+        if let Some(requires) = self.requires {
+            portal.requires = requires;
+        }
+        if let Some(transition) = self.transition {
+            portal.transition = transition;
+        }
+        if let Some(activation) = self.activation {
+            portal.activation = activation;
+        }
+        if let Some(paired) = self.paired {
+            portal.paired = paired;
+        }
+        if let Some(cost) = self.cost {
+            portal.cost = Some(cost);
+        }
+        if let Some(schedule) = self.schedule {
+            portal.schedule = schedule;
+        }
+        if let Some(confirm) = self.confirm {
+            portal.confirm = confirm;
+        }
+    }
+}
+
+// 数据来源：文件路径与所属 mod（基础数据为 None）
+#[derive(Clone, Debug)]
+pub struct Origin {
+    pub file: String,
+    pub mod_name: Option<String>,
+}
+
+impl Origin {
+    // 基础数据来源
+    pub fn base(file: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            mod_name: None,
+        }
+    }
+
+    // 报告中使用的来源描述
+    pub fn label(&self) -> String {
+        match &self.mod_name {
+            Some(name) => format!("mod {name}（{}）", self.file),
+            None => self.file.clone(),
+        }
+    }
+}
+
+// 组装结果：起始地图、合并后的地图表、各地图来源与组装过程中发现的问题
+pub struct Assembled {
+    pub start_map: String,
+    pub maps: HashMap<String, MapConfig>,
+    pub origins: HashMap<String, Origin>,
+    // 被其他文件的补丁修改过的地图
+    pub patched: HashSet<String>,
+    pub issues: Vec<MapIssue>,
+    // 导入的 Tiled 地图与图块集文件
+    pub tiled_files: Vec<PathBuf>,
+}
+
+// 世界组装器：按加载顺序合并地图与补丁，记录覆盖与冲突
+#[derive(Default)]
+pub struct Assembler {
+    maps: HashMap<String, MapConfig>,
+    origins: HashMap<String, Origin>,
+    // 已打过补丁的传送点（地图, 传送点键）及补丁来源
+    patched: HashMap<(String, String), Origin>,
+    // 被其他文件的补丁修改过的地图（整张替换后清除）
+    patched_maps: HashSet<String>,
+    issues: Vec<MapIssue>,
+    tiled_files: Vec<PathBuf>,
+}
+
+impl Assembler {
+    // 加入一组地图：基础数据间重复 id 为错误，mod 覆盖已有地图时给出警告
+    pub fn add_maps(&mut self, maps: Vec<MapConfig>, origin: &Origin) {
+        for map in maps {
+            if let Some(old) = self.origins.get(&map.id) {
+                let issue = if old.mod_name == origin.mod_name {
+                    MapIssue::DuplicateMapId {
+                        id: map.id.clone(),
+                        first: old.label(),
+                        second: origin.label(),
+                    }
+                } else if let (Some(first), Some(second)) = (&old.mod_name, &origin.mod_name) {
+                    MapIssue::ModConflict {
+                        map: map.id.clone(),
+                        first: first.clone(),
+                        second: second.clone(),
+                    }
+                } else {
+                    MapIssue::MapOverridden {
+                        map: map.id.clone(),
+                        source: old.label(),
+                        by: origin.label(),
+                    }
+                };
+                self.issues.push(issue);
+                // 整张地图被替换后，之前的补丁随之失效
+                self.patched.retain(|(id, _), _| *id != map.id);
+                self.patched_maps.remove(&map.id);
+            }
+            self.origins.insert(map.id.clone(), origin.clone());
+            self.maps.insert(map.id.clone(), map);
+        }
+    }
+
+    // 按顺序应用传送点补丁；目标不存在为错误，不同来源修改同一传送点给出警告
+    pub fn apply_patches(&mut self, patches: Vec<PortalPatch>, origin: &Origin) {
+        for patch in patches {
+            match patch {
+                PortalPatch::Modify {
+                    map,
+                    portal,
+                    changes,
+                } => {
+                    let target = self.maps.get_mut(&map).and_then(|config| {
+                        let index = (0..config.portals.len())
+                            .find(|&i| config.portals[i].key(&config.id, i) == portal)?;
+                        config.portals.get_mut(index)
+                    });
+                    let Some(target) = target else {
+                        self.issues.push(MapIssue::PatchTargetMissing {
+                            map,
+                            portal,
+                            source: origin.label(),
+                        });
+                        continue;
+                    };
+                    if target.id.is_none() {
+                        self.issues.push(MapIssue::UnnamedPortalRef {
+                            portal: portal.clone(),
+                            source: format!("{} 的传送点补丁", origin.label()),
+                        });
+                    }
+                    changes.apply(target);
+                    self.mark_patched(&map, origin);
+                    if let Some(old) = self
+                        .patched
+                        .insert((map.clone(), portal.clone()), origin.clone())
+                    {
+                        if old.file != origin.file {
+                            self.issues.push(MapIssue::PortalPatchConflict {
+                                map,
+                                portal,
+                                first: old.label(),
+                                second: origin.label(),
+                            });
+                        }
+                    }
+                }
+                PortalPatch::Add { map, portal } => match self.maps.get_mut(&map) {
+                    Some(config) => {
+                        config.portals.push(portal);
+                        self.mark_patched(&map, origin);
+                    }
+                    None => self.issues.push(MapIssue::PatchTargetMissing {
+                        map,
+                        portal: portal.id.unwrap_or_else(|| "新传送点".to_string()),
+                        source: origin.label(),
+                    }),
+                },
+            }
+        }
+    }
+
+    // 记录地图被来源文件以外的补丁修改（编辑器不能写回这类地图）
+    fn mark_patched(&mut self, map: &str, origin: &Origin) {
+        if self.origins.get(map).is_some_and(|o| o.file != origin.file) {
+            self.patched_maps.insert(map.to_string());
+        }
+    }
+
+    // 导入 Tiled 地图，来源记为 Tiled 文件本身
+    fn add_tiled(
+        &mut self,
+        dir: &Path,
+        files: Vec<String>,
+        origin: &Origin,
+    ) -> Result<(), WorldError> {
+        for file in files {
+            let path = dir.join(file);
+            let (map, files) = import_tiled(&path).map_err(WorldError::Tiled)?;
+            self.tiled_files.extend(files);
+            let tiled_origin = Origin {
+                file: path.display().to_string(),
+                mod_name: origin.mod_name.clone(),
+            };
+            self.add_maps(vec![map], &tiled_origin);
+        }
+        Ok(())
+    }
+
+    // 加入一个片段：先导入其中的地图，再应用补丁
+    fn add_fragment(&mut self, path: &Path, origin: &Origin) -> Result<(), WorldError> {
+        let fragment: MapFragment = read_json(path)?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        self.add_maps(fragment.maps, origin);
+        self.add_tiled(dir, fragment.tiled, origin)?;
+        self.apply_patches(fragment.portal_patches, origin);
+        Ok(())
+    }
+
+    // 取出合并结果（此时才生成回程传送点，补丁对 paired 的修改同样生效）
+    pub fn finish(mut self, start_map: String) -> Assembled {
+        link_paired_portals(&mut self.maps);
+        Assembled {
+            start_map,
+            maps: self.maps,
+            origins: self.origins,
+            patched: self.patched_maps,
+            issues: self.issues,
+            tiled_files: self.tiled_files,
+        }
+    }
+}
+
+// 为 paired 传送点在目标地图的落点生成回程传送点；目标地图在落点处已有通往来源地图的传送点时不再生成。
+// 回程传送点只沿用半径与过场效果：始终开启、免费、全天可用、按键触发且无需确认
+fn link_paired_portals(maps: &mut HashMap<String, MapConfig>) {
+    let mut returns = Vec::new();
+    for map in maps.values() {
+        for (idx, portal) in map.portals.iter().enumerate() {
+            if !portal.paired || portal.generated {
+                continue;
+            }
+            let Some(target) = maps.get(&portal.to_map) else {
+                continue;
+            };
+            let exists = target
+                .portals
+                .iter()
+                .any(|p| p.to_map == map.id && p.contains(portal.to_pos.to_vec2()));
+            if exists {
+                continue;
+            }
+            let back = PortalConfig {
+                id: Some(format!("{}:return", portal.key(&map.id, idx))),
+                pos: portal.to_pos,
+                to_map: map.id.clone(),
+                to_pos: portal.pos,
+                is_unlocked: true,
+                requires: Vec::new(),
+                activation: PortalActivation::default(),
+                paired: false,
+                cost: None,
+                schedule: Vec::new(),
+                confirm: false,
+                generated: true,
+                ..portal.clone()
+            };
+            returns.push((portal.to_map.clone(), back));
+        }
+    }
+    // 按目标地图与 id 排序，回程传送点的序号与键保持稳定
+    returns.sort_by(|(a, x), (b, y)| (a, &x.id).cmp(&(b, &y.id)));
+    for (to_map, back) in returns {
+        if let Some(target) = maps.get_mut(&to_map) {
+            target.portals.push(back);
+        }
+    }
+}
+
+// 从 maps.json 组装世界数据：主文件 → 地图目录（按文件名）→ 各 mod（按优先级与目录名）
+pub fn assemble(path: &str) -> Result<Assembled, WorldError> {
+    let text = fs::read_to_string(path)?;
+    // 兼容带 BOM 的 UTF-8 文件
+    let text = text.trim_start_matches('\u{feff}');
+    let config: MapsConfig = serde_json::from_str(text)?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+
+    let mut assembler = Assembler::default();
+    let origin = Origin::base(path);
+    assembler.add_maps(config.maps, &origin);
+    assembler.add_tiled(dir, config.tiled, &origin)?;
+    for file in json_files(&dir.join(MAP_DIR))? {
+        let origin = Origin::base(file.display().to_string());
+        assembler.add_fragment(&file, &origin)?;
+    }
+    for (name, mod_dir) in mod_dirs(&dir.join(MOD_DIR))? {
+        for file in json_files(&mod_dir)? {
+            let origin = Origin {
+                file: file.display().to_string(),
+                mod_name: Some(name.clone()),
+            };
+            assembler.add_fragment(&file, &origin)?;
+        }
+    }
+    Ok(assembler.finish(config.start_map))
+}
+
+// 按加载顺序列出启用的 mod：(显示名, 目录)
+fn mod_dirs(root: &Path) -> Result<Vec<(String, PathBuf)>, WorldError> {
+    let mut mods = Vec::new();
+    for dir in sorted_entries(root)?.into_iter().filter(|p| p.is_dir()) {
+        let manifest_path = dir.join(MOD_MANIFEST);
+        let manifest: Option<ModManifest> = if manifest_path.is_file() {
+            Some(read_json(&manifest_path)?)
+        } else {
+            None
+        };
+        if manifest.as_ref().is_some_and(|m| !m.enabled) {
+            continue;
+        }
+        let dir_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let priority = manifest.as_ref().map_or(0, |m| m.priority);
+        let name = manifest.and_then(|m| m.name).unwrap_or(dir_name);
+        mods.push((priority, name, dir));
+    }
+    // 稳定排序：同优先级保持目录名顺序
+    mods.sort_by_key(|(priority, _, _)| *priority);
+    Ok(mods.into_iter().map(|(_, name, dir)| (name, dir)).collect())
+}
+
+// 目录中按文件名排序的 JSON 文件（不含 mod 清单）
+fn json_files(dir: &Path) -> Result<Vec<PathBuf>, WorldError> {
+    Ok(sorted_entries(dir)?
+        .into_iter()
+        .filter(|p| {
+            p.is_file()
+                && p.extension().is_some_and(|e| e == "json")
+                && p.file_name().is_some_and(|n| n != MOD_MANIFEST)
+        })
+        .collect())
+}
+
+// 按文件名排序的目录项，目录不存在时视为空
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, WorldError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = fs::read_dir(dir)
+        .and_then(|rd| {
+            rd.map(|e| e.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| file_error(dir, e))?;
+    entries.sort();
+    Ok(entries)
+}
+
+// 读取并解析 JSON 文件（兼容 BOM），错误信息带上文件路径
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, WorldError> {
+    let text = fs::read_to_string(path).map_err(|e| file_error(path, e))?;
+    serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| file_error(path, e))
+}
+
+// 带路径的文件错误
+fn file_error(path: &Path, e: impl std::fmt::Display) -> WorldError {
+    WorldError::File {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // 只有一个传送点 gate 的地图
+    fn map(id: &str, gate_radius: f32) -> MapConfig {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": id,
+            "spawn": { "x": 100.0, "y": 100.0 },
+            "portals": [{
+                "id": "gate",
+                "pos": { "x": 300.0, "y": 100.0 },
+                "radius": gate_radius,
+                "to_map": "town",
+                "to_pos": { "x": 100.0, "y": 100.0 }
+            }],
+            "bosses": []
+        }))
+        .unwrap()
+    }
+
+    // 修改传送点半径的补丁
+    fn patch(map: &str, portal: &str, radius: f32) -> PortalPatch {
+        serde_json::from_value(json!({
+            "op": "modify",
+            "map": map,
+            "portal": portal,
+            "radius": radius
+        }))
+        .unwrap()
+    }
+
+    // mod 来源
+    fn from_mod(name: &str) -> Origin {
+        Origin {
+            file: format!("mods/{name}/maps.json"),
+            mod_name: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn mods_load_by_priority_then_name() {
+        let root = std::env::temp_dir().join(format!("airfight_mods_{}", std::process::id()));
+        let manifests = [
+            ("a_late", Some(r#"{ "priority": 5 }"#)),
+            ("b_early", Some(r#"{ "name": "早", "priority": -1 }"#)),
+            ("c_plain", None),
+            ("d_off", Some(r#"{ "priority": -9, "enabled": false }"#)),
+            ("e_plain", Some("{}")),
+        ];
+        for (dir, manifest) in manifests {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            if let Some(text) = manifest {
+                fs::write(root.join(dir).join(MOD_MANIFEST), text).unwrap();
+            }
+        }
+        let names: Vec<String> = mod_dirs(&root)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(names, vec!["早", "c_plain", "e_plain", "a_late"]);
+    }
+
+    #[test]
+    fn later_sources_override_maps_and_report_it() {
+        let mut assembler = Assembler::default();
+        assembler.add_maps(vec![map("town", 20.0)], &Origin::base("maps.json"));
+        assembler.add_maps(vec![map("town", 30.0)], &from_mod("a"));
+        assembler.add_maps(vec![map("town", 40.0)], &from_mod("b"));
+        assembler.add_maps(vec![map("town", 50.0)], &from_mod("b"));
+        let assembled = assembler.finish("town".to_string());
+        assert_eq!(assembled.maps["town"].portals[0].radius, 50.0);
+        assert!(matches!(
+            &assembled.issues[..],
+            [
+                MapIssue::MapOverridden { map, .. },
+                MapIssue::ModConflict { first, second, .. },
+                MapIssue::DuplicateMapId { .. },
+            ] if map == "town" && first == "a" && second == "b"
+        ));
+    }
+
+    #[test]
+    fn conflicting_portal_patches_are_reported() {
+        let mut assembler = Assembler::default();
+        assembler.add_maps(vec![map("town", 20.0)], &Origin::base("maps.json"));
+        let a = from_mod("a");
+        assembler.apply_patches(vec![patch("town", "gate", 10.0)], &a);
+        // 同一文件再次修改不算冲突
+        assembler.apply_patches(vec![patch("town", "gate", 12.0)], &a);
+        assembler.apply_patches(vec![patch("town", "gate", 14.0)], &from_mod("b"));
+        let assembled = assembler.finish("town".to_string());
+        assert_eq!(assembled.maps["town"].portals[0].radius, 14.0);
+        assert!(assembled.patched.contains("town"));
+        assert!(matches!(
+            &assembled.issues[..],
+            [MapIssue::PortalPatchConflict { portal, first, second, .. }]
+                if portal == "gate" && first.contains("mod a") && second.contains("mod b")
+        ));
+    }
+
+    #[test]
+    fn replacing_a_map_discards_earlier_patches() {
+        let mut assembler = Assembler::default();
+        assembler.add_maps(vec![map("town", 20.0)], &Origin::base("maps.json"));
+        assembler.apply_patches(vec![patch("town", "gate", 10.0)], &from_mod("a"));
+        assembler.add_maps(vec![map("town", 30.0)], &from_mod("b"));
+        let replaced = assembler.maps["town"].portals[0].radius;
+        assert!(!assembler.patched_maps.contains("town"));
+        // 替换后再打补丁不与已失效的补丁冲突
+        assembler.apply_patches(vec![patch("town", "gate", 35.0)], &from_mod("c"));
+        let assembled = assembler.finish("town".to_string());
+        assert_eq!(replaced, 30.0);
+        assert_eq!(assembled.maps["town"].portals[0].radius, 35.0);
+        assert!(matches!(
+            &assembled.issues[..],
+            [MapIssue::MapOverridden { .. }]
+        ));
+    }
+
+    #[test]
+    fn patches_need_a_named_target() {
+        let mut unnamed = map("town", 20.0);
+        unnamed.portals[0].id = None;
+        let mut assembler = Assembler::default();
+        assembler.add_maps(vec![unnamed], &Origin::base("maps.json"));
+        let a = from_mod("a");
+        assembler.apply_patches(
+            vec![patch("town", "gate", 10.0), patch("town", "town#0", 10.0)],
+            &a,
+        );
+        let assembled = assembler.finish("town".to_string());
+        assert!(matches!(
+            &assembled.issues[..],
+            [
+                MapIssue::PatchTargetMissing { portal: missing, .. },
+                MapIssue::UnnamedPortalRef { portal, .. },
+            ] if missing == "gate" && portal == "town#0"
+        ));
+    }
+
+    #[test]
+    fn return_portals_only_keep_radius_and_transition() {
+        let mut town = map("town", 20.0);
+        let mut field = map("field", 20.0);
+        field.portals.clear();
+        town.portals[0] = serde_json::from_value(json!({
+            "id": "gate",
+            "pos": { "x": 300.0, "y": 100.0 },
+            "radius": 18.0,
+            "to_map": "field",
+            "to_pos": { "x": 50.0, "y": 50.0 },
+            "requires": [{ "type": "flag", "flag": "pass" }],
+            "transition": "iris",
+            "activation": "touch",
+            "paired": true,
+            "cost": { "count": 3 },
+            "schedule": ["night"],
+            "confirm": true
+        }))
+        .unwrap();
+        let mut assembler = Assembler::default();
+        assembler.add_maps(vec![town, field], &Origin::base("maps.json"));
+        let assembled = assembler.finish("town".to_string());
+        let back = &assembled.maps["field"].portals[0];
+        assert!(back.generated && !back.paired && back.is_unlocked);
+        assert_eq!(back.id.as_deref(), Some("gate:return"));
+        assert_eq!((back.pos.x, back.pos.y), (50.0, 50.0));
+        assert_eq!((back.to_map.as_str(), back.to_pos.x), ("town", 300.0));
+        assert_eq!(back.radius, 18.0);
+        assert_eq!(back.transition, TransitionKind::Iris);
+        assert_eq!(back.activation, PortalActivation::Interact);
+        assert!(back.requires.is_empty() && back.schedule.is_empty());
+        assert!(back.cost.is_none() && !back.confirm);
+    }
+
+    #[test]
+    fn patches_portal_variant_fields() {
+        let mut assembler = Assembler::default();
+        assembler.add_maps(
+            vec![map("town", 20.0), map("field", 20.0)],
+            &Origin::base("maps.json"),
+        );
+        let patch: PortalPatch = serde_json::from_value(json!({
+            "op": "modify",
+            "map": "field",
+            "portal": "gate",
+            "activation": "touch",
+            "paired": true,
+            "cost": { "count": 2 },
+            "schedule": ["night"],
+            "confirm": true
+        }))
+        .unwrap();
+        assembler.apply_patches(vec![patch], &from_mod("a"));
+        let assembled = assembler.finish("town".to_string());
+        assert!(assembled.issues.is_empty(), "{:?}", assembled.issues);
+        let gate = &assembled.maps["field"].portals[0];
+        assert_eq!(gate.activation, PortalActivation::Touch);
+        assert!(gate.paired && gate.confirm);
+        let cost = gate.cost.as_ref().unwrap();
+        assert_eq!((cost.item.as_str(), cost.count), ("spirit_stone", 2));
+        assert_eq!(gate.schedule, [DayPhase::Night]);
+        // paired 补丁在组装结束时同样生成回程传送点
+        let back = assembled.maps["town"].portals.iter().find(|p| p.generated);
+        assert_eq!(back.unwrap().to_map, "field");
+        let gate = &assembled.maps["town"].portals[0];
+        assert_eq!(gate.activation, PortalActivation::Interact);
+        assert!(gate.cost.is_none());
+    }
+
+    #[test]
+    fn shipped_sample_mod_patches_existing_portals() {
+        let config: MapsConfig = read_json(Path::new("data/maps.json")).unwrap();
+        let mut assembler = Assembler::default();
+        assembler.add_maps(config.maps, &Origin::base("data/maps.json"));
+        let file = Path::new("data/mods/lantern_festival/festival.json");
+        assembler
+            .add_fragment(file, &from_mod("lantern_festival"))
+            .unwrap();
+        let assembled = assembler.finish(config.start_map);
+        assert!(assembled.issues.is_empty(), "{:?}", assembled.issues);
+        assert!(assembled.maps.contains_key("denghui"));
+    }
+}
@@ -0,0 +1,642 @@
+﻿use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::sprite::{parse_hex_color, ColorDef};
+use crate::world::map::{
+    default_npc_scale, BossSpawn, ChestConfig, MapConfig, NpcConfig, PortalActivation,
+    PortalConfig, SavePointConfig, SignConfig, TileGrid, TransitionKind, Vec2Def,
+};
+
+mod tmj;
+mod tmx;
+mod xml;
+
+// gid 高 4 位为翻转/旋转标记
+const GID_MASK: u32 = 0x0fff_ffff;
+// 导入后可分配给图块的字符（'.' 为空地，'#' 为无图案的阻挡格）
+const TILE_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+// 未指定半径时的传送点半径
+const DEFAULT_PORTAL_RADIUS: f32 = 26.0;
+
+// 自定义属性（值统一保存为字符串）
+type Properties = HashMap<String, String>;
+
+// TMJ 与 TMX 解析后的统一结构，只保留导入需要的部分
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_size: f32,
+    properties: Properties,
+    tilesets: Vec<Tileset>,
+    layers: Vec<Layer>,
+}
+
+// 图块集：首个 gid、各图块（按本地 id）的自定义属性与外部图块集的文件路径
+struct Tileset {
+    first_gid: u32,
+    tiles: HashMap<u32, Properties>,
+    source: Option<PathBuf>,
+}
+
+// 图层：图块层的 gid 按行排列，组图层在解析时已展开
+enum Layer {
+    Tiles {
+        name: String,
+        visible: bool,
+        properties: Properties,
+        data: Vec<u32>,
+    },
+    Objects {
+        objects: Vec<Object>,
+    },
+}
+
+// 对象层中的对象（点对象的宽高为 0）
+struct Object {
+    name: String,
+    kind: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    properties: Properties,
+}
+
+// 导入 Tiled 地图文件（.tmj/.json 或 .tmx），地图 id 缺省为文件名；同时返回导入时读取的文件（地图与外部图块集，供热重载监视）
+pub fn import_tiled(path: &Path) -> Result<(MapConfig, Vec<PathBuf>), String> {
+    let result = (|| {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let text = text.trim_start_matches('\u{feff}');
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let map = match extension(path) {
+            "tmx" => tmx::parse_map(text, dir)?,
+            "tmj" | "json" => tmj::parse_map(text, dir)?,
+            other => return Err(format!("不支持的地图格式 .{other}")),
+        };
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("tiled");
+        let mut files = vec![path.to_path_buf()];
+        files.extend(map.tilesets.iter().filter_map(|t| t.source.clone()));
+        Ok((map.into_config(stem)?, files))
+    })();
+    result.map_err(|e| format!("{}: {e}", path.display()))
+}
+
+// 读取外部图块集文件（.tsj/.json 或 .tsx）
+fn load_tileset(dir: &Path, source: &str, first_gid: u32) -> Result<Tileset, String> {
+    let path = dir.join(source);
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let text = text.trim_start_matches('\u{feff}');
+    let tileset = match extension(&path) {
+        "tsx" => tmx::parse_tileset(text, first_gid),
+        _ => tmj::parse_tileset(text, first_gid),
+    }
+    .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Tileset {
+        source: Some(path),
+        ..tileset
+    })
+}
+
+// 文件扩展名
+fn extension(path: &Path) -> &str {
+    path.extension().and_then(|e| e.to_str()).unwrap_or("")
+}
+
+// 解码图块层数据：支持 CSV 与未压缩的 Base64（小端 u32）
+fn decode_gids(encoding: &str, compression: Option<&str>, text: &str) -> Result<Vec<u32>, String> {
+    if let Some(method) = compression.filter(|c| !c.is_empty()) {
+        return Err(format!(
+            "不支持压缩的图块数据（{method}），请在 Tiled 中改用 CSV 或未压缩的 Base64"
+        ));
+    }
+    match encoding {
+        "csv" => text
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<u32>().map_err(|_| format!("无效的图块编号：{v}")))
+            .collect(),
+        "base64" => {
+            let bytes = decode_base64(text)?;
+            if bytes.len() % 4 != 0 {
+                return Err("Base64 图块数据长度不是 4 的倍数".to_string());
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+        other => Err(format!("不支持的图块编码：{other}")),
+    }
+}
+
+// 标准 Base64 解码（忽略空白字符）
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for ch in text.bytes() {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            c => return Err(format!("无效的 Base64 字符：{}", c as char)),
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+// 属性值是否为真（布尔属性导出为 true/false）
+fn is_true(value: Option<&String>) -> bool {
+    matches!(value.map(String::as_str), Some("true" | "1"))
+}
+
+impl TiledMap {
+    // 转换为地图配置：图块层生成瓦片网格，对象层生成出生点、传送点与首领等
+    fn into_config(self, fallback_id: &str) -> Result<MapConfig, String> {
+        let id = self
+            .properties
+            .get("id")
+            .cloned()
+            .unwrap_or_else(|| fallback_id.to_string());
+        let name = self
+            .properties
+            .get("name")
+            .cloned()
+            .unwrap_or_else(|| id.clone());
+        let mut config = MapConfig {
+            id,
+            name,
+            size: None,
+            spawn: Vec2Def::new(0.0, 0.0),
+            portals: Vec::new(),
+            bosses: Vec::new(),
+            npcs: Vec::new(),
+            chests: Vec::new(),
+            signs: Vec::new(),
+            save_points: Vec::new(),
+            tiles: Some(self.build_grid()?),
+            background: Vec::new(),
+            spawn_zones: Vec::new(),
+            triggers: Vec::new(),
+            weather: Vec::new(),
+        };
+
+        let mut spawn = None;
+        for layer in &self.layers {
+            let Layer::Objects { objects } = layer else {
+                continue;
+            };
+            for obj in objects {
+                let pos = obj.center();
+                match obj.kind.as_str() {
+                    "spawn" => spawn = Some(pos),
+                    "portal" => config.portals.push(obj.portal(pos)?),
+                    "boss" => config.bosses.push(BossSpawn::Placed {
+                        name: obj.text_or_name("boss"),
+                        pos,
+                    }),
+                    "npc" => config.npcs.push(NpcConfig {
+                        id: obj.text_or_name("id"),
+                        name: obj.name.clone(),
+                        pos,
+                        sprite: obj.required("sprite")?,
+                        scale: obj.number("scale")?.unwrap_or_else(default_npc_scale),
+                        dialogue: obj.required("dialogue")?,
+                        schedule: obj.json("schedule")?.unwrap_or_default(),
+                    }),
+                    "chest" => config.chests.push(ChestConfig {
+                        id: obj.text_or_name("id"),
+                        pos,
+                        requires: obj.json("requires")?.unwrap_or_default(),
+                        contents: obj.json("contents")?.unwrap_or_default(),
+                        loot: obj.properties.get("loot").cloned(),
+                    }),
+                    "sign" => config.signs.push(SignConfig {
+                        pos,
+                        title: (!obj.name.is_empty()).then(|| obj.name.clone()),
+                        text: obj.required("text")?,
+                    }),
+                    "save_point" => config.save_points.push(SavePointConfig { pos }),
+                    // 其他对象（注释、参考线等）不参与导入
+                    _ => {}
+                }
+            }
+        }
+        config.spawn = spawn.ok_or("缺少类型为 spawn 的出生点对象")?;
+        Ok(config)
+    }
+
+    // 合并图块层：可见层的最上层图块决定外观，碰撞层或 solid 图块决定阻挡
+    fn build_grid(&self) -> Result<TileGrid, String> {
+        let cells = self.width * self.height;
+        let mut visual = vec![0u32; cells];
+        let mut solid = vec![false; cells];
+        for layer in &self.layers {
+            let Layer::Tiles {
+                name,
+                visible,
+                properties,
+                data,
+            } = layer
+            else {
+                continue;
+            };
+            if data.len() != cells {
+                return Err(format!(
+                    "图块层 {name} 有 {} 格，与地图尺寸 {}x{} 不符",
+                    data.len(),
+                    self.width,
+                    self.height
+                ));
+            }
+            let collision =
+                name.eq_ignore_ascii_case("collision") || is_true(properties.get("collision"));
+            for (i, raw) in data.iter().enumerate() {
+                let gid = raw & GID_MASK;
+                if gid == 0 {
+                    continue;
+                }
+                if collision || is_true(self.tile_property(gid, "solid")) {
+                    solid[i] = true;
+                }
+                if *visible {
+                    visual[i] = gid;
+                }
+            }
+        }
+
+        // 每种（图块, 是否阻挡）组合分配一个字符
+        let mut pool = TILE_CHARS.chars();
+        let mut assigned: HashMap<(u32, bool), char> = HashMap::new();
+        let mut solid_chars = String::from("#");
+        let mut colors = BTreeMap::new();
+        let mut rows = Vec::with_capacity(self.height);
+        for y in 0..self.height {
+            let mut row = String::with_capacity(self.width);
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                let key = (visual[i], solid[i]);
+                let ch = match key {
+                    (0, false) => '.',
+                    (0, true) => '#',
+                    _ => match assigned.get(&key) {
+                        Some(&ch) => ch,
+                        None => {
+                            let ch = pool.next().ok_or("图块种类过多，超出可分配的字符数")?;
+                            if key.1 {
+                                solid_chars.push(ch);
+                            }
+                            colors.insert(ch, ColorDef(self.tile_color(key.0)));
+                            assigned.insert(key, ch);
+                            ch
+                        }
+                    },
+                };
+                row.push(ch);
+            }
+            rows.push(row);
+        }
+
+        Ok(TileGrid {
+            tile_size: self.tile_size,
+            width: self.width,
+            height: self.height,
+            rows,
+            solid: solid_chars,
+            colors,
+        })
+    }
+
+    // 查找图块的自定义属性
+    fn tile_property(&self, gid: u32, key: &str) -> Option<&String> {
+        let tileset = self
+            .tilesets
+            .iter()
+            .filter(|t| t.first_gid <= gid)
+            .max_by_key(|t| t.first_gid)?;
+        tileset.tiles.get(&(gid - tileset.first_gid))?.get(key)
+    }
+
+    // 图块颜色：优先使用 color 属性，否则按 gid 生成稳定的暗色调
+    fn tile_color(&self, gid: u32) -> Color {
+        if let Some(color) = self
+            .tile_property(gid, "color")
+            .and_then(|c| tiled_color(c))
+        {
+            return color;
+        }
+        let h = gid.wrapping_mul(2_654_435_761);
+        let channel = |shift: u32| 0.2 + ((h >> shift) & 0xff) as f32 / 255.0 * 0.3;
+        Color::new(channel(0), channel(8), channel(16), 1.0)
+    }
+}
+
+// 解析颜色：Tiled 的颜色属性为 #AARRGGBB，手写时也可用 #RRGGBB
+fn tiled_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() == 8 {
+        parse_hex_color(&format!("#{}{}", &hex[2..], &hex[..2]))
+    } else {
+        parse_hex_color(text)
+    }
+}
+
+impl Object {
+    // 对象中心点（矩形/椭圆取中心，点对象即自身坐标）
+    fn center(&self) -> Vec2Def {
+        Vec2Def::new(self.x + self.width * 0.5, self.y + self.height * 0.5)
+    }
+
+    // 带对象信息的错误描述
+    fn error(&self, message: &str) -> String {
+        format!("对象 {}（{}）{message}", self.name, self.kind)
+    }
+
+    // 必填的文本属性
+    fn required(&self, key: &str) -> Result<String, String> {
+        self.properties
+            .get(key)
+            .cloned()
+            .ok_or_else(|| self.error(&format!("缺少属性 {key}")))
+    }
+
+    // 文本属性，缺失时使用对象名称
+    fn text_or_name(&self, key: &str) -> String {
+        self.properties
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    // 可选的数值属性
+    fn number(&self, key: &str) -> Result<Option<f32>, String> {
+        self.properties
+            .get(key)
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| self.error(&format!("的属性 {key} 不是数值：{v}")))
+            })
+            .transpose()
+    }
+
+    // 可选的 JSON 属性（用于条件与效果列表，格式同 maps.json）
+    fn json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.properties
+            .get(key)
+            .map(|v| {
+                serde_json::from_str(v)
+                    .map_err(|e| self.error(&format!("的属性 {key} 解析失败：{e}")))
+            })
+            .transpose()
+    }
+
+    // 传送点：to_map 与 to_pos（"x,y"）必填，半径缺省取对象尺寸
+    fn portal(&self, pos: Vec2Def) -> Result<PortalConfig, String> {
+        let radius = match self.number("radius")? {
+            Some(radius) => radius,
+            None if self.width > 0.0 => self.width.max(self.height) * 0.5,
+            None => DEFAULT_PORTAL_RADIUS,
+        };
+        let to_pos = self.required("to_pos")?;
+        let to_pos = to_pos
+            .split_once(',')
+            .and_then(|(x, y)| Some(Vec2Def::new(x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            .ok_or_else(|| self.error(&format!("的属性 to_pos 应为 \"x,y\"：{to_pos}")))?;
+        let transition = match self.properties.get("transition") {
+            Some(name) => TransitionKind::from_name(name)
+                .ok_or_else(|| self.error(&format!("的属性 transition 未知：{name}")))?,
+            None => TransitionKind::default(),
+        };
+        let activation = match self.properties.get("activation") {
+            Some(name) => PortalActivation::from_name(name)
+                .ok_or_else(|| self.error(&format!("的属性 activation 未知：{name}")))?,
+            None => PortalActivation::default(),
+        };
+        Ok(PortalConfig {
+            id: self
+                .properties
+                .get("id")
+                .cloned()
+                .or_else(|| (!self.name.is_empty()).then(|| self.name.clone())),
+            pos,
+            radius,
+            to_map: self.required("to_map")?,
+            to_pos,
+            is_unlocked: !is_true(self.properties.get("locked")),
+            requires: self.json("requires")?.unwrap_or_default(),
+            transition,
+            activation,
+            paired: is_true(self.properties.get("paired")),
+            cost: self.json("cost")?,
+            schedule: self.json("schedule")?.unwrap_or_default(),
+            confirm: is_true(self.properties.get("confirm")),
+            generated: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按 Tiled 的方式把 gid 编码为小端 Base64
+    fn encode_base64(gids: &[u32]) -> String {
+        const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let bytes: Vec<u8> = gids.iter().flat_map(|g| g.to_le_bytes()).collect();
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(TABLE[(n >> (18 - i * 6) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    // 内存中的对象
+    fn object(kind: &str, name: &str, x: f32, y: f32, properties: &[(&str, &str)]) -> Object {
+        Object {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            x,
+            y,
+            width: 0.0,
+            height: 0.0,
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    // 内存中的 2x2 地图：一个图块层与一个对象层
+    fn tiled_map(data: Vec<u32>, objects: Vec<Object>) -> TiledMap {
+        TiledMap {
+            width: 2,
+            height: 2,
+            tile_size: 16.0,
+            properties: Properties::new(),
+            tilesets: vec![Tileset {
+                first_gid: 1,
+                tiles: HashMap::from([(
+                    1,
+                    Properties::from([("solid".to_string(), "true".to_string())]),
+                )]),
+                source: None,
+            }],
+            layers: vec![
+                Layer::Tiles {
+                    name: "ground".to_string(),
+                    visible: true,
+                    properties: Properties::new(),
+                    data,
+                },
+                Layer::Objects { objects },
+            ],
+        }
+    }
+
+    #[test]
+    fn decodes_csv_gids() {
+        let gids = decode_gids("csv", None, "\n1, 2,\n3,0\n").unwrap();
+        assert_eq!(gids, vec![1, 2, 3, 0]);
+        assert!(decode_gids("csv", None, "1,x").is_err());
+    }
+
+    #[test]
+    fn decodes_base64_gids() {
+        let gids = vec![1, 0, 0x8000_0002, 70_000];
+        let text = format!("\n   {}\n  ", encode_base64(&gids));
+        assert_eq!(decode_gids("base64", None, &text).unwrap(), gids);
+        assert_eq!(
+            decode_gids("base64", Some(""), "AQAAAA==").unwrap(),
+            vec![1]
+        );
+        // 长度不是 4 字节的倍数
+        assert!(decode_gids("base64", None, "AQA=").is_err());
+        assert!(decode_gids("base64", None, "AQ*A").is_err());
+        assert!(decode_gids("base64", Some("zlib"), "AQAAAA==").is_err());
+        assert!(decode_gids("xml", None, "").is_err());
+    }
+
+    #[test]
+    fn flip_flags_do_not_change_the_tile() {
+        // 水平、垂直、对角翻转与六边形旋转标记都应被去掉
+        let data = vec![1, 0x8000_0001, 0x4000_0001 | 0x2000_0000, 0x1000_0002];
+        let config = tiled_map(data, vec![object("spawn", "", 8.0, 8.0, &[])])
+            .into_config("flip")
+            .unwrap();
+        let grid = config.tiles.unwrap();
+        assert_eq!(grid.rows, vec!["AA", "AB"]);
+        assert_eq!(grid.solid, "#B");
+    }
+
+    #[test]
+    fn converts_objects_into_config() {
+        let objects = vec![
+            object("spawn", "", 8.0, 24.0, &[]),
+            object(
+                "portal",
+                "north_gate",
+                16.0,
+                0.0,
+                &[
+                    ("to_map", "qingtian"),
+                    ("to_pos", "120, 405"),
+                    ("paired", "true"),
+                ],
+            ),
+            object("chest", "box", 4.0, 4.0, &[("loot", "chest_qingtian")]),
+            object("boss", "青鳞王", 20.0, 20.0, &[]),
+            object("note", "参考线", 0.0, 0.0, &[]),
+        ];
+        let config = tiled_map(vec![0; 4], objects)
+            .into_config("fallback")
+            .unwrap();
+        assert_eq!(config.id, "fallback");
+        assert_eq!(config.name, "fallback");
+        assert_eq!((config.spawn.x, config.spawn.y), (8.0, 24.0));
+        let portal = &config.portals[0];
+        assert_eq!(portal.id.as_deref(), Some("north_gate"));
+        assert_eq!(portal.to_map, "qingtian");
+        assert_eq!((portal.to_pos.x, portal.to_pos.y), (120.0, 405.0));
+        assert_eq!(portal.radius, DEFAULT_PORTAL_RADIUS);
+        assert!(portal.paired && portal.is_unlocked);
+        assert_eq!(config.chests[0].loot.as_deref(), Some("chest_qingtian"));
+        assert_eq!(config.bosses.len(), 1);
+        assert_eq!(config.tiles.unwrap().rows, vec!["..", ".."]);
+    }
+
+    #[test]
+    fn reports_bad_objects_and_layers() {
+        let missing_spawn = tiled_map(vec![0; 4], Vec::new()).into_config("a");
+        assert!(missing_spawn.is_err());
+        let bad_pos = vec![
+            object("spawn", "", 0.0, 0.0, &[]),
+            object("portal", "p", 0.0, 0.0, &[("to_map", "a"), ("to_pos", "1")]),
+        ];
+        assert!(tiled_map(vec![0; 4], bad_pos).into_config("a").is_err());
+        let spawn = vec![object("spawn", "", 0.0, 0.0, &[])];
+        assert!(tiled_map(vec![0; 3], spawn).into_config("a").is_err());
+    }
+
+    #[test]
+    fn imports_sample_tmx() {
+        let (config, files) = import_tiled(Path::new("data/tiled/sample.tmx")).unwrap();
+        assert_eq!(config.id, "tiled_sample_tmx");
+        let grid = config.tiles.as_ref().unwrap();
+        assert_eq!(grid.rows, vec!["AAAA", "ABAA", "AAA#"]);
+        assert_eq!(grid.solid, "#B");
+        let [r, g, b, _]: [u8; 4] = grid.colors[&'A'].0.into();
+        assert_eq!((r, g, b), (0x3a, 0x5f, 0x3a));
+        assert_eq!((config.spawn.x, config.spawn.y), (48.0, 48.0));
+        assert_eq!(config.portals[0].id.as_deref(), Some("sample_gate"));
+        assert_eq!(config.portals[0].radius, 16.0);
+        assert_eq!(config.signs[0].text, "示例地图 & 图块集\n<第二行>");
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("data/tiled/sample.tmx"),
+                PathBuf::from("data/tiled/sample.tsx"),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_sample_tmj() {
+        let (config, files) = import_tiled(Path::new("data/tiled/sample.tmj")).unwrap();
+        assert_eq!(config.id, "sample");
+        assert_eq!(config.name, "Tiled 示例（TMJ）");
+        assert_eq!(config.tiles.unwrap().rows, vec!["AAB", "AAA"]);
+        assert_eq!((config.spawn.x, config.spawn.y), (16.0, 16.0));
+        assert_eq!(config.chests[0].id, "sample_chest");
+        assert_eq!(config.chests[0].loot.as_deref(), Some("chest_qingtian"));
+        assert_eq!(files, vec![PathBuf::from("data/tiled/sample.tmj")]);
+    }
+}
@@ -0,0 +1,228 @@
+﻿use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{decode_gids, load_tileset, Layer, Object, Properties, TiledMap, Tileset};
+
+// Tiled JSON 地图（只声明导入用到的字段）
+#[derive(Deserialize)]
+struct TmjMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+    #[serde(default)]
+    tilesets: Vec<TmjTilesetRef>,
+    #[serde(default)]
+    layers: Vec<TmjLayer>,
+}
+
+// 自定义属性
+#[derive(Deserialize)]
+struct TmjProperty {
+    name: String,
+    #[serde(default)]
+    value: Value,
+}
+
+// 地图内的图块集引用：内嵌或指向外部 .tsj 文件
+#[derive(Deserialize)]
+struct TmjTilesetRef {
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TmjTile>,
+}
+
+// 外部图块集文件
+#[derive(Deserialize)]
+struct TmjTileset {
+    #[serde(default)]
+    tiles: Vec<TmjTile>,
+}
+
+// 带自定义属性的图块
+#[derive(Deserialize)]
+struct TmjTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+}
+
+// 图层（图块层、对象层与组图层共用）
+#[derive(Deserialize)]
+struct TmjLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    data: Option<TmjData>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<TmjObject>,
+    #[serde(default)]
+    layers: Vec<TmjLayer>,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+}
+
+// 未声明时图层可见
+fn default_visible() -> bool {
+    true
+}
+
+// 图块数据：默认为 gid 数组，Base64 编码时为字符串
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TmjData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+// 对象（Tiled 1.9 将 type 改名为 class，两者都兼容）
+#[derive(Deserialize)]
+struct TmjObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+}
+
+// 解析 Tiled JSON 地图
+pub(super) fn parse_map(text: &str, dir: &Path) -> Result<TiledMap, String> {
+    let map: TmjMap = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if map.infinite {
+        return Err("不支持无限地图，请在 Tiled 中关闭“无限”选项".to_string());
+    }
+    if map.tilewidth != map.tileheight {
+        return Err("仅支持正方形图块".to_string());
+    }
+    let mut tilesets = Vec::new();
+    for tileset in map.tilesets {
+        tilesets.push(match &tileset.source {
+            Some(source) => load_tileset(dir, source, tileset.firstgid)?,
+            None => Tileset {
+                first_gid: tileset.firstgid,
+                tiles: tile_properties(tileset.tiles),
+                source: None,
+            },
+        });
+    }
+    let mut layers = Vec::new();
+    flatten_layers(map.layers, true, &mut layers)?;
+    Ok(TiledMap {
+        width: map.width,
+        height: map.height,
+        tile_size: map.tilewidth,
+        properties: properties(map.properties),
+        tilesets,
+        layers,
+    })
+}
+
+// 解析外部 JSON 图块集
+pub(super) fn parse_tileset(text: &str, first_gid: u32) -> Result<Tileset, String> {
+    let tileset: TmjTileset = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(Tileset {
+        first_gid,
+        tiles: tile_properties(tileset.tiles),
+        source: None,
+    })
+}
+
+// 展开组图层，组不可见时其子图层一并视为不可见
+fn flatten_layers(
+    layers: Vec<TmjLayer>,
+    visible: bool,
+    out: &mut Vec<Layer>,
+) -> Result<(), String> {
+    for layer in layers {
+        let visible = visible && layer.visible;
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                let data = match layer.data {
+                    Some(TmjData::Gids(gids)) => gids,
+                    Some(TmjData::Encoded(text)) => decode_gids(
+                        layer.encoding.as_deref().unwrap_or("base64"),
+                        layer.compression.as_deref(),
+                        &text,
+                    )?,
+                    None => Vec::new(),
+                };
+                out.push(Layer::Tiles {
+                    name: layer.name,
+                    visible,
+                    properties: properties(layer.properties),
+                    data,
+                });
+            }
+            "objectgroup" => out.push(Layer::Objects {
+                objects: layer
+                    .objects
+                    .into_iter()
+                    .map(|obj| Object {
+                        name: obj.name,
+                        kind: if obj.kind.is_empty() {
+                            obj.class
+                        } else {
+                            obj.kind
+                        },
+                        x: obj.x,
+                        y: obj.y,
+                        width: obj.width,
+                        height: obj.height,
+                        properties: properties(obj.properties),
+                    })
+                    .collect(),
+            }),
+            "group" => flatten_layers(layer.layers, visible, out)?,
+            // 图像层等不参与导入
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// 属性列表转为字符串表
+fn properties(list: Vec<TmjProperty>) -> Properties {
+    list.into_iter()
+        .map(|p| {
+            let value = match p.value {
+                Value::String(text) => text,
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            (p.name, value)
+        })
+        .collect()
+}
+
+// 图块属性按本地 id 建表
+fn tile_properties(tiles: Vec<TmjTile>) -> std::collections::HashMap<u32, Properties> {
+    tiles
+        .into_iter()
+        .map(|tile| (tile.id, properties(tile.properties)))
+        .collect()
+}
@@ -0,0 +1,152 @@
+﻿use std::collections::HashMap;
+use std::path::Path;
+
+use super::xml::{self, Element};
+use super::{decode_gids, load_tileset, Layer, Object, Properties, TiledMap, Tileset};
+
+// 解析 Tiled XML 地图
+pub(super) fn parse_map(text: &str, dir: &Path) -> Result<TiledMap, String> {
+    let root = xml::parse(text)?;
+    if root.name != "map" {
+        return Err(format!("根元素应为 map，实际为 {}", root.name));
+    }
+    if root.attr("infinite") == Some("1") {
+        return Err("不支持无限地图，请在 Tiled 中关闭“无限”选项".to_string());
+    }
+    let tile_width: f32 = number(&root, "tilewidth")?;
+    let tile_height: f32 = number(&root, "tileheight")?;
+    if tile_width != tile_height {
+        return Err("仅支持正方形图块".to_string());
+    }
+    let mut tilesets = Vec::new();
+    for tileset in root.children_named("tileset") {
+        let first_gid = number(tileset, "firstgid")?;
+        tilesets.push(match tileset.attr("source") {
+            Some(source) => load_tileset(dir, source, first_gid)?,
+            None => read_tileset(tileset, first_gid)?,
+        });
+    }
+    let mut layers = Vec::new();
+    read_layers(&root, true, &mut layers)?;
+    Ok(TiledMap {
+        width: number(&root, "width")?,
+        height: number(&root, "height")?,
+        tile_size: tile_width,
+        properties: properties(&root),
+        tilesets,
+        layers,
+    })
+}
+
+// 解析外部 XML 图块集
+pub(super) fn parse_tileset(text: &str, first_gid: u32) -> Result<Tileset, String> {
+    let root = xml::parse(text)?;
+    if root.name != "tileset" {
+        return Err(format!("根元素应为 tileset，实际为 {}", root.name));
+    }
+    read_tileset(&root, first_gid)
+}
+
+// 读取图块集中各图块的属性
+fn read_tileset(element: &Element, first_gid: u32) -> Result<Tileset, String> {
+    let mut tiles = HashMap::new();
+    for tile in element.children_named("tile") {
+        tiles.insert(number(tile, "id")?, properties(tile));
+    }
+    Ok(Tileset {
+        first_gid,
+        tiles,
+        source: None,
+    })
+}
+
+// 按文档顺序读取图层，展开组图层
+fn read_layers(parent: &Element, visible: bool, out: &mut Vec<Layer>) -> Result<(), String> {
+    for child in &parent.children {
+        let visible = visible && child.attr("visible") != Some("0");
+        match child.name.as_str() {
+            "layer" => {
+                let name = child.attr("name").unwrap_or_default().to_string();
+                let data = match child.child("data") {
+                    Some(data) => match data.attr("encoding") {
+                        Some(encoding) => {
+                            decode_gids(encoding, data.attr("compression"), &data.text)?
+                        }
+                        // 未编码时每格为一个 <tile gid=".."/>
+                        None => data
+                            .children_named("tile")
+                            .map(|tile| match tile.attr("gid") {
+                                Some(_) => number(tile, "gid"),
+                                None => Ok(0),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    },
+                    None => Vec::new(),
+                };
+                out.push(Layer::Tiles {
+                    name,
+                    visible,
+                    properties: properties(child),
+                    data,
+                });
+            }
+            "objectgroup" => {
+                let mut objects = Vec::new();
+                for obj in child.children_named("object") {
+                    objects.push(Object {
+                        name: obj.attr("name").unwrap_or_default().to_string(),
+                        kind: obj
+                            .attr("type")
+                            .or_else(|| obj.attr("class"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        x: number(obj, "x")?,
+                        y: number(obj, "y")?,
+                        width: optional_number(obj, "width")?,
+                        height: optional_number(obj, "height")?,
+                        properties: properties(obj),
+                    });
+                }
+                out.push(Layer::Objects { objects });
+            }
+            "group" => read_layers(child, visible, out)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// 读取 <properties> 子元素；多行文本属性写在元素内容里
+fn properties(element: &Element) -> Properties {
+    let Some(list) = element.child("properties") else {
+        return Properties::new();
+    };
+    list.children_named("property")
+        .filter_map(|p| {
+            let name = p.attr("name")?.to_string();
+            let value = p
+                .attr("value")
+                .map_or_else(|| p.text.clone(), str::to_string);
+            Some((name, value))
+        })
+        .collect()
+}
+
+// 必填的数值属性
+fn number<T: std::str::FromStr>(element: &Element, key: &str) -> Result<T, String> {
+    let value = element
+        .attr(key)
+        .ok_or_else(|| format!("元素 {} 缺少属性 {key}", element.name))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("元素 {} 的属性 {key} 不是有效数值：{value}", element.name))
+}
+
+// 可选的数值属性，缺失为 0
+fn optional_number(element: &Element, key: &str) -> Result<f32, String> {
+    match element.attr(key) {
+        Some(_) => number(element, key),
+        None => Ok(0.0),
+    }
+}
@@ -0,0 +1,280 @@
+﻿use std::collections::HashMap;
+
+// 精简的 XML 元素树，仅满足 TMX/TSX 读取所需（不支持 DTD 与命名空间）
+pub(super) struct Element {
+    pub name: String,
+    pub attrs: HashMap<String, String>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    // 读取属性
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.get(key).map(String::as_str)
+    }
+
+    // 第一个指定名称的子元素
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    // 全部指定名称的子元素
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+// 解析 XML 文档，返回根元素
+pub(super) fn parse(text: &str) -> Result<Element, String> {
+    let mut parser = Parser { src: text, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos < parser.src.len() {
+        return Err(parser.error("根元素之后存在多余内容"));
+    }
+    Ok(root)
+}
+
+// 递归下降解析器，pos 为当前字节偏移
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    // 剩余未解析的文本
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    // 带位置的错误描述
+    fn error(&self, message: &str) -> String {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        format!("XML 第 {line} 行：{message}")
+    }
+
+    // 跳过空白
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // 跳到指定结束标记之后
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(idx) => {
+                self.pos += idx + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("缺少 {end}"))),
+        }
+    }
+
+    // 跳过空白、声明、注释与 DOCTYPE
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_ws();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // 读取元素名或属性名
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("缺少名称"));
+        }
+        let name = rest[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    // 期望下一个字符串
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("此处应为 {token}")))
+        }
+    }
+
+    // 解析一个元素（含属性、子元素与文本）
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut element = Element {
+            name,
+            attrs: HashMap::new(),
+            children: Vec::new(),
+            text: String::new(),
+        };
+
+        // 属性
+        loop {
+            self.skip_ws();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.name()?;
+            self.skip_ws();
+            self.expect("=")?;
+            self.skip_ws();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("属性值缺少引号")),
+            };
+            self.pos += 1;
+            let Some(len) = self.rest().find(quote) else {
+                return Err(self.error("属性值缺少结束引号"));
+            };
+            let value = unescape(&self.rest()[..len]);
+            self.pos += len + 1;
+            element.attrs.insert(key, value);
+        }
+
+        // 内容
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("元素 {} 未闭合", element.name)));
+            }
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let close = self.name()?;
+                if close != element.name {
+                    return Err(self.error(&format!(
+                        "结束标签 {close} 与开始标签 {} 不匹配",
+                        element.name
+                    )));
+                }
+                self.skip_ws();
+                self.expect(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(body) = rest.strip_prefix("<![CDATA[") {
+                let Some(len) = body.find("]]>") else {
+                    return Err(self.error("CDATA 未闭合"));
+                };
+                element.text.push_str(&body[..len]);
+                self.pos += "<![CDATA[".len() + len + 3;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                element.children.push(self.element()?);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&unescape(&rest[..len]));
+                self.pos += len;
+            }
+        }
+    }
+}
+
+// 还原实体引用
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_entities_in_attributes_and_text() {
+        let root =
+            parse(r#"<p v="&lt;&amp;&#65;&#x42;&quot;&apos;&bogus;">a &gt; b &amp c</p>"#).unwrap();
+        assert_eq!(root.attr("v"), Some("<&AB\"'&bogus;"));
+        assert_eq!(root.text, "a > b &amp c");
+    }
+
+    #[test]
+    fn parses_self_closing_tags() {
+        let root =
+            parse("<?xml version=\"1.0\"?>\n<a>\n <b/>\n <c x='1' y = \"2\" />\n</a>\n").unwrap();
+        assert_eq!(root.children.len(), 2);
+        assert!(root.child("b").unwrap().children.is_empty());
+        let c = root.child("c").unwrap();
+        assert_eq!((c.attr("x"), c.attr("y")), (Some("1"), Some("2")));
+    }
+
+    #[test]
+    fn parses_nested_groups() {
+        let text = "<map><!-- 注释 --><group name=\"g1\"><group name=\"g2\">\
+                    <layer name=\"a\"/><layer name=\"b\"><data><![CDATA[1,2]]></data></layer>\
+                    </group></group><layer name=\"c\"/></map>";
+        let root = parse(text).unwrap();
+        let inner = root.child("group").unwrap().child("group").unwrap();
+        let names: Vec<_> = inner
+            .children_named("layer")
+            .filter_map(|l| l.attr("name"))
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(inner.children[1].child("data").unwrap().text, "1,2");
+        assert_eq!(root.children_named("layer").count(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("<a/><b/>").is_err());
+        assert!(parse("<a x=1/>").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+﻿use std::collections::{HashMap, HashSet, VecDeque};
+
+use macroquad::prelude::*;
+
+use crate::world::progress::Progress;
+use crate::world::World;
+
+// 世界地图上的节点：地图 id 与布局位置（列为距起始地图的传送步数）
+#[derive(Clone, Debug)]
+pub struct MapNode {
+    pub id: String,
+    pub col: usize,
+    pub row: usize,
+}
+
+// 快速旅行路线：依次经过的地图（含起点与终点）与抵达终点时的落点
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub maps: Vec<String>,
+    pub arrival: Vec2,
+}
+
+impl World {
+    // 世界地图布局：从起始地图沿传送点广度优先分层，无法到达的地图排在最后一列
+    pub fn overworld_layout(&self) -> Vec<MapNode> {
+        let mut nodes = Vec::new();
+        let mut rows: Vec<usize> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        if self.maps.contains_key(&self.start) {
+            seen.insert(self.start.as_str());
+            queue.push_back((self.start.as_str(), 0));
+        }
+        while let Some((id, col)) = queue.pop_front() {
+            if rows.len() <= col {
+                rows.resize(col + 1, 0);
+            }
+            nodes.push(MapNode {
+                id: id.to_string(),
+                col,
+                row: rows[col],
+            });
+            rows[col] += 1;
+            for portal in &self.maps[id].portals {
+                let to_map = portal.to_map.as_str();
+                if self.maps.contains_key(to_map) && seen.insert(to_map) {
+                    queue.push_back((to_map, col + 1));
+                }
+            }
+        }
+        let mut rest: Vec<&str> = self
+            .maps
+            .keys()
+            .map(String::as_str)
+            .filter(|id| !seen.contains(id))
+            .collect();
+        rest.sort();
+        let col = rows.len();
+        for (row, id) in rest.into_iter().enumerate() {
+            nodes.push(MapNode {
+                id: id.to_string(),
+                col,
+                row,
+            });
+        }
+        nodes
+    }
+
+    // 从当前地图前往已到访地图的最短路线：只经过到访过的地图与当前可通行的传送点
+    pub fn find_route(&self, to: &str, progress: &Progress) -> Result<Route, String> {
+        let from = self.current.as_str();
+        if to == from {
+            return Err("你已身在此处".to_string());
+        }
+        if !self.maps.contains_key(to) || !progress.visited_maps.contains(to) {
+            return Err("尚未到访此地".to_string());
+        }
+
+        // 广度优先搜索，记录每张地图的上一站与抵达落点
+        let mut prev: HashMap<&str, (&str, Vec2)> = HashMap::new();
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                break;
+            }
+            // 快速旅行不经过需要阵资的传送点，也不经过当前时段未开放的传送点
+            let map = &self.maps[id];
+            for (idx, portal) in map.portals.iter().enumerate() {
+                let next = portal.to_map.as_str();
+                if !self.maps.contains_key(next)
+                    || !progress.visited_maps.contains(next)
+                    || seen.contains(next)
+                    || portal.cost.is_some()
+                    || portal.usable(&portal.key(&map.id, idx), progress).is_err()
+                {
+                    continue;
+                }
+                seen.insert(next);
+                prev.insert(next, (id, portal.to_pos.to_vec2()));
+                queue.push_back(next);
+            }
+        }
+
+        let &(_, arrival) = prev
+            .get(to)
+            .ok_or_else(|| "没有可通行的传送路线，沿途传送阵尚未开启或已被封锁".to_string())?;
+        let mut maps = vec![to.to_string()];
+        let mut at = to;
+        while let Some(&(step, _)) = prev.get(at) {
+            maps.push(step.to_string());
+            at = step;
+        }
+        maps.reverse();
+        Ok(Route { maps, arrival })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::world::map::MapsConfig;
+
+    // 传送点：前往 to_map，extra 中的字段覆盖默认配置
+    fn portal(to_map: &str, extra: Value) -> Value {
+        let mut portal = json!({
+            "pos": { "x": 400.0, "y": 300.0 },
+            "radius": 20.0,
+            "to_map": to_map,
+            "to_pos": { "x": 100.0, "y": 100.0 }
+        });
+        portal
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        portal
+    }
+
+    // 世界：a→b→c 与 a→c 直达，a→d 由 extra 配置，e 孤立；全部地图都已到访
+    fn setup(direct: Value, to_d: Value) -> (World, Progress) {
+        let map = |id: &str, portals: Vec<Value>| {
+            json!({
+                "id": id,
+                "name": id,
+                "spawn": { "x": 100.0, "y": 100.0 },
+                "portals": portals,
+                "bosses": []
+            })
+        };
+        let config: MapsConfig = serde_json::from_value(json!({
+            "start_map": "a",
+            "maps": [
+                map("a", vec![portal("b", json!({})), portal("c", direct), portal("d", to_d)]),
+                map("b", vec![portal("c", json!({}))]),
+                map("c", vec![]),
+                map("d", vec![]),
+                map("e", vec![])
+            ]
+        }))
+        .unwrap();
+        let progress = Progress {
+            visited_maps: ["a", "b", "c", "d", "e"].map(String::from).into(),
+            ..Progress::default()
+        };
+        (World::from_config(config), progress)
+    }
+
+    #[test]
+    fn takes_the_shortest_route() {
+        let (world, progress) = setup(json!({}), json!({}));
+        let route = world.find_route("c", &progress).unwrap();
+        assert_eq!(route.maps, ["a", "c"]);
+        assert_eq!(route.arrival, vec2(100.0, 100.0));
+    }
+
+    #[test]
+    fn locked_portal_blocks_the_only_route() {
+        let (world, progress) = setup(json!({}), json!({ "is_unlocked": false }));
+        assert!(world.find_route("d", &progress).is_err());
+
+        let flag = json!({ "requires": [{ "type": "flag", "flag": "opened" }] });
+        let (world, mut progress) = setup(json!({}), flag);
+        assert!(world.find_route("d", &progress).is_err());
+        progress.flags.insert("opened".to_string());
+        assert_eq!(world.find_route("d", &progress).unwrap().maps, ["a", "d"]);
+    }
+
+    #[test]
+    fn skips_costed_and_locked_shortcuts() {
+        let cost = json!({ "cost": { "count": 1 } });
+        let (world, progress) = setup(cost, json!({}));
+        assert_eq!(
+            world.find_route("c", &progress).unwrap().maps,
+            ["a", "b", "c"]
+        );
+
+        let (mut world, mut progress) = setup(json!({}), json!({}));
+        progress.locked_portals.insert("a#1".to_string());
+        assert_eq!(
+            world.find_route("c", &progress).unwrap().maps,
+            ["a", "b", "c"]
+        );
+        world.switch_map("b".to_string());
+        assert_eq!(world.find_route("c", &progress).unwrap().maps, ["b", "c"]);
+    }
+
+    #[test]
+    fn unreachable_or_unvisited_destinations_have_no_route() {
+        let (world, mut progress) = setup(json!({}), json!({}));
+        assert!(world.find_route("e", &progress).is_err());
+        assert!(world.find_route("a", &progress).is_err());
+        progress.visited_maps.remove("b");
+        assert!(world.find_route("b", &progress).is_err());
+        assert_eq!(world.find_route("c", &progress).unwrap().maps, ["a", "c"]);
+    }
+}
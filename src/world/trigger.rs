@@ -0,0 +1,110 @@
+﻿use serde::{Deserialize, Serialize};
+
+use crate::world::area::Area;
+use crate::world::condition::Condition;
+use crate::world::map::{is_default, TransitionKind, Vec2Def};
+
+// 触发区域：玩家进入、离开或停留时按顺序执行动作
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TriggerConfig {
+    pub id: String,
+    #[serde(flatten)]
+    pub area: Area,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub on: TriggerEvent,
+    // 停留触发的间隔（秒）
+    #[serde(
+        default = "default_interval",
+        skip_serializing_if = "is_default_interval"
+    )]
+    pub interval: f32,
+    // 为真时只触发一次（记入存档）
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub once: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<Condition>,
+    pub actions: Vec<TriggerAction>,
+}
+
+impl TriggerConfig {
+    // 一次性触发在进度中的键：“地图#触发区域 id”，不同地图的同名触发区域互不影响
+    pub fn key(&self, map_id: &str) -> String {
+        format!("{map_id}#{}", self.id)
+    }
+}
+
+// 默认停留间隔
+fn default_interval() -> f32 {
+    1.0
+}
+
+// 写回时省略默认停留间隔
+fn is_default_interval(interval: &f32) -> bool {
+    *interval == default_interval()
+}
+
+// 触发时机
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerEvent {
+    #[default]
+    Enter,
+    Leave,
+    Stay,
+}
+
+// 触发动作
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    // 屏幕提示
+    ShowText {
+        text: String,
+    },
+    SetFlag {
+        flag: String,
+    },
+    ClearFlag {
+        flag: String,
+    },
+    // 在指定位置（缺省为玩家附近）生成妖兽
+    SpawnMonsters {
+        monster: String,
+        #[serde(
+            default = "default_spawn_count",
+            skip_serializing_if = "is_default_spawn_count"
+        )]
+        count: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        at: Option<Vec2Def>,
+    },
+    Teleport {
+        to_map: String,
+        to_pos: Vec2Def,
+        #[serde(default, skip_serializing_if = "is_default")]
+        transition: TransitionKind,
+    },
+    // 播放过场对话（data/dialogues.json 中的对话树）
+    Cutscene {
+        dialogue: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        speaker: String,
+    },
+    // 封锁/解封传送点（按传送点键，见 PortalConfig::key）
+    LockPortal {
+        portal: String,
+    },
+    UnlockPortal {
+        portal: String,
+    },
+}
+
+// 默认生成数量
+fn default_spawn_count() -> u32 {
+    1
+}
+
+// 写回时省略默认生成数量
+fn is_default_spawn_count(count: &u32) -> bool {
+    *count == default_spawn_count()
+}
@@ -0,0 +1,725 @@
+﻿use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::io;
+
+use macroquad::prelude::*;
+
+use crate::world::map::{MapConfig, Vec2Def};
+use crate::world::trigger::TriggerAction;
+use crate::world::World;
+
+// 世界数据加载错误
+#[derive(Debug)]
+pub enum WorldError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Tiled(String),
+    // 地图目录或 mod 目录中的文件读取/解析失败
+    File { path: String, message: String },
+    Invalid(ValidationReport),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::Io(e) => write!(f, "读取地图文件失败：{e}"),
+            WorldError::Parse(e) => write!(f, "解析地图文件失败：{e}"),
+            WorldError::Tiled(e) => write!(f, "导入 Tiled 地图失败：{e}"),
+            WorldError::File { path, message } => write!(f, "加载 {path} 失败：{message}"),
+            WorldError::Invalid(report) => write!(f, "地图数据校验未通过：\n{report}"),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+impl From<io::Error> for WorldError {
+    fn from(e: io::Error) -> Self {
+        WorldError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for WorldError {
+    fn from(e: serde_json::Error) -> Self {
+        WorldError::Parse(e)
+    }
+}
+
+// 问题严重程度：错误会阻止加载，警告仅提示
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// 坐标所属的位置（用于定位问题）
+#[derive(Clone, Debug)]
+pub enum Location {
+    Spawn { map: String },
+    Portal { map: String, portal: usize },
+    PortalTarget { map: String, portal: usize },
+    TriggerTarget { map: String, trigger: String },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Spawn { map } => write!(f, "地图 {map} 的出生点"),
+            Location::Portal { map, portal } => write!(f, "地图 {map} 第 {portal} 个传送点"),
+            Location::PortalTarget { map, portal } => {
+                write!(f, "地图 {map} 第 {portal} 个传送点的目标坐标")
+            }
+            Location::TriggerTarget { map, trigger } => {
+                write!(f, "地图 {map} 触发区域 {trigger} 的传送坐标")
+            }
+        }
+    }
+}
+
+// 单条校验问题
+#[derive(Clone, Debug)]
+pub enum MapIssue {
+    MissingStartMap {
+        start_map: String,
+    },
+    DuplicateMapId {
+        id: String,
+        first: String,
+        second: String,
+    },
+    MapOverridden {
+        map: String,
+        source: String,
+        by: String,
+    },
+    ModConflict {
+        map: String,
+        first: String,
+        second: String,
+    },
+    PatchTargetMissing {
+        map: String,
+        portal: String,
+        source: String,
+    },
+    PortalPatchConflict {
+        map: String,
+        portal: String,
+        first: String,
+        second: String,
+    },
+    // 按“地图#序号”引用了没有 id 的传送点，增删传送点后引用会错位
+    UnnamedPortalRef {
+        portal: String,
+        source: String,
+    },
+    DanglingPortal {
+        map: String,
+        portal: usize,
+        to_map: String,
+    },
+    DanglingTrigger {
+        map: String,
+        trigger: String,
+        to_map: String,
+    },
+    OutOfBounds {
+        at: Location,
+        pos: Vec2,
+    },
+    BlockedByWall {
+        at: Location,
+        pos: Vec2,
+    },
+    PortalOverlapsSpawn {
+        at: Location,
+        map: String,
+        portal: usize,
+    },
+    UnreachableMap {
+        map: String,
+    },
+    // 同一地图中的宝箱 id 重复，开启其中一个会让其余的一并记为已开启
+    DuplicateChestId {
+        map: String,
+        id: String,
+    },
+}
+
+impl MapIssue {
+    // 问题严重程度
+    pub fn severity(&self) -> Severity {
+        match self {
+            MapIssue::MissingStartMap { .. }
+            | MapIssue::DuplicateMapId { .. }
+            | MapIssue::PatchTargetMissing { .. }
+            | MapIssue::DanglingPortal { .. }
+            | MapIssue::DanglingTrigger { .. } => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::MissingStartMap { start_map } => {
+                write!(f, "起始地图 {start_map} 不存在")
+            }
+            MapIssue::DuplicateMapId { id, first, second } => {
+                write!(f, "地图 id {id} 重复：{first} 与 {second}")
+            }
+            MapIssue::MapOverridden { map, source, by } => {
+                write!(f, "地图 {map}（{source}）被 {by} 覆盖")
+            }
+            MapIssue::ModConflict { map, first, second } => write!(
+                f,
+                "mod {first} 与 mod {second} 都覆盖了地图 {map}，以后加载的 {second} 为准"
+            ),
+            MapIssue::PatchTargetMissing {
+                map,
+                portal,
+                source,
+            } => write!(f, "{source} 的传送点补丁找不到目标：地图 {map} 的 {portal}"),
+            MapIssue::PortalPatchConflict {
+                map,
+                portal,
+                first,
+                second,
+            } => write!(
+                f,
+                "地图 {map} 的传送点 {portal} 同时被 {first} 与 {second} 修改，以后者为准"
+            ),
+            MapIssue::UnnamedPortalRef { portal, source } => write!(
+                f,
+                "{source} 按序号引用了没有 id 的传送点 {portal}，请为该传送点设置 id"
+            ),
+            MapIssue::DanglingPortal {
+                map,
+                portal,
+                to_map,
+            } => write!(
+                f,
+                "地图 {map} 第 {portal} 个传送点指向不存在的地图 {to_map}"
+            ),
+            MapIssue::DanglingTrigger {
+                map,
+                trigger,
+                to_map,
+            } => write!(
+                f,
+                "地图 {map} 触发区域 {trigger} 传送到不存在的地图 {to_map}"
+            ),
+            MapIssue::OutOfBounds { at, pos } => {
+                write!(f, "{at} ({:.0}, {:.0}) 超出地图范围", pos.x, pos.y)
+            }
+            MapIssue::BlockedByWall { at, pos } => {
+                write!(f, "{at} ({:.0}, {:.0}) 位于阻挡格内", pos.x, pos.y)
+            }
+            MapIssue::PortalOverlapsSpawn { at, map, portal } => {
+                write!(f, "{at} 落在地图 {map} 第 {portal} 个传送点范围内")
+            }
+            MapIssue::UnreachableMap { map } => {
+                write!(f, "地图 {map} 无法从起始地图经传送点到达")
+            }
+            MapIssue::DuplicateChestId { map, id } => {
+                write!(f, "地图 {map} 中有多个 id 为 {id} 的宝箱")
+            }
+        }
+    }
+}
+
+// 校验报告：一次性收集全部问题
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<MapIssue>,
+}
+
+impl ValidationReport {
+    // 是否存在阻止加载的错误
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity() == Severity::Error)
+    }
+
+    // 仅包含错误的问题列表
+    pub fn errors(&self) -> impl Iterator<Item = &MapIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+    }
+
+    // 仅包含警告的问题列表
+    pub fn warnings(&self) -> impl Iterator<Item = &MapIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Warning)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            let tag = match issue.severity() {
+                Severity::Error => "错误",
+                Severity::Warning => "警告",
+            };
+            writeln!(f, "[{tag}] {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    // 校验地图数据：传送点位置与目标、坐标范围、出生点重叠、宝箱 id 与连通性
+    pub fn validate(&self) -> ValidationReport {
+        let mut issues = Vec::new();
+
+        if !self.maps.contains_key(&self.current) {
+            issues.push(MapIssue::MissingStartMap {
+                start_map: self.current.clone(),
+            });
+        }
+        issues.extend(self.load_issues.iter().cloned());
+
+        // 没有 id 的传送点只能按序号引用，触发区域的封锁/解除封锁动作不应依赖这种键
+        let unnamed: HashSet<String> = self
+            .maps
+            .values()
+            .flat_map(|map| {
+                map.portals
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.id.is_none())
+                    .map(|(idx, p)| p.key(&map.id, idx))
+            })
+            .collect();
+        for map in self.sorted_maps() {
+            for (trigger, portal) in trigger_portal_refs(map) {
+                if unnamed.contains(portal) {
+                    issues.push(MapIssue::UnnamedPortalRef {
+                        portal: portal.to_string(),
+                        source: format!("地图 {} 触发区域 {trigger}", map.id),
+                    });
+                }
+            }
+        }
+
+        for map in self.sorted_maps() {
+            let spawn = Location::Spawn {
+                map: map.id.clone(),
+            };
+            check_position(&mut issues, map, spawn, map.spawn);
+
+            let mut chests = HashSet::new();
+            for chest in &map.chests {
+                if !chests.insert(chest.id.as_str()) {
+                    issues.push(MapIssue::DuplicateChestId {
+                        map: map.id.clone(),
+                        id: chest.id.clone(),
+                    });
+                }
+            }
+
+            // 回程传送点的落点就是来源传送点，不再检查
+            let portals = map.portals.iter().enumerate().filter(|(_, p)| !p.generated);
+            for (idx, portal) in portals {
+                let at = Location::Portal {
+                    map: map.id.clone(),
+                    portal: idx,
+                };
+                check_placement(&mut issues, map, at, portal.pos);
+                let Some(target) = self.maps.get(&portal.to_map) else {
+                    issues.push(MapIssue::DanglingPortal {
+                        map: map.id.clone(),
+                        portal: idx,
+                        to_map: portal.to_map.clone(),
+                    });
+                    continue;
+                };
+                let at = Location::PortalTarget {
+                    map: map.id.clone(),
+                    portal: idx,
+                };
+                check_position(&mut issues, target, at, portal.to_pos);
+            }
+
+            for (trigger, to_map, to_pos) in trigger_teleports(map) {
+                let Some(target) = self.maps.get(to_map) else {
+                    issues.push(MapIssue::DanglingTrigger {
+                        map: map.id.clone(),
+                        trigger: trigger.to_string(),
+                        to_map: to_map.to_string(),
+                    });
+                    continue;
+                };
+                let at = Location::TriggerTarget {
+                    map: map.id.clone(),
+                    trigger: trigger.to_string(),
+                };
+                check_position(&mut issues, target, at, to_pos);
+            }
+        }
+
+        // 从起始地图沿传送点与触发区域传送广度优先搜索，找出孤立地图
+        if self.maps.contains_key(&self.current) {
+            let mut seen = HashSet::new();
+            let mut queue = VecDeque::from([self.current.as_str()]);
+            seen.insert(self.current.as_str());
+            while let Some(id) = queue.pop_front() {
+                let map = &self.maps[id];
+                let targets = map
+                    .portals
+                    .iter()
+                    .map(|p| p.to_map.as_str())
+                    .chain(trigger_teleports(map).map(|(_, to_map, _)| to_map));
+                for to_map in targets {
+                    if self.maps.contains_key(to_map) && seen.insert(to_map) {
+                        queue.push_back(to_map);
+                    }
+                }
+            }
+            for map in self.sorted_maps() {
+                if !seen.contains(map.id.as_str()) {
+                    issues.push(MapIssue::UnreachableMap {
+                        map: map.id.clone(),
+                    });
+                }
+            }
+        }
+
+        ValidationReport { issues }
+    }
+
+    // 按 id 排序的地图列表，保证报告顺序稳定
+    fn sorted_maps(&self) -> Vec<&MapConfig> {
+        let mut maps: Vec<&MapConfig> = self.maps.values().collect();
+        maps.sort_by(|a, b| a.id.cmp(&b.id));
+        maps
+    }
+}
+
+// 地图中触发区域的传送动作：(触发区域 id, 目标地图, 目标坐标)
+fn trigger_teleports(map: &MapConfig) -> impl Iterator<Item = (&str, &str, Vec2Def)> {
+    map.triggers.iter().flat_map(|trigger| {
+        trigger
+            .actions
+            .iter()
+            .filter_map(move |action| match action {
+                TriggerAction::Teleport { to_map, to_pos, .. } => {
+                    Some((trigger.id.as_str(), to_map.as_str(), *to_pos))
+                }
+                _ => None,
+            })
+    })
+}
+
+// 地图中触发区域引用的传送点：(触发区域 id, 传送点键)
+pub fn trigger_portal_refs(map: &MapConfig) -> impl Iterator<Item = (&str, &str)> {
+    map.triggers.iter().flat_map(|trigger| {
+        trigger
+            .actions
+            .iter()
+            .filter_map(move |action| match action {
+                TriggerAction::LockPortal { portal } | TriggerAction::UnlockPortal { portal } => {
+                    Some((trigger.id.as_str(), portal.as_str()))
+                }
+                _ => None,
+            })
+    })
+}
+
+// 检查摆放位置：是否越界、是否在墙内，越界时返回 false
+fn check_placement(
+    issues: &mut Vec<MapIssue>,
+    map: &MapConfig,
+    at: Location,
+    pos: Vec2Def,
+) -> bool {
+    let pos = pos.to_vec2();
+    let size = map.world_size();
+    if pos.x < 0.0 || pos.y < 0.0 || pos.x > size.x || pos.y > size.y {
+        issues.push(MapIssue::OutOfBounds { at, pos });
+        return false;
+    }
+    if let Some(grid) = &map.tiles {
+        if grid.is_solid(grid.tile_coord(pos.x), grid.tile_coord(pos.y)) {
+            issues.push(MapIssue::BlockedByWall { at, pos });
+        }
+    }
+    true
+}
+
+// 检查落点：在 check_placement 的基础上检查是否与传送点重叠（会导致来回传送）；
+// 自动生成的回程传送点本就位于落点处，不算重叠
+fn check_position(issues: &mut Vec<MapIssue>, map: &MapConfig, at: Location, pos: Vec2Def) {
+    if !check_placement(issues, map, at.clone(), pos) {
+        return;
+    }
+    let pos = pos.to_vec2();
+    for (idx, portal) in map.portals.iter().enumerate() {
+        if !portal.generated && portal.contains(pos) {
+            issues.push(MapIssue::PortalOverlapsSpawn {
+                at: at.clone(),
+                map: map.id.clone(),
+                portal: idx,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::world::map::MapsConfig;
+
+    // 测试用地图：10×10 格、每格 32 像素，第 5 列为墙
+    fn map(id: &str, portals: Value) -> Value {
+        json!({
+            "id": id,
+            "name": id,
+            "spawn": { "x": 48.0, "y": 48.0 },
+            "portals": portals,
+            "bosses": [],
+            "tiles": {
+                "tile_size": 32.0,
+                "width": 10,
+                "height": 10,
+                "rows": vec!["....#....."; 10]
+            }
+        })
+    }
+
+    // 传送点：位于 pos，目标为 to_map 的 to_pos
+    fn portal(pos: (f32, f32), to_map: &str, to_pos: (f32, f32)) -> Value {
+        json!({
+            "pos": { "x": pos.0, "y": pos.1 },
+            "radius": 10.0,
+            "to_map": to_map,
+            "to_pos": { "x": to_pos.0, "y": to_pos.1 }
+        })
+    }
+
+    // 由若干地图构建世界并校验
+    fn validate(maps: Vec<Value>) -> ValidationReport {
+        let config: MapsConfig = serde_json::from_value(json!({
+            "start_map": "a",
+            "maps": maps
+        }))
+        .unwrap();
+        World::from_config(config).validate()
+    }
+
+    // 两张互通的地图
+    fn linked() -> Vec<Value> {
+        vec![
+            map("a", json!([portal((48.0, 200.0), "b", (48.0, 100.0))])),
+            map("b", json!([portal((48.0, 200.0), "a", (48.0, 100.0))])),
+        ]
+    }
+
+    #[test]
+    fn clean_world_has_no_issues() {
+        let report = validate(linked());
+        assert!(report.issues.is_empty(), "{report}");
+    }
+
+    #[test]
+    fn dangling_portal_is_an_error() {
+        let mut maps = linked();
+        maps[1]["portals"][0]["to_map"] = json!("nowhere");
+        let report = validate(maps);
+        assert!(report.has_errors());
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::DanglingPortal { map, portal: 0, to_map } if map == "b" && to_map == "nowhere"
+        )));
+    }
+
+    #[test]
+    fn out_of_bounds_target_and_spawn() {
+        let mut maps = linked();
+        maps[0]["portals"][0]["to_pos"] = json!({ "x": 48.0, "y": 900.0 });
+        maps[1]["spawn"] = json!({ "x": -5.0, "y": 48.0 });
+        let report = validate(maps);
+        assert!(!report.has_errors());
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::OutOfBounds { at: Location::PortalTarget { map, portal: 0 }, .. } if map == "a"
+        )));
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::OutOfBounds { at: Location::Spawn { map }, .. } if map == "b"
+        )));
+    }
+
+    #[test]
+    fn portal_in_a_solid_tile() {
+        let mut maps = linked();
+        maps[0]["portals"][0]["pos"] = json!({ "x": 144.0, "y": 200.0 });
+        let report = validate(maps);
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::BlockedByWall { at: Location::Portal { map, portal: 0 }, .. } if map == "a"
+        )));
+    }
+
+    #[test]
+    fn duplicate_map_ids() {
+        let mut maps = linked();
+        maps.push(map("b", json!([])));
+        let report = validate(maps);
+        assert!(report.has_errors());
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, MapIssue::DuplicateMapId { id, .. } if id == "b")));
+    }
+
+    #[test]
+    fn duplicate_chest_ids_within_a_map() {
+        let mut maps = linked();
+        let chest = json!({ "id": "chest", "pos": { "x": 48.0, "y": 48.0 } });
+        maps[0]["chests"] = json!([chest, chest]);
+        maps[1]["chests"] = json!([chest]);
+        let report = validate(maps);
+        assert!(!report.has_errors());
+        let duplicates: Vec<&str> = report
+            .issues
+            .iter()
+            .filter_map(|i| match i {
+                MapIssue::DuplicateChestId { map, .. } => Some(map.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(duplicates, ["a"]);
+    }
+
+    #[test]
+    fn unreachable_map() {
+        let mut maps = linked();
+        maps.push(map("c", json!([portal((48.0, 200.0), "a", (48.0, 100.0))])));
+        let report = validate(maps);
+        assert!(!report.has_errors());
+        let unreachable: Vec<&str> = report
+            .issues
+            .iter()
+            .filter_map(|i| match i {
+                MapIssue::UnreachableMap { map } => Some(map.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(unreachable, ["c"]);
+    }
+
+    // 地图上的触发区域：进入时执行 actions
+    fn trigger(id: &str, actions: Value) -> Value {
+        json!({
+            "id": id,
+            "shape": "circle",
+            "center": { "x": 48.0, "y": 300.0 },
+            "radius": 10.0,
+            "actions": actions
+        })
+    }
+
+    #[test]
+    fn missing_start_map_is_an_error() {
+        let config: MapsConfig = serde_json::from_value(json!({
+            "start_map": "nowhere",
+            "maps": linked()
+        }))
+        .unwrap();
+        let report = World::from_config(config).validate();
+        assert!(report.has_errors());
+        assert!(report.issues.iter().any(
+            |i| matches!(i, MapIssue::MissingStartMap { start_map } if start_map == "nowhere")
+        ));
+    }
+
+    #[test]
+    fn spawn_and_arrival_inside_a_portal() {
+        let mut maps = linked();
+        maps[0]["portals"][0]["pos"] = json!({ "x": 48.0, "y": 48.0 });
+        maps[1]["portals"][0]["pos"] = json!({ "x": 48.0, "y": 100.0 });
+        let report = validate(maps);
+        assert!(!report.has_errors());
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::PortalOverlapsSpawn { at: Location::Spawn { map: at }, map, portal: 0 }
+                if at == "a" && map == "a"
+        )));
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::PortalOverlapsSpawn { at: Location::PortalTarget { map: at, portal: 0 }, map, portal: 0 }
+                if at == "a" && map == "b"
+        )));
+    }
+
+    #[test]
+    fn dangling_trigger_teleport_is_an_error() {
+        let mut maps = linked();
+        let teleport = json!([{
+            "type": "teleport",
+            "to_map": "nowhere",
+            "to_pos": { "x": 48.0, "y": 48.0 }
+        }]);
+        maps[0]["triggers"] = json!([trigger("warp", teleport)]);
+        let report = validate(maps);
+        assert!(report.has_errors());
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            MapIssue::DanglingTrigger { map, trigger, to_map }
+                if map == "a" && trigger == "warp" && to_map == "nowhere"
+        )));
+    }
+
+    #[test]
+    fn trigger_referencing_an_unnamed_portal() {
+        let mut maps = linked();
+        maps[1]["portals"][0]["id"] = json!("b_gate");
+        let lock = |portal: &str| json!([{ "type": "lock_portal", "portal": portal }]);
+        maps[0]["triggers"] = json!([
+            trigger("by_index", lock("a#0")),
+            trigger("by_id", lock("b_gate"))
+        ]);
+        let report = validate(maps);
+        assert!(!report.has_errors());
+        let refs: Vec<&str> = report
+            .issues
+            .iter()
+            .filter_map(|i| match i {
+                MapIssue::UnnamedPortalRef { portal, .. } => Some(portal.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(refs, ["a#0"]);
+    }
+
+    #[test]
+    fn reports_every_issue_at_once() {
+        let mut maps = linked();
+        maps[0]["spawn"] = json!({ "x": 144.0, "y": 48.0 });
+        maps[0]["portals"][0]["to_pos"] = json!({ "x": 999.0, "y": 48.0 });
+        maps[1]["portals"][0]["to_map"] = json!("nowhere");
+        maps.push(map("c", json!([])));
+        let report = validate(maps);
+        let kinds: Vec<&str> = report
+            .issues
+            .iter()
+            .map(|i| match i {
+                MapIssue::BlockedByWall { .. } => "wall",
+                MapIssue::OutOfBounds { .. } => "bounds",
+                MapIssue::DanglingPortal { .. } => "dangling",
+                MapIssue::UnreachableMap { .. } => "unreachable",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["wall", "bounds", "dangling", "unreachable"]);
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().count(), 3);
+    }
+}
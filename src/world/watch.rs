@@ -0,0 +1,101 @@
+﻿use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// 轮询间隔（秒）：避免每帧都访问文件系统
+const POLL_INTERVAL: f32 = 0.5;
+
+// 数据文件监视器：按修改时间轮询判断是否需要重新加载
+pub struct FileWatcher {
+    path: String,
+    // 附加监视的目录（递归包含其中的文件，增删文件也会触发）
+    dirs: Vec<PathBuf>,
+    // 附加监视的单个文件（如 maps.json 引用的 Tiled 地图与图块集），随加载结果更新
+    files: Vec<PathBuf>,
+    last_modified: Option<SystemTime>,
+    last_dir_stamp: Vec<(PathBuf, Option<SystemTime>)>,
+    timer: f32,
+}
+
+impl FileWatcher {
+    // 创建监视器并记录当前修改时间
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            dirs: Vec::new(),
+            files: Vec::new(),
+            last_modified: modified_time(Path::new(path)),
+            last_dir_stamp: Vec::new(),
+            timer: 0.0,
+        }
+    }
+
+    // 追加监视一个目录
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self.last_dir_stamp = self.dir_stamp();
+        self
+    }
+
+    // 替换附加监视的文件列表并记录其当前修改时间
+    pub fn watch_files(&mut self, files: &[PathBuf]) {
+        self.files = files.to_vec();
+        self.last_dir_stamp = self.dir_stamp();
+    }
+
+    // 推进计时，文件或目录内容的修改时间变化时返回 true
+    pub fn poll(&mut self, dt: f32) -> bool {
+        self.timer += dt;
+        if self.timer < POLL_INTERVAL {
+            return false;
+        }
+        self.timer = 0.0;
+        let modified = modified_time(Path::new(&self.path));
+        if modified.is_none() {
+            return false;
+        }
+        let dir_stamp = self.dir_stamp();
+        if modified == self.last_modified && dir_stamp == self.last_dir_stamp {
+            return false;
+        }
+        self.last_modified = modified;
+        self.last_dir_stamp = dir_stamp;
+        true
+    }
+
+    // 监视目录下全部文件与附加文件的路径与修改时间（排序后便于比较）
+    fn dir_stamp(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut files = self.files.clone();
+        for dir in &self.dirs {
+            collect_files(dir, &mut files);
+        }
+        files.sort();
+        files
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect()
+    }
+}
+
+// 递归收集目录中的文件，目录不存在时跳过
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// 读取文件修改时间，文件暂时缺失（编辑器保存中）时返回 None
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
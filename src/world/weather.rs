@@ -0,0 +1,46 @@
+﻿use serde::{Deserialize, Serialize};
+
+use crate::world::clock::{in_schedule, DayPhase};
+use crate::world::map::is_default;
+
+// 地图天气层：种类、强度（粒子数量倍率）、风力（向右为正）与出现的时段
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WeatherConfig {
+    pub kind: WeatherKind,
+    #[serde(
+        default = "default_intensity",
+        skip_serializing_if = "is_default_intensity"
+    )]
+    pub intensity: f32,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub wind: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<DayPhase>,
+}
+
+// 默认强度
+fn default_intensity() -> f32 {
+    1.0
+}
+
+// 写回时省略默认强度
+fn is_default_intensity(intensity: &f32) -> bool {
+    *intensity == default_intensity()
+}
+
+impl WeatherConfig {
+    // 当前时段是否有这层天气
+    pub fn is_active(&self, phase: DayPhase) -> bool {
+        in_schedule(&self.schedule, phase)
+    }
+}
+
+// 天气种类
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherKind {
+    Rain,
+    Snow,
+    Mist,
+    Petals,
+}